@@ -0,0 +1,2 @@
+-- When the importer last refreshed a repository's issues.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS last_synced_at TIMESTAMPTZ;
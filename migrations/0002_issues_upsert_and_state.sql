@@ -0,0 +1,7 @@
+-- Lets re-imports upsert issues instead of duplicating them.
+CREATE UNIQUE INDEX IF NOT EXISTS issues_repository_id_number_key
+    ON issues (repository_id, number);
+
+-- Issues no longer open on GitHub are kept but marked closed.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS open BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS issue_closed_at TIMESTAMPTZ;
@@ -0,0 +1,9 @@
+-- Progress of HTTP imports, so one cut off by the Lambda timeout can be
+-- resumed with its token. Rows are deleted once the import completes.
+CREATE TABLE IF NOT EXISTS import_checkpoints (
+    token TEXT PRIMARY KEY,
+    payload TEXT NOT NULL,
+    state JSONB,
+    cursor JSONB,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,12 @@
+-- Messages of the import dead-letter queue given up on by the replay
+-- handler, kept for curators to inspect and fix.
+CREATE TABLE IF NOT EXISTS import_failures (
+    id SERIAL PRIMARY KEY,
+    message_id TEXT NOT NULL,
+    body TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT NOT NULL,
+    receive_count INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,18 @@
+-- Queue of asynchronous imports, claimed with `FOR UPDATE SKIP LOCKED` so
+-- concurrent invocations never run the same job.
+CREATE TABLE IF NOT EXISTS jobs (
+    id BIGSERIAL PRIMARY KEY,
+    -- SQS message id for queued messages, so redeliveries don't run twice.
+    dedupe_key TEXT UNIQUE,
+    payload TEXT NOT NULL,
+    state TEXT NOT NULL DEFAULT 'queued'
+        CHECK (state IN ('queued', 'running', 'done', 'failed')),
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    run_after TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS jobs_claimable_idx ON jobs (run_after, id)
+    WHERE state IN ('queued', 'running');
@@ -0,0 +1,8 @@
+-- Issue syncs owed to repositories imported while GitHub was unavailable.
+-- The scheduled re-sync refreshes pending repositories first.
+CREATE TABLE IF NOT EXISTS repo_sync_state (
+    repository_id INTEGER PRIMARY KEY REFERENCES repositories (id) ON DELETE CASCADE,
+    pending BOOLEAN NOT NULL DEFAULT TRUE,
+    reason TEXT,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
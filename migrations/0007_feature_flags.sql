@@ -0,0 +1,6 @@
+-- Feature flags toggled without a redeploy, overriding `FEATURE_FLAGS`.
+CREATE TABLE IF NOT EXISTS feature_flags (
+    name TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,4 @@
+-- Forge each repository is imported from, so re-syncs keep using the one
+-- its import was given or detected. NULL for repositories imported before,
+-- whose forge is detected from their url.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS forge TEXT;
@@ -0,0 +1,6 @@
+-- Progress of the scheduled re-sync: every repository it attempts gets a
+-- row, recording the last attempt and why it failed. Failed repositories
+-- wait until they're stale again instead of being retried by every run.
+ALTER TABLE repo_sync_state ADD COLUMN IF NOT EXISTS last_attempted_at TIMESTAMPTZ;
+ALTER TABLE repo_sync_state ADD COLUMN IF NOT EXISTS last_error TEXT;
+ALTER TABLE repo_sync_state ADD COLUMN IF NOT EXISTS consecutive_failures INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- S3 key of the detailed report of a done job, when reports are written.
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS report_key TEXT;
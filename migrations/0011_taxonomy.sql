@@ -0,0 +1,8 @@
+-- Values allowed in the attribute facets of projects, on top of the
+-- `TAXONOMY_*` settings. `facet` is `purposes`, `stack_levels`,
+-- `technologies` or `types`.
+CREATE TABLE IF NOT EXISTS taxonomy (
+    facet TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (facet, value)
+);
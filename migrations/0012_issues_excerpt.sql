@@ -0,0 +1,2 @@
+-- Plain-text excerpt of each issue's description, sanitized for the site.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS excerpt TEXT;
@@ -0,0 +1,4 @@
+-- Directories of a monorepo each repository's import is scoped to, so
+-- re-syncs keep importing only the issues labelled for them. Empty for
+-- repositories imported whole.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS paths TEXT[] NOT NULL DEFAULT '{}';
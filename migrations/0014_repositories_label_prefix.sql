@@ -0,0 +1,3 @@
+-- Prefix of the labels each repository's import is scoped to, along its
+-- paths. NULL for repositories imported whole or by path only.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS label_prefix TEXT;
@@ -0,0 +1,4 @@
+-- Whether each issue carries a label of BEGINNER_FRIENDLY_LABELS, so the
+-- site can filter on it. FALSE for issues imported before, until their
+-- repository is re-synced.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS is_beginner_friendly BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,14 @@
+-- Rules translating issue labels into Kudos categories, on top of the
+-- `LABEL_CATEGORIES` setting: labels matching `pattern` (lowercase, `*`
+-- standing for any characters) get the `category:value` tag, e.g.
+-- `difficulty:easy`.
+CREATE TABLE IF NOT EXISTS label_categories (
+    pattern TEXT NOT NULL,
+    category TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (pattern, category, value)
+);
+
+-- Category tags of each issue, kept along its raw labels. Empty for issues
+-- imported before, until their repository is re-synced.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS categories TEXT[] NOT NULL DEFAULT '{}';
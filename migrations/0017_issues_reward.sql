@@ -0,0 +1,6 @@
+-- Rewards of paid issues, told by their labels or an amount in their labels
+-- or body. `reward_amount` and `reward_currency` are NULL when no amount is
+-- given, or for issues imported before, until their repository is re-synced.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS is_paid BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS reward_amount DOUBLE PRECISION;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS reward_currency TEXT;
@@ -0,0 +1,5 @@
+-- Estimated difficulty of each issue, `easy`, `medium` or `hard`, with the
+-- signals it was estimated from, e.g. `label:e-easy` or `body:long`. NULL
+-- for issues imported before, until their repository is re-synced.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS difficulty TEXT;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS difficulty_signals TEXT[] NOT NULL DEFAULT '{}';
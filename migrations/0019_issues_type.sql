@@ -0,0 +1,4 @@
+-- Type of each issue, `bug`, `feature`, `docs`, `test` or `chore`, from its
+-- labels or title prefix. NULL when neither tells, and for issues imported
+-- before, until their repository is re-synced.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS issue_type TEXT;
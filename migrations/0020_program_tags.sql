@@ -0,0 +1,5 @@
+-- Programs (Hacktoberfest, GSoC, mentorship...) repositories and issues take
+-- part in, from their topics and labels. Issues imported before get theirs on
+-- their next re-sync, repositories imported before have none.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS program_tags TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS program_tags TEXT[] NOT NULL DEFAULT '{}';
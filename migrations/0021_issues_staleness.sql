@@ -0,0 +1,4 @@
+-- Staleness of each issue, from 0 for issues that just moved to 1 for ones
+-- untouched for a year and open for two, as of its last sync. NULL for issues
+-- imported before, until their repository is re-synced.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS staleness DOUBLE PRECISION;
@@ -0,0 +1,7 @@
+-- Curation flags set by the Kudos team through `PATCH /issues/{id}`: featured
+-- issues are listed first, hidden ones not at all. Re-syncs leave them as
+-- they are.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS featured BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS hidden BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS curator_note TEXT;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS curated_at TIMESTAMPTZ;
@@ -0,0 +1,4 @@
+-- Numbers of the issues a repository's payload entry excludes, from its
+-- `excludedIssueNumbers` and `excludedIssueUrls`, kept so re-syncs skip them
+-- too.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS excluded_issues BIGINT[] NOT NULL DEFAULT '{}';
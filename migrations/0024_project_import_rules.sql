@@ -0,0 +1,8 @@
+-- Import rules of each project, the payload's `filters`, saved when the
+-- project is created so re-syncs apply them too. Projects imported before have
+-- no row, and are re-synced without filters.
+CREATE TABLE IF NOT EXISTS project_import_rules (
+    project_id INTEGER PRIMARY KEY REFERENCES projects (id) ON DELETE CASCADE,
+    filters JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
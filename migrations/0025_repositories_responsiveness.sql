@@ -0,0 +1,9 @@
+-- How quickly maintainers reply to the repository's issues, measured on a
+-- sample of its recent ones at import and sync: the share first answered by
+-- a maintainer within a week, from 0 to 1, and the median hours until that
+-- first answer. NULL until measured, and on forges that don't tell
+-- maintainers apart.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS responsiveness DOUBLE PRECISION;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS median_first_response_hours DOUBLE PRECISION;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS responsiveness_sampled INTEGER;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS responsiveness_measured_at TIMESTAMPTZ;
@@ -0,0 +1,10 @@
+-- Health of each repository, refreshed on sync when the `health_scores` flag
+-- is on: from 0 to 1, out of its commits of the last 90 days, the share of
+-- its issues that are closed and how recent its last release is, stored
+-- along them. NULL until scored, and on forges other than GitHub.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS health_score DOUBLE PRECISION;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS recent_commits INTEGER;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS open_issues_count BIGINT;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS closed_issues_count BIGINT;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS last_release_at TIMESTAMPTZ;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS health_scored_at TIMESTAMPTZ;
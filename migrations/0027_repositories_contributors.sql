@@ -0,0 +1,7 @@
+-- Contributors of each repository, refreshed on sync when the
+-- `contributor_stats` flag is on: its top contributors by commits, as a JSON
+-- array of `{"login", "contributions"}`, and how many people committed to it
+-- over the last 90 days. NULL until fetched, and on forges other than GitHub.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS top_contributors JSONB;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS recent_contributors INTEGER;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS contributors_fetched_at TIMESTAMPTZ;
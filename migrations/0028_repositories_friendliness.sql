@@ -0,0 +1,11 @@
+-- How welcoming each repository is to first-time contributors, checked at
+-- import and on sync: whether it has a contributing guide, issue templates
+-- and beginner-friendly labels, the share of those it has, from 0 to 1, and
+-- whether that makes it friendly. NULL until checked, and on forges other
+-- than GitHub.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS has_contributing_guide BOOLEAN;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS has_issue_templates BOOLEAN;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS has_beginner_labels BOOLEAN;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS friendliness DOUBLE PRECISION;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS first_timer_friendly BOOLEAN;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS friendliness_checked_at TIMESTAMPTZ;
@@ -0,0 +1,10 @@
+-- Languages of each repository, refreshed on sync: the main one and the
+-- share of the code in each, as a JSON object of percentages such as
+-- `{"Rust": 92.4, "Shell": 7.6}`. NULL until fetched, and on forges that
+-- don't report languages.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS primary_language TEXT;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS languages JSONB;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS languages_fetched_at TIMESTAMPTZ;
+-- Projects imported without technologies get their repositories' main
+-- languages instead, and keep getting them while this is set.
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS technologies_from_languages BOOLEAN NOT NULL DEFAULT FALSE;
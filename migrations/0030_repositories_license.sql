@@ -0,0 +1,4 @@
+-- SPDX identifier of each repository's license, e.g. `MIT`, as its forge
+-- detected it at import. `NOASSERTION` for a license file GitHub doesn't
+-- recognize, NULL without a license and on forges that don't report them.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS license TEXT;
@@ -0,0 +1,16 @@
+-- Issues marked closed that show up open again on a later sync are counted
+-- as reopened, with an event each, so churny issues can be told apart.
+-- Issues left out by the filters for a while, then imported again, count
+-- too.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS reopen_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS last_reopened_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS issue_reopens (
+    id BIGSERIAL PRIMARY KEY,
+    issue_id INTEGER NOT NULL REFERENCES issues (id) ON DELETE CASCADE,
+    -- When the issue had been marked closed.
+    closed_at TIMESTAMPTZ,
+    reopened_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS issue_reopens_issue_id_idx ON issue_reopens (issue_id);
@@ -0,0 +1,21 @@
+-- When issues were opened and closed, as fetched by the closed issues
+-- backfill over a window, for time-to-close metrics. Kept apart from
+-- `issues`, which holds the importable ones.
+CREATE TABLE IF NOT EXISTS closed_issues (
+    repository_id INTEGER NOT NULL REFERENCES repositories (id) ON DELETE CASCADE,
+    number BIGINT NOT NULL,
+    issue_created_at TIMESTAMPTZ NOT NULL,
+    issue_closed_at TIMESTAMPTZ NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (repository_id, number)
+);
+
+-- Median time-to-close of each repository's backfilled issues.
+CREATE OR REPLACE VIEW repository_time_to_close AS
+SELECT repository_id,
+       COUNT(*) AS closed_issues,
+       percentile_cont(0.5) WITHIN GROUP (
+           ORDER BY EXTRACT(EPOCH FROM issue_closed_at - issue_created_at) / 3600
+       ) AS median_hours_to_close
+FROM closed_issues
+GROUP BY repository_id;
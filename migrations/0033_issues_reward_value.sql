@@ -0,0 +1,8 @@
+-- Rewards in a single currency, `REWARD_BASE_CURRENCY`, converted with the
+-- static rates of `REWARD_RATES`, so issues can be sorted by reward. NULL
+-- for rewards in currencies without a rate, and for issues imported before,
+-- until their repository is re-synced.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS reward_value DOUBLE PRECISION;
+
+CREATE INDEX IF NOT EXISTS issues_reward_value_idx
+    ON issues (reward_value DESC) WHERE reward_value IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Issues asking for outside help, by a label of `HELP_WANTED_LABELS` or a
+-- phrase of their body such as "PRs welcome", many repositories leaving
+-- their issues unlabeled. FALSE for issues imported before, until their
+-- repository is re-synced.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS seeking_contributors BOOLEAN NOT NULL DEFAULT FALSE;
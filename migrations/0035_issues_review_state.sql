@@ -0,0 +1,12 @@
+-- Review of imported issues before they're listed, see `src/review.rs`.
+-- Issues imported before were listed, so they're approved; new ones are
+-- approved or left pending by `REVIEW_AUTO_APPROVE`. `reviewed_at` is set
+-- by `PATCH /issues/{id}/review`, after which re-syncs leave the state as
+-- it is.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS review_state TEXT NOT NULL DEFAULT 'approved'
+    CHECK (review_state IN ('pending', 'approved', 'rejected'));
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS reviewed_at TIMESTAMPTZ;
+
+-- The review queue.
+CREATE INDEX IF NOT EXISTS issues_pending_review_idx ON issues (repository_id)
+    WHERE review_state = 'pending';
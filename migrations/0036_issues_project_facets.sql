@@ -0,0 +1,16 @@
+-- The technologies and purposes of each issue's project, copied onto the
+-- issue so the site's main queries, e.g. open Rust issues, filter on the
+-- `issues` table alone. Refreshed with the issue on every sync, and when
+-- the project gets technologies from its languages.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS technologies TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS purposes TEXT[] NOT NULL DEFAULT '{}';
+
+UPDATE issues i
+SET technologies = COALESCE(p.technologies, '{}'),
+    purposes = COALESCE(p.purposes, '{}')
+FROM repositories r
+JOIN projects p ON p.id = r.project_id
+WHERE r.id = i.repository_id;
+
+CREATE INDEX IF NOT EXISTS issues_technologies_idx ON issues USING GIN (technologies) WHERE open;
+CREATE INDEX IF NOT EXISTS issues_purposes_idx ON issues USING GIN (purposes) WHERE open;
@@ -0,0 +1,7 @@
+-- Issues cross-posted across a project's repositories, see
+-- `src/duplicates.rs`: `duplicate_of` is the earliest issue a duplicate
+-- repeats, NULL for the others, and duplicates aren't listed.
+-- `referenced_issues` are the lowercase urls of the issues an issue's body
+-- links to. Refreshed on every sync.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS referenced_issues TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS duplicate_of INTEGER REFERENCES issues (id) ON DELETE SET NULL;
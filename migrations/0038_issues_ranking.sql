@@ -0,0 +1,9 @@
+-- Ranking of issues for the Kudos feed, see `src/ranking.rs`: a score from
+-- 0 to 1 out of their recency, reactions, beginner-friendliness and
+-- repository health, computed on sync. NULL until ranked.
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS reactions INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE issues ADD COLUMN IF NOT EXISTS ranking_score DOUBLE PRECISION;
+
+-- The feed: listed issues, highest-ranked first.
+CREATE INDEX IF NOT EXISTS issues_ranking_idx ON issues (ranking_score DESC NULLS LAST)
+    WHERE open AND NOT hidden AND duplicate_of IS NULL AND review_state = 'approved';
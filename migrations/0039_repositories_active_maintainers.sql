@@ -0,0 +1,6 @@
+-- How many maintainers landed commits on each repository's default branch
+-- over the last 90 days, their committers or the authors of the merges
+-- GitHub committed, refreshed with the contributors when the
+-- `contributor_stats` flag is on. NULL until fetched, and on forges other
+-- than GitHub.
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS active_maintainers INTEGER;
@@ -0,0 +1,3 @@
+-- Where a repository's sync stopped over `REPOSITORY_TIME_BUDGET_SECONDS`,
+-- for the scheduled re-sync to continue from; cleared once a sync completes.
+ALTER TABLE repo_sync_state ADD COLUMN IF NOT EXISTS cursor JSONB;
@@ -0,0 +1,16 @@
+-- One row per repository url within a project, so retried imports reuse
+-- the row they inserted, see `insert_repository`, instead of importing the
+-- repository's issues again under a new one. Duplicates left by earlier
+-- retries are dropped, with their copies of the issues, keeping the first.
+DELETE FROM issues i
+USING repositories r
+WHERE i.repository_id = r.id
+  AND EXISTS (
+      SELECT 1 FROM repositories k
+      WHERE k.project_id = r.project_id AND k.url = r.url AND k.id < r.id
+  );
+DELETE FROM repositories r
+USING repositories k
+WHERE k.project_id = r.project_id AND k.url = r.url AND k.id < r.id;
+CREATE UNIQUE INDEX IF NOT EXISTS repositories_project_id_url_key
+    ON repositories (project_id, url);
@@ -0,0 +1,14 @@
+//! Shared AWS SDK configuration.
+
+use aws_config::{BehaviorVersion, SdkConfig};
+use tokio::sync::OnceCell;
+
+static SDK_CONFIG: OnceCell<SdkConfig> = OnceCell::const_new();
+
+/// Loads the SDK configuration from the Lambda environment once per
+/// execution environment and reuses it across invocations.
+pub async fn sdk_config() -> &'static SdkConfig {
+    SDK_CONFIG
+        .get_or_init(|| aws_config::load_defaults(BehaviorVersion::latest()))
+        .await
+}
@@ -0,0 +1,92 @@
+//! Backfill of closed issues: fetches the issues repositories closed within
+//! a window and stores when they were opened and closed, for time-to-close
+//! metrics computed in SQL (see the `repository_time_to_close` view). Run
+//! from the command line, with `--backfill-closed-days`.
+
+use chrono::{DateTime, Utc};
+use lambda_http::tracing::{error, info, warn};
+use serde::Serialize;
+
+use crate::error::ImportError;
+use crate::github::IssueSource;
+use crate::store::Store;
+
+/// An issue closed on its forge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedIssue {
+    pub number: i64,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+}
+
+/// One page of a repository's closed issues.
+#[derive(Debug)]
+pub struct ClosedIssuePage {
+    /// Issues of the page closed within the window, pull requests excluded.
+    pub issues: Vec<ClosedIssue>,
+    pub has_next: bool,
+}
+
+/// Outcome of a backfill.
+#[derive(Debug, Default, Serialize)]
+pub struct BackfillSummary {
+    pub repositories_backfilled: usize,
+    /// Repositories on forges closed issues aren't fetched from.
+    pub repositories_skipped: usize,
+    pub repositories_failed: usize,
+    pub closed_issues: u64,
+}
+
+/// Backfills the issues closed since `since` by the repositories of the
+/// project, or of every project. A repository that fails is logged and
+/// counted, the others are still backfilled.
+pub async fn backfill_closed_issues(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    project_id: Option<i32>,
+    since: DateTime<Utc>,
+) -> Result<BackfillSummary, ImportError> {
+    let mut summary = BackfillSummary::default();
+    for repo in store.repositories(project_id).await? {
+        let repo_info = match repo.repo_info() {
+            Ok(repo_info) => repo_info,
+            Err(e) => {
+                error!(repo = %repo.url, error = %e, "Couldn't extract repo info from stored url");
+                summary.repositories_failed += 1;
+                continue;
+            }
+        };
+
+        let mut closed_issues = 0;
+        let mut page = 1;
+        let result = loop {
+            let fetched = match source.closed_issues(&repo_info, since, page).await {
+                Ok(Some(fetched)) => fetched,
+                Ok(None) => break Ok(false),
+                Err(e) => break Err(e),
+            };
+            match store.upsert_closed_issues(repo.id, &fetched.issues).await {
+                Ok(upserted) => closed_issues += upserted,
+                Err(e) => break Err(e),
+            }
+            if !fetched.has_next {
+                break Ok(true);
+            }
+            page += 1;
+        };
+
+        summary.closed_issues += closed_issues;
+        match result {
+            Ok(true) => {
+                info!(repo = %repo.url, closed_issues, "Backfilled closed issues");
+                summary.repositories_backfilled += 1;
+            }
+            Ok(false) => summary.repositories_skipped += 1,
+            Err(e) => {
+                warn!(repo = %repo.url, error = %e, "Couldn't backfill closed issues");
+                summary.repositories_failed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
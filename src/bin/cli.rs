@@ -0,0 +1,140 @@
+//! Runs the import pipeline from the command line, for curators and CI
+//! seeding without going through the Lambda, and the closed issues backfill
+//! (see [`backfill`]).
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use chrono::{Duration, Utc};
+use clap::Parser;
+use gh_import_issues::{
+    backfill,
+    config::{self, Config},
+    forge::ForgeRegistry,
+    import,
+    store::{PgStore, Store},
+    validation::{parse_project, PayloadError},
+};
+use lambda_http::Error;
+
+#[derive(Parser, Debug)]
+#[command(about = "Import Kudos project definitions and their GitHub issues")]
+struct Args {
+    /// Project JSON files, or directories of them.
+    #[arg(required_unless_present = "backfill_closed_days")]
+    paths: Vec<PathBuf>,
+
+    /// Falls back to `DATABASE_URL`, or the `DATABASE_URL_SECRET_ARN` secret.
+    #[arg(long)]
+    database_url: Option<String>,
+
+    /// Falls back to `GITHUB_TOKEN`, or the `GITHUB_TOKEN_SECRET_ARN` secret.
+    #[arg(long)]
+    github_token: Option<String>,
+
+    /// Reject unknown payload fields.
+    #[arg(long)]
+    strict: bool,
+
+    /// Instead of importing, backfill the issues closed over this many
+    /// last days by already imported repositories.
+    #[arg(long, value_name = "DAYS", conflicts_with = "paths")]
+    backfill_closed_days: Option<u32>,
+
+    /// Slug of the project to backfill, instead of every project.
+    #[arg(long, requires = "backfill_closed_days", conflicts_with = "paths")]
+    project: Option<String>,
+}
+
+/// Expands directories into the `.json` files they contain, sorted by name.
+fn project_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.retain(|entry| entry.extension().is_some_and(|ext| ext == "json"));
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+async fn import_file(
+    path: &Path,
+    store: &PgStore,
+    source: &ForgeRegistry,
+    strict: bool,
+) -> Result<(), Error> {
+    let json = fs::read_to_string(path)?;
+    let project = match parse_project(&json, strict) {
+        Ok(project) => project,
+        Err(PayloadError::Syntax(e)) => return Err(format!("not valid JSON: {}", e).into()),
+        Err(PayloadError::Invalid(errors)) => {
+            let details = errors
+                .iter()
+                .map(|e| format!("\n  {}: {}", e.pointer, e.message))
+                .collect::<String>();
+            return Err(format!("invalid project payload:{}", details).into());
+        }
+    };
+
+    let result = import::import_project(store, source, project).await?;
+    println!("{}", serde_json::to_string(&result)?);
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, Error> {
+    let args = Args::parse();
+
+    // Flags take precedence over the env vars of the same settings.
+    let overrides = [
+        ("DATABASE_URL", args.database_url),
+        ("GITHUB_TOKEN", args.github_token),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((name.to_string(), value?)));
+    config::init(Config::from_vars(env::vars().chain(overrides))?);
+
+    let store = PgStore::connect().await?;
+    let source = ForgeRegistry::from_config().await?;
+
+    if let Some(days) = args.backfill_closed_days {
+        let project_id = match &args.project {
+            Some(slug) => match store.find_project_id(slug).await? {
+                Some(project_id) => Some(project_id),
+                None => return Err(format!("project `{}` not found", slug).into()),
+            },
+            None => None,
+        };
+        let since = Utc::now() - Duration::days(days.into());
+        let summary = backfill::backfill_closed_issues(&store, &source, project_id, since).await?;
+        println!("{}", serde_json::to_string(&summary)?);
+        return Ok(if summary.repositories_failed == 0 {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    let mut failed = 0;
+    for path in project_files(&args.paths)? {
+        if let Err(e) = import_file(&path, &store, &source, args.strict).await {
+            eprintln!("{}: {}", path.display(), e);
+            failed += 1;
+        }
+    }
+
+    Ok(if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
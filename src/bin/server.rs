@@ -0,0 +1,12 @@
+//! Local development server, see [`gh_import_issues::server`].
+
+use gh_import_issues::{config, panics, server, telemetry};
+use lambda_http::Error;
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let config = config::load()?;
+    telemetry::init();
+    panics::install_hook();
+    server::serve(&config.local_server_addr).await
+}
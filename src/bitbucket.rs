@@ -0,0 +1,220 @@
+//! Access to the Bitbucket Cloud REST API (2.0), for repositories on
+//! `bitbucket.org`.
+//!
+//! Bitbucket issues have no free-form labels. Their `kind` (bug,
+//! enhancement, proposal, task) and `component` are what curators label them
+//! by, so they're imported as the issue's labels.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::breaker::{CircuitBreaker, Upstream};
+use crate::error::ImportError;
+use crate::forge::{self, Forge};
+use crate::github::{IssuePage, IssueSource, RepositoryMetadata, RepositoryStatus};
+use crate::model::{ForgeKind, KudosIssue, RepoInfo};
+use crate::review::ReviewState;
+use crate::secrets;
+
+const FORGE: &str = "Bitbucket";
+
+const API_URL: &str = "https://api.bitbucket.org/2.0";
+
+/// Largest page Bitbucket returns for issues.
+const PAGE_LEN: u32 = 50;
+
+/// Bitbucket's open states, `new` being an open issue nobody triaged yet.
+const OPEN_STATES_QUERY: &str = r#"state = "new" OR state = "open""#;
+
+/// A page of a Bitbucket listing, `next` being the url of the next one.
+#[derive(Debug, Deserialize)]
+struct BitbucketPage<T> {
+    values: Vec<T>,
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketUser {
+    nickname: Option<String>,
+    display_name: Option<String>,
+}
+
+impl BitbucketUser {
+    fn login(self) -> Option<String> {
+        self.nickname.or(self.display_name)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketName {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketLink {
+    href: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketLinks {
+    html: BitbucketLink,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketContent {
+    raw: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketIssue {
+    id: i64,
+    title: String,
+    links: BitbucketLinks,
+    created_on: DateTime<Utc>,
+    updated_on: Option<DateTime<Utc>>,
+    /// `None` for issues reported anonymously.
+    reporter: Option<BitbucketUser>,
+    assignee: Option<BitbucketUser>,
+    kind: Option<String>,
+    component: Option<BitbucketName>,
+    content: Option<BitbucketContent>,
+}
+
+impl From<BitbucketIssue> for KudosIssue {
+    fn from(value: BitbucketIssue) -> Self {
+        KudosIssue {
+            number: value.id,
+            title: value.title,
+            html_url: value.links.html.href,
+            issue_created_at: value.created_on,
+            issue_updated_at: value.updated_on.unwrap_or(value.created_on),
+            user: value
+                .reporter
+                .and_then(BitbucketUser::login)
+                .unwrap_or_else(|| "anonymous".to_string()),
+            labels: value
+                .kind
+                .into_iter()
+                .chain(value.component.map(|component| component.name))
+                .collect(),
+            assignees: value
+                .assignee
+                .and_then(BitbucketUser::login)
+                .into_iter()
+                .collect(),
+            body: value.content.and_then(|content| content.raw),
+            excerpt: None,
+            is_beginner_friendly: false,
+            categories: Vec::new(),
+            is_paid: false,
+            reward: None,
+            difficulty: None,
+            issue_type: None,
+            program_tags: Vec::new(),
+            staleness: None,
+            seeking_contributors: false,
+            review_state: ReviewState::Pending,
+            referenced_issues: Vec::new(),
+            reactions: 0,
+            ranking_score: None,
+        }
+    }
+}
+
+/// [`IssueSource`] backed by the Bitbucket Cloud REST API.
+pub struct BitbucketSource {
+    client: reqwest::Client,
+    token: Option<String>,
+    breaker: CircuitBreaker,
+}
+
+impl BitbucketSource {
+    pub fn new(client: reqwest::Client, token: Option<String>) -> Self {
+        BitbucketSource {
+            client,
+            token,
+            breaker: CircuitBreaker::new(Upstream::Forge(FORGE)),
+        }
+    }
+
+    /// Builds a client authenticated with the Bitbucket token when there's
+    /// one.
+    pub async fn from_config() -> Result<Self, ImportError> {
+        let token = secrets::bitbucket_token().await?;
+        Ok(BitbucketSource::new(forge::client()?, token))
+    }
+
+    /// The API url of the repository, the owner being its workspace.
+    fn repository_url(repo_info: &RepoInfo) -> String {
+        format!(
+            "{}/repositories/{}/{}",
+            API_URL, repo_info.owner, repo_info.name
+        )
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+impl Forge for BitbucketSource {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Bitbucket
+    }
+}
+
+#[async_trait]
+impl IssueSource for BitbucketSource {
+    /// Bitbucket Cloud has no archived repositories, only ones without an
+    /// issue tracker, which simply have no issues to import.
+    async fn repository_status(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<RepositoryStatus, ImportError> {
+        self.breaker
+            .call(async {
+                let url = BitbucketSource::repository_url(repo_info);
+                match forge::send(FORGE, self.get(&url)).await {
+                    Ok(_) => Ok(RepositoryStatus::Active(RepositoryMetadata::default())),
+                    Err(e) if forge::is_not_found(&e) => Ok(RepositoryStatus::NotFound),
+                    Err(e) => Err(e),
+                }
+            })
+            .await
+    }
+
+    /// Bitbucket pages carry the url of the next one; issue listings also
+    /// accept page numbers, which is what lets a sync resume from a cursor.
+    async fn open_issues(&self, repo_info: &RepoInfo, page: u32) -> Result<IssuePage, ImportError> {
+        self.breaker
+            .call(async {
+                let url = format!("{}/issues", BitbucketSource::repository_url(repo_info));
+                let request = self.get(&url).query(&[
+                    ("q", OPEN_STATES_QUERY.to_string()),
+                    ("pagelen", PAGE_LEN.to_string()),
+                    ("page", page.to_string()),
+                ]);
+                let page: BitbucketPage<BitbucketIssue> = match forge::send(FORGE, request).await {
+                    Ok(response) => forge::json(FORGE, response).await?,
+                    // The repository exists but its issue tracker is disabled.
+                    Err(e) if forge::is_not_found(&e) => BitbucketPage {
+                        values: Vec::new(),
+                        next: None,
+                    },
+                    Err(e) => return Err(e),
+                };
+
+                Ok(IssuePage {
+                    has_next: page.next.is_some(),
+                    issues: page.values.into_iter().map(KudosIssue::from).collect(),
+                    pull_requests: 0,
+                })
+            })
+            .await
+    }
+}
@@ -0,0 +1,91 @@
+//! Circuit breakers around the importer's dependencies. Once a dependency
+//! failed too many times in a row, further calls fail right away with
+//! [`ImportError::Unavailable`] instead of each waiting for its own timeout,
+//! so the rest of the invocation's work is cut short.
+//!
+//! Breakers belong to a [`PgStore`](crate::store::PgStore) or a forge, e.g.
+//! [`GitHubSource`](crate::github::GitHubSource), which are created per
+//! invocation, so a breaker never outlives the invocation that opened it.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use lambda_http::tracing::warn;
+
+use crate::config;
+use crate::error::ImportError;
+use crate::xray;
+
+/// A dependency guarded by a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Upstream {
+    GitHub,
+    Postgres,
+    /// Another code host, by its forge name.
+    Forge(&'static str),
+}
+
+impl fmt::Display for Upstream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Upstream::GitHub => "GitHub",
+            Upstream::Postgres => "Postgres",
+            Upstream::Forge(name) => name,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    upstream: Upstream,
+    threshold: u32,
+    consecutive_failures: AtomicU32,
+}
+
+impl CircuitBreaker {
+    /// A breaker opening after `BREAKER_FAILURE_THRESHOLD` failures in a row.
+    pub fn new(upstream: Upstream) -> Self {
+        CircuitBreaker {
+            upstream,
+            threshold: config::get().breaker_failure_threshold,
+            consecutive_failures: AtomicU32::new(0),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.consecutive_failures.load(Ordering::Relaxed) >= self.threshold
+    }
+
+    /// Runs `call` unless the breaker is open, counting whether it failed
+    /// because the upstream did. Other errors, e.g. a constraint violation,
+    /// say nothing about the upstream's health and leave the count as is.
+    ///
+    /// Every call is also an X-Ray subsegment, see [`xray`].
+    pub async fn call<T>(
+        &self,
+        call: impl Future<Output = Result<T, ImportError>>,
+    ) -> Result<T, ImportError> {
+        if self.is_open() {
+            return Err(ImportError::Unavailable(self.upstream));
+        }
+
+        let result = xray::subsegment(self.upstream, call).await;
+        match &result {
+            Ok(_) => self.consecutive_failures.store(0, Ordering::Relaxed),
+            Err(e) if e.is_upstream_failure() => {
+                let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+                if failures == self.threshold {
+                    warn!(
+                        upstream = %self.upstream,
+                        failures,
+                        "Circuit breaker opened, skipping further calls: {}",
+                        e
+                    );
+                }
+            }
+            Err(_) => {}
+        }
+        result
+    }
+}
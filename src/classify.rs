@@ -0,0 +1,903 @@
+//! Heuristics deriving facets of an issue from its labels, so the site can
+//! filter on them without knowing every project's label conventions.
+//!
+//! Labels are matched once sanitized, lowercase, against patterns where `*`
+//! stands for any run of characters, e.g. `good first*`.
+//!
+//! Labels are also translated into Kudos categories, `category:value` tags
+//! such as `difficulty:easy` or `area:runtime`, by the rules listed in
+//! `LABEL_CATEGORIES` and the `label_categories` table. Like the taxonomy's,
+//! table rows are cached for `TAXONOMY_CACHE_SECONDS`.
+//!
+//! Paid issues are told by a label of `REWARD_LABELS` or an amount, e.g.
+//! `$500` or `200 USDC`, in a label or a line of the body mentioning a
+//! bounty or a reward. Amounts are converted to `REWARD_BASE_CURRENCY` with
+//! the static rates of `REWARD_RATES`, so rewards can be sorted by value.
+//!
+//! Difficulty is estimated from the labels saying it, or else from keywords
+//! of the title and the length of the body, keeping the signals it was
+//! estimated from so the estimate can be explained and tuned.
+//!
+//! Issues are typed as bugs, features, docs, tests or chores from their
+//! labels, or else from the conventional prefix of their title, e.g.
+//! `fix:` or `docs(api):`.
+//!
+//! Issues and repositories taking part in a program, e.g. Hacktoberfest or
+//! Google Summer of Code, are tagged with it from their labels or topics,
+//! by the rules of `PROGRAM_LABELS`. An issue carries its repository's
+//! programs too.
+//!
+//! Issues are seeking contributors when a label of `HELP_WANTED_LABELS`
+//! says so, or a phrase of their body such as "PRs welcome" or "mentor
+//! available", many repositories leaving their issues unlabeled.
+//!
+//! Staleness scores, from 0 for issues that just moved to 1 for ones
+//! untouched for a year and open for two, how long an issue has gone
+//! without activity, forges' update dates moving with every comment, and
+//! how long it has been open.
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use lambda_http::tracing::warn;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::config;
+use crate::store::Store;
+
+/// Currency codes amounts are recognized in, lowercase, besides the `$`, `€`
+/// and `£` signs.
+const CURRENCIES: &[&str] = &[
+    "usd", "usdc", "usdt", "dai", "eur", "gbp", "dot", "ksm", "eth", "btc", "sol",
+];
+
+/// Words marking a line of an issue's body as describing its reward.
+const REWARD_WORDS: &[&str] = &["bounty", "reward"];
+
+/// Phrases of an issue's body, lowercase, asking for outside help.
+const HELP_PHRASES: &[&str] = &[
+    "prs welcome",
+    "prs are welcome",
+    "pr welcome",
+    "pull requests welcome",
+    "pull requests are welcome",
+    "contributions welcome",
+    "contributions are welcome",
+    "help wanted",
+    "help welcome",
+    "help is welcome",
+    "looking for contributors",
+    "looking for help",
+    "up for grabs",
+    "mentor available",
+    "mentoring available",
+    "happy to mentor",
+    "willing to mentor",
+];
+
+/// Label patterns saying an issue is of medium or hard difficulty, the easy
+/// ones being `BEGINNER_FRIENDLY_LABELS`.
+const MEDIUM_LABELS: &[&str] = &["*medium*", "*intermediate*", "*moderate*"];
+const HARD_LABELS: &[&str] = &["*hard*", "*difficult*", "*expert*", "*complex*"];
+
+/// Words of titles hinting at small or large changes.
+const EASY_WORDS: &[&str] = &[
+    "typo",
+    "typos",
+    "spelling",
+    "docs",
+    "documentation",
+    "readme",
+    "comment",
+    "comments",
+    "rename",
+    "lint",
+    "clippy",
+];
+const HARD_WORDS: &[&str] = &[
+    "refactor",
+    "redesign",
+    "rewrite",
+    "architecture",
+    "performance",
+    "security",
+    "concurrency",
+    "race",
+    "deadlock",
+    "migration",
+    "consensus",
+    "protocol",
+];
+
+/// Bodies shorter or longer than this, in characters, hint at small or
+/// large changes.
+const SHORT_BODY_CHARS: usize = 300;
+const LONG_BODY_CHARS: usize = 3000;
+
+/// Label patterns of each issue type, first matching type first. Short
+/// words only match whole or after a prefix, not to take `latest` for a
+/// test label.
+const TYPE_LABELS: &[(IssueType, &[&str])] = &[
+    (
+        IssueType::Bug,
+        &[
+            "bug*",
+            "*-bug*",
+            "* bug*",
+            "*defect*",
+            "*regression*",
+            "*crash*",
+        ],
+    ),
+    (
+        IssueType::Feature,
+        &["*feature*", "*enhancement*", "*improvement*", "feat*"],
+    ),
+    (
+        IssueType::Docs,
+        &["doc", "docs", "*-doc", "*-docs", "*documentation*"],
+    ),
+    (
+        IssueType::Test,
+        &["test", "tests", "*-test", "*-tests", "*testing*"],
+    ),
+    (
+        IssueType::Chore,
+        &[
+            "*chore*",
+            "*maintenance*",
+            "*refactor*",
+            "*tooling*",
+            "*dependencies*",
+            "ci",
+            "*-ci",
+        ],
+    ),
+];
+
+/// Conventional commit types of titles, e.g. `fix` in `fix(parser): …`.
+const TYPE_PREFIXES: &[(&str, IssueType)] = &[
+    ("fix", IssueType::Bug),
+    ("bug", IssueType::Bug),
+    ("feat", IssueType::Feature),
+    ("feature", IssueType::Feature),
+    ("docs", IssueType::Docs),
+    ("doc", IssueType::Docs),
+    ("test", IssueType::Test),
+    ("tests", IssueType::Test),
+    ("chore", IssueType::Chore),
+    ("ci", IssueType::Chore),
+    ("build", IssueType::Chore),
+    ("refactor", IssueType::Chore),
+    ("style", IssueType::Chore),
+];
+
+/// Days without activity, and days open, making an issue fully stale.
+const INACTIVE_DAYS: f64 = 365.0;
+const OPEN_DAYS: f64 = 730.0;
+
+/// Weight of inactivity in the staleness score, the rest being the time
+/// the issue has been open.
+const INACTIVITY_WEIGHT: f64 = 0.7;
+
+/// Database rules, with when they were read.
+static RULES: Mutex<Option<(Vec<CategoryRule>, Instant)>> = Mutex::const_new(None);
+
+/// Maps the labels matching `pattern` to the `category:value` tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryRule {
+    pub pattern: String,
+    pub tag: String,
+}
+
+/// Parses a `LABEL_CATEGORIES` entry, `pattern=category:value`.
+pub fn parse_category(entry: &str) -> Result<CategoryRule, String> {
+    let rule = entry
+        .rsplit_once('=')
+        .and_then(|(pattern, tag)| category_rule(pattern, tag));
+    rule.ok_or_else(|| {
+        format!(
+            "LABEL_CATEGORIES has an invalid entry `{}`, expected `pattern=category:value`",
+            entry
+        )
+    })
+}
+
+/// A rule from its label pattern and tag, `None` if either is malformed.
+fn category_rule(pattern: &str, tag: &str) -> Option<CategoryRule> {
+    let (category, value) = tag.split_once(':')?;
+    let (pattern, category, value) = (pattern.trim(), category.trim(), value.trim());
+    if pattern.is_empty() || category.is_empty() || value.is_empty() {
+        return None;
+    }
+    Some(CategoryRule {
+        pattern: pattern.to_lowercase(),
+        tag: format!("{}:{}", category.to_lowercase(), value.to_lowercase()),
+    })
+}
+
+async fn stored_rules(store: &dyn Store) -> Vec<CategoryRule> {
+    let ttl = Duration::from_secs(config::get().taxonomy_cache_seconds);
+    let mut cache = RULES.lock().await;
+    if let Some((rules, read_at)) = cache.as_ref() {
+        if read_at.elapsed() < ttl {
+            return rules.clone();
+        }
+    }
+
+    // Keep the last known rules on failure, issues are re-classified on
+    // their next sync anyway.
+    let rules = match store.label_categories().await {
+        Ok(rows) => rows
+            .into_iter()
+            .filter_map(|(pattern, category, value)| {
+                let rule = category_rule(&pattern, &format!("{}:{}", category, value));
+                if rule.is_none() {
+                    warn!(pattern, category, value, "Ignoring invalid label category");
+                }
+                rule
+            })
+            .collect(),
+        Err(e) => {
+            warn!(
+                "Couldn't read the label categories, using the previous ones: {}",
+                e
+            );
+            cache
+                .as_ref()
+                .map(|(rules, _)| rules.clone())
+                .unwrap_or_default()
+        }
+    };
+    *cache = Some((rules.clone(), Instant::now()));
+    rules
+}
+
+/// The rules issues are categorized by: the configured ones, then the
+/// database's.
+pub async fn category_rules(store: &dyn Store) -> Vec<CategoryRule> {
+    let mut rules: Vec<CategoryRule> = config::get()
+        .label_categories
+        .iter()
+        .filter_map(|entry| parse_category(entry).ok())
+        .collect();
+    rules.extend(stored_rules(store).await);
+    rules
+}
+
+/// The category tags of an issue with these labels, sorted.
+pub fn categories(labels: &[String], rules: &[CategoryRule]) -> Vec<String> {
+    let mut tags: Vec<String> = rules
+        .iter()
+        .filter(|rule| {
+            labels
+                .iter()
+                .any(|label| matches_pattern(label, &rule.pattern))
+        })
+        .map(|rule| rule.tag.clone())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Parses a `PROGRAM_LABELS` entry, `pattern=program`, into the pattern and
+/// the program, both lowercase.
+pub fn parse_program(entry: &str) -> Result<(String, String), String> {
+    match entry.rsplit_once('=') {
+        Some((pattern, program)) if !pattern.trim().is_empty() && !program.trim().is_empty() => {
+            Ok((pattern.trim().to_lowercase(), program.trim().to_lowercase()))
+        }
+        _ => Err(format!(
+            "PROGRAM_LABELS has an invalid entry `{}`, expected `pattern=program`",
+            entry
+        )),
+    }
+}
+
+/// The programs named by these labels or topics, sorted.
+pub fn programs(values: &[String]) -> Vec<String> {
+    let rules: Vec<(String, String)> = config::get()
+        .program_labels
+        .iter()
+        .filter_map(|entry| parse_program(entry).ok())
+        .collect();
+    program_tags(values, &rules)
+}
+
+fn program_tags(values: &[String], rules: &[(String, String)]) -> Vec<String> {
+    let mut programs: Vec<String> = rules
+        .iter()
+        .filter(|(pattern, _)| values.iter().any(|value| matches_pattern(value, pattern)))
+        .map(|(_, program)| program.clone())
+        .collect();
+    programs.sort();
+    programs.dedup();
+    programs
+}
+
+/// The reward offered for an issue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reward {
+    pub amount: f64,
+    /// Uppercase code, e.g. `USD` for `$` amounts.
+    pub currency: String,
+    /// The amount in `REWARD_BASE_CURRENCY`, `None` without a rate for the
+    /// currency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+}
+
+/// Parses a `REWARD_RATES` entry into the uppercase currency code and the
+/// value of one unit of it in the base currency.
+pub fn parse_rate(entry: &str) -> Result<(String, f64), String> {
+    let invalid = || {
+        format!(
+            "REWARD_RATES has an invalid entry `{}`, expected `CURRENCY=rate`",
+            entry
+        )
+    };
+    let (currency, rate) = entry.split_once('=').ok_or_else(invalid)?;
+    let currency = currency.trim();
+    if currency.is_empty() || !currency.chars().all(char::is_alphanumeric) {
+        return Err(invalid());
+    }
+    match rate.trim().parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok((currency.to_uppercase(), rate)),
+        _ => Err(invalid()),
+    }
+}
+
+/// The value of `amount` of `currency` in `base`, with `rates` (see
+/// [`parse_rate`]), rounded to two decimals.
+pub fn reward_value(
+    amount: f64,
+    currency: &str,
+    base: &str,
+    rates: &[(String, f64)],
+) -> Option<f64> {
+    let rate = if currency == base {
+        1.0
+    } else {
+        rates
+            .iter()
+            .find(|(known, _)| known == currency)
+            .map(|(_, rate)| *rate)?
+    };
+    Some((amount * rate * 100.0).round() / 100.0)
+}
+
+/// [`reward_value`] with the configured rates.
+pub fn configured_reward_value(reward: &Reward) -> Option<f64> {
+    let config = config::get();
+    let rates: Vec<(String, f64)> = config
+        .reward_rates
+        .iter()
+        .filter_map(|entry| parse_rate(entry).ok())
+        .collect();
+    reward_value(
+        reward.amount,
+        &reward.currency,
+        &config.reward_base_currency,
+        &rates,
+    )
+}
+
+/// Whether an issue with these labels is marked as paid by one of them,
+/// matching `REWARD_LABELS`.
+pub fn is_paid(labels: &[String]) -> bool {
+    any_matches(labels, &config::get().reward_labels)
+}
+
+/// The reward of an issue: the first amount found in its labels, or else in
+/// the lines of its body mentioning a bounty or a reward.
+pub fn reward(labels: &[String], body: Option<&str>) -> Option<Reward> {
+    labels.iter().find_map(|label| amount(label)).or_else(|| {
+        body?
+            .lines()
+            .filter(|line| {
+                let line = line.to_lowercase();
+                REWARD_WORDS.iter().any(|word| line.contains(word))
+            })
+            .find_map(amount)
+    })
+}
+
+/// The first amount of `text` with a currency, e.g. `$500`, `$1.5k`,
+/// `200 USDC` or `€50`. Numbers without one aren't taken as amounts, as they
+/// may count anything else.
+fn amount(text: &str) -> Option<Reward> {
+    let mut search = 0;
+    while let Some(found) = text[search..].find(|c: char| c.is_ascii_digit()) {
+        let start = search + found;
+        let len = text[start..]
+            .find(|c: char| !(c.is_ascii_digit() || c == ',' || c == '.'))
+            .unwrap_or(text.len() - start);
+        search = start + len;
+        let number = text[start..start + len].trim_end_matches(['.', ',']);
+
+        // Numbers within words, e.g. `v2` or `#12`, aren't amounts.
+        let before = text[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '#' || c == '_') {
+            continue;
+        }
+        let Ok(mut value) = number.replace(',', "").parse::<f64>() else {
+            continue;
+        };
+        let mut rest = &text[start + number.len()..];
+        if let Some(thousands) = rest.strip_prefix(['k', 'K']) {
+            if !thousands.starts_with(char::is_alphanumeric) {
+                value *= 1000.0;
+                rest = thousands;
+            }
+        }
+
+        let code: String = rest
+            .trim_start()
+            .chars()
+            .take_while(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        let sign = match before {
+            Some('$') => Some("usd"),
+            Some('€') => Some("eur"),
+            Some('£') => Some("gbp"),
+            _ => None,
+        };
+        // A code wins over the sign, e.g. `$500 USDC`.
+        let currency = CURRENCIES
+            .iter()
+            .find(|known| **known == code)
+            .copied()
+            .or(sign);
+        if let Some(currency) = currency {
+            return Some(Reward {
+                amount: value,
+                currency: currency.to_uppercase(),
+                value: None,
+            });
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_db(name: &str) -> Option<Self> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.as_str() == name)
+    }
+}
+
+/// An issue's estimated difficulty, with the signals it's estimated from,
+/// e.g. `label:e-easy`, `title:refactor` or `body:long`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyEstimate {
+    pub difficulty: Difficulty,
+    pub signals: Vec<String>,
+}
+
+/// Estimates the difficulty of an issue. Labels saying it decide, the
+/// hardest winning; otherwise each title keyword and a short or long body
+/// count one point towards easy or hard, and a tie is medium.
+pub fn difficulty(labels: &[String], title: &str, body: Option<&str>) -> DifficultyEstimate {
+    let easy = &config::get().beginner_friendly_labels;
+    estimate_difficulty(labels, title, body, easy)
+}
+
+/// [`difficulty`], with the patterns of the labels saying an issue is easy.
+fn estimate_difficulty(
+    labels: &[String],
+    title: &str,
+    body: Option<&str>,
+    easy: &[String],
+) -> DifficultyEstimate {
+    let medium: Vec<String> = MEDIUM_LABELS.iter().map(|p| p.to_string()).collect();
+    let hard: Vec<String> = HARD_LABELS.iter().map(|p| p.to_string()).collect();
+    let mut labelled = None;
+    let mut signals = Vec::new();
+    for label in labels {
+        let said = [
+            (Difficulty::Hard, &hard[..]),
+            (Difficulty::Medium, &medium[..]),
+            (Difficulty::Easy, easy),
+        ]
+        .into_iter()
+        .find(|(_, patterns)| {
+            patterns
+                .iter()
+                .any(|pattern| matches_pattern(label, pattern))
+        });
+        if let Some((difficulty, _)) = said {
+            labelled = labelled.max(Some(difficulty));
+            signals.push(format!("label:{}", label));
+        }
+    }
+    if let Some(difficulty) = labelled {
+        return DifficultyEstimate {
+            difficulty,
+            signals,
+        };
+    }
+
+    let mut score = 0;
+    let title = title.to_lowercase();
+    for word in title.split(|c: char| !c.is_alphanumeric()) {
+        let points = if EASY_WORDS.contains(&word) {
+            -1
+        } else if HARD_WORDS.contains(&word) {
+            1
+        } else {
+            continue;
+        };
+        let signal = format!("title:{}", word);
+        if !signals.contains(&signal) {
+            score += points;
+            signals.push(signal);
+        }
+    }
+    let body_chars = body.map_or(0, |body| body.trim().chars().count());
+    if body_chars < SHORT_BODY_CHARS {
+        score -= 1;
+        signals.push("body:short".to_string());
+    } else if body_chars > LONG_BODY_CHARS {
+        score += 1;
+        signals.push("body:long".to_string());
+    }
+
+    let difficulty = match score {
+        ..=-1 => Difficulty::Easy,
+        0 => Difficulty::Medium,
+        1.. => Difficulty::Hard,
+    };
+    DifficultyEstimate {
+        difficulty,
+        signals,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueType {
+    Bug,
+    Feature,
+    Docs,
+    Test,
+    Chore,
+}
+
+impl IssueType {
+    pub const ALL: [IssueType; 5] = [
+        IssueType::Bug,
+        IssueType::Feature,
+        IssueType::Docs,
+        IssueType::Test,
+        IssueType::Chore,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IssueType::Bug => "bug",
+            IssueType::Feature => "feature",
+            IssueType::Docs => "docs",
+            IssueType::Test => "test",
+            IssueType::Chore => "chore",
+        }
+    }
+
+    pub fn from_db(name: &str) -> Option<Self> {
+        IssueType::ALL
+            .into_iter()
+            .find(|issue_type| issue_type.as_str() == name)
+    }
+}
+
+/// The type of an issue: the first of [`TYPE_LABELS`] one of its labels
+/// matches, or else the one of its title's prefix, e.g. `fix:`,
+/// `docs(api):` or `[feat]`. `None` when neither tells.
+pub fn issue_type(labels: &[String], title: &str) -> Option<IssueType> {
+    let labelled = TYPE_LABELS.iter().find(|(_, patterns)| {
+        labels.iter().any(|label| {
+            patterns
+                .iter()
+                .any(|pattern| matches_pattern(label, pattern))
+        })
+    });
+    if let Some((issue_type, _)) = labelled {
+        return Some(*issue_type);
+    }
+
+    let title = title.trim_start().to_lowercase();
+    let prefix = match title.strip_prefix('[') {
+        Some(bracketed) => bracketed.split_once(']')?.0,
+        None => {
+            let (prefix, _) = title.split_once(':')?;
+            // The scope of `type(scope):` and the `!` of breaking changes.
+            prefix
+                .split('(')
+                .next()
+                .unwrap_or(prefix)
+                .trim_end_matches('!')
+        }
+    };
+    TYPE_PREFIXES
+        .iter()
+        .find(|(known, _)| *known == prefix.trim())
+        .map(|(_, issue_type)| *issue_type)
+}
+
+/// The staleness of an issue created and last updated at these dates, as of
+/// `now`, between 0 and 1 and rounded to two decimals.
+pub fn staleness(created_at: DateTime<Utc>, updated_at: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let days = |since: DateTime<Utc>| (now - since).num_seconds().max(0) as f64 / 86_400.0;
+    let inactivity = (days(updated_at) / INACTIVE_DAYS).min(1.0);
+    let age = (days(created_at) / OPEN_DAYS).min(1.0);
+    let score = INACTIVITY_WEIGHT * inactivity + (1.0 - INACTIVITY_WEIGHT) * age;
+    (score * 100.0).round() / 100.0
+}
+
+/// Whether `value` matches `pattern`, `*` in it matching any run of
+/// characters, possibly none.
+pub fn matches_pattern(value: &str, pattern: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    // Without a `*`, the pattern is the whole value.
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(found) => rest = &rest[found + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Whether one of `labels` matches one of `patterns`.
+fn any_matches(labels: &[String], patterns: &[String]) -> bool {
+    labels.iter().any(|label| {
+        patterns
+            .iter()
+            .any(|pattern| matches_pattern(label, pattern))
+    })
+}
+
+/// Whether an issue asks for outside help, by one of its labels matching
+/// `HELP_WANTED_LABELS` or a phrase of its body.
+pub fn seeking_contributors(labels: &[String], body: Option<&str>) -> bool {
+    any_matches(labels, &config::get().help_wanted_labels) || body.is_some_and(asks_for_help)
+}
+
+/// Whether `text` has one of [`HELP_PHRASES`], whatever its case and
+/// spacing.
+fn asks_for_help(text: &str) -> bool {
+    let text = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    HELP_PHRASES.iter().any(|phrase| text.contains(phrase))
+}
+
+/// Whether an issue with these labels suits newcomers, as one of them
+/// matches `BEGINNER_FRIENDLY_LABELS`.
+pub fn is_beginner_friendly(labels: &[String]) -> bool {
+    any_matches(labels, &config::get().beginner_friendly_labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcard_patterns() {
+        assert!(matches_pattern("good first issue", "good first issue"));
+        assert!(!matches_pattern("good first issue!", "good first issue"));
+        assert!(matches_pattern("good first issue", "good first*"));
+        assert!(matches_pattern("d1-easy", "*-easy"));
+        assert!(matches_pattern("e-easy", "*easy"));
+        assert!(matches_pattern("difficulty: easy", "difficulty:*easy"));
+        assert!(matches_pattern("easy", "*"));
+        assert!(!matches_pattern("easy-ish", "*-easy"));
+        assert!(!matches_pattern("e-ea", "e-*ea*sy"));
+        assert!(!matches_pattern("ab", "ab*b"));
+    }
+
+    #[test]
+    fn maps_labels_to_categories() {
+        let rules: Vec<CategoryRule> = [
+            "good first*=difficulty:easy",
+            "e-easy=Difficulty:Easy",
+            "a-*=area:runtime",
+            "bounty*=reward:paid",
+        ]
+        .iter()
+        .map(|entry| parse_category(entry).unwrap())
+        .collect();
+        let labels = ["e-easy", "good first issue", "a-runtime"].map(String::from);
+        assert_eq!(
+            categories(&labels, &rules),
+            ["area:runtime", "difficulty:easy"]
+        );
+        assert!(categories(&["bug".to_string()], &rules).is_empty());
+        assert!(parse_category("bounty=reward").is_err());
+        assert!(parse_category("=reward:paid").is_err());
+    }
+
+    #[test]
+    fn tags_programs() {
+        let rules: Vec<(String, String)> = [
+            "hacktoberfest*=hacktoberfest",
+            "gsoc*=GSoC",
+            "google summer of code*=gsoc",
+            "*mentor*=mentorship",
+        ]
+        .iter()
+        .map(|entry| parse_program(entry).unwrap())
+        .collect();
+        let values = ["hacktoberfest-accepted", "mentor available", "gsoc-2026"].map(String::from);
+        assert_eq!(
+            program_tags(&values, &rules),
+            ["gsoc", "hacktoberfest", "mentorship"]
+        );
+        assert!(program_tags(&["bug".to_string()], &rules).is_empty());
+        assert!(parse_program("hacktoberfest").is_err());
+        assert!(parse_program("hacktoberfest=").is_err());
+    }
+
+    #[test]
+    fn extracts_rewards() {
+        let reward = |text: &str| amount(text).map(|reward| (reward.amount, reward.currency));
+        assert_eq!(reward("$500"), Some((500.0, "USD".to_string())));
+        assert_eq!(
+            reward("bounty: $1,500.50"),
+            Some((1500.5, "USD".to_string()))
+        );
+        assert_eq!(
+            reward("reward: 200 USDC."),
+            Some((200.0, "USDC".to_string()))
+        );
+        assert_eq!(reward("💰 $2k"), Some((2000.0, "USD".to_string())));
+        assert_eq!(reward("€50 bounty"), Some((50.0, "EUR".to_string())));
+        assert_eq!(reward("$500 usdt"), Some((500.0, "USDT".to_string())));
+        assert_eq!(reward("fix 2 bugs in v3 for #12"), None);
+        assert_eq!(reward("bounty"), None);
+
+        let labels = ["bug".to_string()];
+        let body = "Fixes a $5 lambda bill.\n\n**Bounty**: 300 DOT once merged";
+        assert_eq!(
+            super::reward(&labels, Some(body)),
+            Some(Reward {
+                amount: 300.0,
+                currency: "DOT".to_string(),
+                value: None,
+            })
+        );
+        assert_eq!(super::reward(&labels, Some("Costs $5 a month")), None);
+    }
+
+    #[test]
+    fn finds_help_requests() {
+        assert!(asks_for_help("Happy to review, **PRs   welcome**!"));
+        assert!(asks_for_help("Mentor\navailable: @alice"));
+        assert!(asks_for_help("Pull requests are welcome."));
+        assert!(!asks_for_help(
+            "Welcome to the project, see the PR template."
+        ));
+        assert!(!asks_for_help(""));
+    }
+
+    #[test]
+    fn converts_rewards() {
+        let rates = [
+            parse_rate("usdc=1").unwrap(),
+            parse_rate("DOT=4.25").unwrap(),
+        ];
+        assert_eq!(rates[0], ("USDC".to_string(), 1.0));
+        assert_eq!(reward_value(500.0, "USD", "USD", &rates), Some(500.0));
+        assert_eq!(reward_value(200.0, "USDC", "USD", &rates), Some(200.0));
+        assert_eq!(reward_value(300.0, "DOT", "USD", &rates), Some(1275.0));
+        assert_eq!(reward_value(1.0, "ETH", "USD", &rates), None);
+
+        assert!(parse_rate("DOT").is_err());
+        assert!(parse_rate("DOT=-1").is_err());
+        assert!(parse_rate("=1").is_err());
+        assert!(parse_rate("DOT=abc").is_err());
+    }
+
+    #[test]
+    fn estimates_difficulty() {
+        let long_body = "context ".repeat(500);
+        let estimate = |labels: &[&str], title: &str, body: &str| {
+            let labels: Vec<String> = labels.iter().map(|label| label.to_string()).collect();
+            let easy = ["*-easy".to_string()];
+            let estimate = estimate_difficulty(&labels, title, Some(body), &easy);
+            (estimate.difficulty, estimate.signals)
+        };
+        let label_signals = vec!["label:hard".to_string(), "label:e-easy".to_string()];
+        assert_eq!(
+            estimate(&["hard", "e-easy", "bug"], "Fix typo", ""),
+            (Difficulty::Hard, label_signals)
+        );
+        assert_eq!(
+            estimate(&[], "Fix typo in the README", &long_body),
+            (
+                Difficulty::Easy,
+                ["title:typo", "title:readme", "body:long"]
+                    .map(String::from)
+                    .to_vec()
+            )
+        );
+        assert_eq!(
+            estimate(&[], "Refactor the consensus engine", &long_body).0,
+            Difficulty::Hard
+        );
+        assert_eq!(
+            estimate(&[], "Support Gitea forks", &"context ".repeat(100)),
+            (Difficulty::Medium, Vec::new())
+        );
+    }
+
+    #[test]
+    fn types_issues() {
+        let typed = |labels: &[&str], title: &str| {
+            let labels: Vec<String> = labels.iter().map(|label| label.to_string()).collect();
+            issue_type(&labels, title)
+        };
+        assert_eq!(typed(&["c-bug"], "Add a flag"), Some(IssueType::Bug));
+        assert_eq!(
+            typed(&["t-documentation", "enhancement"], ""),
+            Some(IssueType::Feature)
+        );
+        assert_eq!(typed(&["a-docs"], ""), Some(IssueType::Docs));
+        assert_eq!(typed(&[], "fix: crash on empty urls"), Some(IssueType::Bug));
+        assert_eq!(
+            typed(&[], "docs(api): list endpoints"),
+            Some(IssueType::Docs)
+        );
+        assert_eq!(
+            typed(&[], "feat!: drop v1 payloads"),
+            Some(IssueType::Feature)
+        );
+        assert_eq!(typed(&[], "[Test] cover the parser"), Some(IssueType::Test));
+        assert_eq!(typed(&["good first issue"], "Note: slow"), None);
+        assert_eq!(typed(&["latest", "debug"], "Improve the import"), None);
+    }
+
+    #[test]
+    fn scores_staleness() {
+        let now = Utc::now();
+        let days_ago = |days| now - chrono::Duration::days(days);
+        assert_eq!(staleness(now, now, now), 0.0);
+        assert_eq!(staleness(days_ago(730), days_ago(365), now), 1.0);
+        assert_eq!(staleness(days_ago(3000), days_ago(1000), now), 1.0);
+        assert_eq!(staleness(days_ago(730), days_ago(1), now), 0.3);
+        assert_eq!(staleness(days_ago(365), days_ago(365), now), 0.85);
+        // Dates ahead of the clock count as now.
+        assert_eq!(staleness(days_ago(-1), days_ago(-1), now), 0.0);
+    }
+}
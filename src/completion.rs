@@ -0,0 +1,169 @@
+//! Events announcing finished imports, published to an SNS topic and/or an
+//! EventBridge bus so other Kudos services, e.g. the search indexer, can
+//! react to them.
+//!
+//! Publishing is best effort: the import is already committed, so a failure
+//! is logged rather than failing it.
+
+use aws_sdk_eventbridge::types::PutEventsRequestEntry;
+use lambda_http::tracing::{error, info};
+use serde::Serialize;
+
+use crate::aws::sdk_config;
+use crate::config;
+use crate::error::ImportError;
+use crate::model::ImportResponse;
+
+/// `source` of the EventBridge events.
+const EVENT_SOURCE: &str = "kudos.gh-import-issues";
+
+/// `detail-type` of the EventBridge events, and `event` attribute of the
+/// SNS messages.
+const DETAIL_TYPE: &str = "Project Import Completed";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionStatus {
+    Succeeded,
+    Failed,
+}
+
+impl CompletionStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CompletionStatus::Succeeded => "succeeded",
+            CompletionStatus::Failed => "failed",
+        }
+    }
+}
+
+/// What a completion event says about the import.
+#[derive(Debug, Serialize)]
+pub struct CompletionEvent {
+    pub project_slug: String,
+    pub status: CompletionStatus,
+    /// `None` when the import failed before the project was inserted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<i32>,
+    pub repositories_imported: usize,
+    pub issues_imported: u64,
+    pub warnings: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CompletionEvent {
+    /// The event of an import, `project_id` being its project's when it got
+    /// inserted, or was already.
+    pub fn new(
+        project_slug: &str,
+        project_id: Option<i32>,
+        result: &Result<ImportResponse, ImportError>,
+    ) -> Self {
+        match result {
+            Ok(response) => CompletionEvent {
+                project_slug: project_slug.to_string(),
+                status: CompletionStatus::Succeeded,
+                project_id: Some(response.project_id),
+                repositories_imported: response.repositories.len(),
+                issues_imported: response.total_issues_imported,
+                warnings: response.warnings.len(),
+                error: None,
+            },
+            Err(e) => CompletionEvent {
+                project_slug: project_slug.to_string(),
+                status: CompletionStatus::Failed,
+                project_id,
+                repositories_imported: 0,
+                issues_imported: 0,
+                warnings: 0,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Publishes the event to the configured destinations, if there are any.
+pub async fn publish(event: &CompletionEvent) {
+    let config = config::get();
+    if config.completion_topic_arn.is_none() && config.completion_event_bus.is_none() {
+        return;
+    }
+
+    let detail = match serde_json::to_string(event) {
+        Ok(detail) => detail,
+        Err(e) => {
+            error!("Couldn't serialize the completion event: {}", e);
+            return;
+        }
+    };
+    if let Some(topic_arn) = &config.completion_topic_arn {
+        if let Err(e) = publish_to_topic(topic_arn, event, &detail).await {
+            error!(topic_arn, "Couldn't publish the completion event: {}", e);
+        }
+    }
+    if let Some(event_bus) = &config.completion_event_bus {
+        if let Err(e) = put_on_bus(event_bus, &detail).await {
+            error!(event_bus, "Couldn't put the completion event: {}", e);
+        }
+    }
+    info!(
+        project_slug = %event.project_slug,
+        status = event.status.as_str(),
+        "Completion event published"
+    );
+}
+
+async fn publish_to_topic(
+    topic_arn: &str,
+    event: &CompletionEvent,
+    detail: &str,
+) -> Result<(), String> {
+    use aws_sdk_sns::error::DisplayErrorContext;
+    use aws_sdk_sns::types::MessageAttributeValue;
+
+    // Attributes let subscriptions filter on status without parsing the body.
+    let attribute = |value: &str| {
+        MessageAttributeValue::builder()
+            .data_type("String")
+            .string_value(value)
+            .build()
+            .map_err(|e| e.to_string())
+    };
+    aws_sdk_sns::Client::new(sdk_config().await)
+        .publish()
+        .topic_arn(topic_arn)
+        .message(detail)
+        .message_attributes("event", attribute(DETAIL_TYPE)?)
+        .message_attributes("status", attribute(event.status.as_str())?)
+        .send()
+        .await
+        .map_err(|e| DisplayErrorContext(e).to_string())?;
+    Ok(())
+}
+
+async fn put_on_bus(event_bus: &str, detail: &str) -> Result<(), String> {
+    use aws_sdk_eventbridge::error::DisplayErrorContext;
+
+    let entry = PutEventsRequestEntry::builder()
+        .event_bus_name(event_bus)
+        .source(EVENT_SOURCE)
+        .detail_type(DETAIL_TYPE)
+        .detail(detail)
+        .build();
+    let output = aws_sdk_eventbridge::Client::new(sdk_config().await)
+        .put_events()
+        .entries(entry)
+        .send()
+        .await
+        .map_err(|e| DisplayErrorContext(e).to_string())?;
+    // Failed entries don't fail the request itself.
+    match output.entries().first() {
+        Some(entry) if entry.error_code().is_some() => Err(format!(
+            "{}: {}",
+            entry.error_code().unwrap_or_default(),
+            entry.error_message().unwrap_or_default()
+        )),
+        _ => Ok(()),
+    }
+}
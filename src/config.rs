@@ -0,0 +1,631 @@
+//! Settings of the importer, read from the environment once at startup so
+//! missing or malformed ones are reported up front instead of on the first
+//! request needing them.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::classify;
+use crate::error::ImportError;
+use crate::flags::Flag;
+use crate::gitea;
+use crate::review::ApprovalRule;
+use crate::taxonomy;
+use crate::telemetry;
+
+/// Settings without which nothing can be imported, each given either in
+/// plain text or as the ARN of a secret holding it.
+const REQUIRED: [(&str, &str); 2] = [
+    ("DATABASE_URL", "DATABASE_URL_SECRET_ARN"),
+    ("GITHUB_TOKEN", "GITHUB_TOKEN_SECRET_ARN"),
+];
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Every setting, each read from the env var of the same name in upper case.
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub database_url: Option<String>,
+    /// Secrets Manager secret or SSM parameter holding the database URL,
+    /// used when `database_url` isn't set.
+    pub database_url_secret_arn: Option<String>,
+    pub github_token: Option<String>,
+    /// Secrets Manager secret or SSM parameter holding the GitHub token,
+    /// used when `github_token` isn't set.
+    pub github_token_secret_arn: Option<String>,
+    /// Token of the GitLab API, only needed for private projects or higher
+    /// rate limits.
+    pub gitlab_token: Option<String>,
+    /// Secrets Manager secret or SSM parameter holding the GitLab token,
+    /// used when `gitlab_token` isn't set.
+    pub gitlab_token_secret_arn: Option<String>,
+    /// Hosts served by GitLab, comma-separated: `gitlab.com` and any
+    /// self-hosted instance.
+    #[serde(default = "defaults::gitlab_hosts")]
+    pub gitlab_hosts: Vec<String>,
+    /// Bitbucket Cloud repository, project or workspace access token, only
+    /// needed for private repositories or higher rate limits.
+    pub bitbucket_token: Option<String>,
+    /// Secrets Manager secret or SSM parameter holding the Bitbucket token,
+    /// used when `bitbucket_token` isn't set.
+    pub bitbucket_token_secret_arn: Option<String>,
+    /// Token sent to every Gitea instance, so only list trusted instances
+    /// when setting one.
+    pub gitea_token: Option<String>,
+    /// Secrets Manager secret or SSM parameter holding the Gitea token, used
+    /// when `gitea_token` isn't set.
+    pub gitea_token_secret_arn: Option<String>,
+    /// Gitea, Forgejo or Codeberg instances, comma-separated, each as `host`
+    /// or `host=api_base_url`.
+    #[serde(default = "defaults::gitea_instances")]
+    pub gitea_instances: Vec<String>,
+    /// SourceHut personal access token with read access to todo.sr.ht,
+    /// required to import SourceHut trackers.
+    pub sourcehut_token: Option<String>,
+    /// Secrets Manager secret or SSM parameter holding the SourceHut token,
+    /// used when `sourcehut_token` isn't set.
+    pub sourcehut_token_secret_arn: Option<String>,
+    /// Hosts repositories may be imported from, comma-separated, e.g. to
+    /// name the `forge` of a self-hosted instance that isn't configured.
+    /// Unset, only the hosts of the configured forges are allowed.
+    #[serde(default)]
+    pub allowed_repository_hosts: Vec<String>,
+    /// How long fetched secrets are reused before being fetched again.
+    #[serde(default = "defaults::secrets_cache_seconds")]
+    pub secrets_cache_seconds: u64,
+
+    /// Don't call GitHub during imports: projects and repositories are still
+    /// created, with their issue syncs left pending for the re-sync.
+    #[serde(default)]
+    pub github_degraded: bool,
+    /// An import stops calling GitHub after this many repositories in a row
+    /// failed because of it, as if `github_degraded` was set.
+    #[serde(default = "defaults::github_degraded_after_failures")]
+    pub github_degraded_after_failures: u32,
+
+    /// Calls to GitHub or Postgres fail right away for the rest of the
+    /// invocation once this many failed in a row.
+    #[serde(default = "defaults::breaker_failure_threshold")]
+    pub breaker_failure_threshold: u32,
+
+    /// Database statements taking longer than this are logged as warnings,
+    /// with the rows they returned and affected.
+    #[serde(default = "defaults::slow_query_threshold_ms")]
+    pub slow_query_threshold_ms: u64,
+
+    /// Feature flags enabled in this environment, comma-separated, unless
+    /// the `feature_flags` table says otherwise.
+    #[serde(default)]
+    pub feature_flags: Vec<String>,
+    /// How long the `feature_flags` table is cached.
+    #[serde(default = "defaults::feature_flags_cache_seconds")]
+    pub feature_flags_cache_seconds: u64,
+
+    /// Values allowed in each attribute facet of projects, comma-separated,
+    /// along the `taxonomy` table's. Facets with none allow any value.
+    #[serde(default)]
+    pub taxonomy_purposes: Vec<String>,
+    #[serde(default)]
+    pub taxonomy_stack_levels: Vec<String>,
+    #[serde(default)]
+    pub taxonomy_technologies: Vec<String>,
+    #[serde(default)]
+    pub taxonomy_types: Vec<String>,
+    /// Spellings of technologies replaced on ingest, comma-separated, each as
+    /// `alias=Technology`. Aliases are matched case-insensitively.
+    #[serde(default = "defaults::technology_aliases")]
+    pub technology_aliases: Vec<String>,
+    /// Reject projects with attribute values outside the taxonomy instead of
+    /// importing them with a warning.
+    #[serde(default)]
+    pub taxonomy_strict: bool,
+    /// How long the `taxonomy` table is cached.
+    #[serde(default = "defaults::taxonomy_cache_seconds")]
+    pub taxonomy_cache_seconds: u64,
+
+    /// Keep the alt text of images in issue excerpts, instead of dropping
+    /// images altogether.
+    #[serde(default)]
+    pub excerpt_image_alt: bool,
+
+    /// Labels whose issues are never imported, comma-separated. Matching is
+    /// case-insensitive. Defaults to labels of non-actionable issues; set it
+    /// empty to import them all.
+    #[serde(default = "defaults::label_blocklist")]
+    pub label_blocklist: Vec<String>,
+    /// Patterns of the labels flagging issues as beginner-friendly,
+    /// comma-separated, see [`crate::classify`].
+    #[serde(default = "defaults::beginner_friendly_labels")]
+    pub beginner_friendly_labels: Vec<String>,
+    /// Patterns of the labels asking for outside help, comma-separated.
+    #[serde(default = "defaults::help_wanted_labels")]
+    pub help_wanted_labels: Vec<String>,
+    /// Rules translating labels into Kudos categories, comma-separated, each
+    /// as `pattern=category:value`, along the `label_categories` table's.
+    #[serde(default)]
+    pub label_categories: Vec<String>,
+    /// Patterns of the labels marking issues as paid, comma-separated.
+    #[serde(default = "defaults::reward_labels")]
+    pub reward_labels: Vec<String>,
+    /// Currency rewards are converted to, uppercase.
+    #[serde(default = "defaults::reward_base_currency")]
+    pub reward_base_currency: String,
+    /// Static rates rewards are converted with, comma-separated, each as
+    /// `CURRENCY=rate`, the value of one unit in the base currency.
+    #[serde(default = "defaults::reward_rates")]
+    pub reward_rates: Vec<String>,
+    /// Rules approving imported issues without a review, comma-separated,
+    /// among `all`, `beginner_friendly`, `seeking_contributors` and `paid`,
+    /// see [`crate::review`]. Empty leaves them all pending.
+    #[serde(default = "defaults::review_auto_approve")]
+    pub review_auto_approve: Vec<String>,
+    /// Rules tagging issues and repositories with the programs they take
+    /// part in, from labels and topics, comma-separated, each as
+    /// `pattern=program`.
+    #[serde(default = "defaults::program_labels")]
+    pub program_labels: Vec<String>,
+    /// How many of a repository's recent issues are sampled to measure how
+    /// quickly maintainers reply, see [`crate::responsiveness`]. 0 turns the
+    /// measure off.
+    #[serde(default = "defaults::responsiveness_sample_size")]
+    pub responsiveness_sample_size: usize,
+    /// Warn about repositories without a license, which are imported all
+    /// the same.
+    #[serde(default)]
+    pub warn_unlicensed: bool,
+
+    /// The end of invocation summary of the GitHub rate limit is a warning
+    /// when fewer calls than this are left.
+    #[serde(default = "defaults::github_rate_limit_warn_remaining")]
+    pub github_rate_limit_warn_remaining: u64,
+
+    /// Repositories last synced longer ago than this are refreshed by the
+    /// scheduled run.
+    #[serde(default = "defaults::resync_after_hours")]
+    pub resync_after_hours: i64,
+    /// How many repositories a single scheduled run refreshes at most.
+    #[serde(default = "defaults::resync_batch_size")]
+    pub resync_batch_size: i64,
+
+    /// No new repository is started when less than this is left before the
+    /// invocation's deadline.
+    #[serde(default = "defaults::import_deadline_margin_seconds")]
+    pub import_deadline_margin_seconds: u64,
+    /// How long a repository's sync may take before it stops between pages,
+    /// leaving the rest to the scheduled re-sync. 0 turns the budget off.
+    #[serde(default = "defaults::repository_time_budget_seconds")]
+    pub repository_time_budget_seconds: u64,
+    /// How many of a project's repositories are imported at a time with the
+    /// `parallel_imports` flag.
+    #[serde(default = "defaults::import_concurrency")]
+    pub import_concurrency: usize,
+
+    /// How often Step Functions task heartbeats are sent; keep it below the
+    /// task's `HeartbeatSeconds`.
+    #[serde(default = "defaults::sfn_heartbeat_seconds")]
+    pub sfn_heartbeat_seconds: u64,
+
+    /// Payloads listing more repositories than this are rejected, so an
+    /// organization's every repository isn't imported by mistake.
+    #[serde(default = "defaults::max_repositories_per_project")]
+    pub max_repositories_per_project: usize,
+
+    /// Queue feeding this function, projects are fanned out to it when set.
+    pub import_queue_url: Option<String>,
+    /// Projects with fewer repositories than this are imported inline.
+    #[serde(default = "defaults::fanout_min_repositories")]
+    pub fanout_min_repositories: usize,
+
+    /// ARN of the import dead-letter queue, whose messages are replayed.
+    pub import_dlq_arn: Option<String>,
+    /// Dead-lettered messages are given up on after this many receives.
+    #[serde(default = "defaults::dlq_max_receives")]
+    pub dlq_max_receives: i32,
+
+    /// Jobs failing with transient errors are retried until they've been
+    /// attempted this many times.
+    #[serde(default = "defaults::job_max_attempts")]
+    pub job_max_attempts: i32,
+    /// How many jobs a scheduled run claims at most.
+    #[serde(default = "defaults::job_batch_size")]
+    pub job_batch_size: i64,
+
+    /// Where S3 bulk import reports are written. Keep it out of the bucket
+    /// notification's filter so reports don't trigger imports themselves.
+    #[serde(default = "defaults::import_report_prefix")]
+    pub import_report_prefix: String,
+    /// Bucket detailed import reports are written to, see [`crate::report`].
+    /// S3 bulk imports default to the bucket of their file, asynchronous
+    /// imports get no report without it.
+    pub import_report_bucket: Option<String>,
+
+    /// Share of the debug events about single issues that are logged, from
+    /// `0` to `1`, see [`crate::telemetry`].
+    #[serde(default = "defaults::issue_log_sample_rate")]
+    pub issue_log_sample_rate: f64,
+
+    /// CloudWatch namespace of the metrics, see [`crate::metrics`].
+    #[serde(default = "defaults::metrics_namespace")]
+    pub metrics_namespace: String,
+
+    /// OTLP/HTTP collector spans and metrics are exported to, e.g.
+    /// `https://otlp.example.com:4318`, when built with the `otlp` feature.
+    pub otel_exporter_otlp_endpoint: Option<String>,
+    /// Headers sent to the collector, e.g. its API key, as comma-separated
+    /// `name=value` pairs.
+    pub otel_exporter_otlp_headers: Option<String>,
+    /// `service.name` of the exported telemetry.
+    #[serde(default = "defaults::otel_service_name")]
+    pub otel_service_name: String,
+
+    /// SNS topic import completion events are published to, if any.
+    pub completion_topic_arn: Option<String>,
+    /// EventBridge bus import completion events are put on, if any.
+    pub completion_event_bus: Option<String>,
+
+    /// Where errors and panics are reported, when built with the
+    /// `error-reporting` feature.
+    pub sentry_dsn: Option<String>,
+    /// Environment reports are filed under, e.g. `production`.
+    pub sentry_environment: Option<String>,
+
+    /// Address the local development server listens on.
+    #[serde(default = "defaults::local_server_addr")]
+    pub local_server_addr: String,
+}
+
+mod defaults {
+    pub fn otel_service_name() -> String {
+        "gh-import-issues".to_string()
+    }
+
+    pub fn issue_log_sample_rate() -> f64 {
+        1.0
+    }
+
+    pub fn metrics_namespace() -> String {
+        "Kudos/GhImportIssues".to_string()
+    }
+
+    pub fn gitlab_hosts() -> Vec<String> {
+        vec!["gitlab.com".to_string()]
+    }
+
+    pub fn gitea_instances() -> Vec<String> {
+        vec!["codeberg.org".to_string()]
+    }
+
+    pub fn secrets_cache_seconds() -> u64 {
+        300
+    }
+
+    pub fn github_degraded_after_failures() -> u32 {
+        3
+    }
+
+    pub fn breaker_failure_threshold() -> u32 {
+        5
+    }
+
+    pub fn slow_query_threshold_ms() -> u64 {
+        500
+    }
+
+    pub fn feature_flags_cache_seconds() -> u64 {
+        60
+    }
+
+    pub fn technology_aliases() -> Vec<String> {
+        [
+            "rust-lang=Rust",
+            "rustlang=Rust",
+            "ts=TypeScript",
+            "typescript=TypeScript",
+            "js=JavaScript",
+            "javascript=JavaScript",
+            "golang=Go",
+            "py=Python",
+            "sol=Solidity",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    pub fn beginner_friendly_labels() -> Vec<String> {
+        [
+            "good first*",
+            "good-first-*",
+            "first-timers-only",
+            "beginner*",
+            "easy",
+            "*-easy",
+            "difficulty:*easy",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    pub fn help_wanted_labels() -> Vec<String> {
+        [
+            "help wanted",
+            "help-wanted",
+            "*contributions welcome*",
+            "*prs welcome*",
+            "up for grabs",
+            "up-for-grabs",
+            "*mentor*",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    pub fn label_blocklist() -> Vec<String> {
+        ["wontfix", "duplicate", "invalid", "stale", "blocked"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    pub fn reward_labels() -> Vec<String> {
+        ["*bounty*", "*reward*", "paid"].map(String::from).to_vec()
+    }
+
+    pub fn reward_base_currency() -> String {
+        "USD".to_string()
+    }
+
+    pub fn reward_rates() -> Vec<String> {
+        ["USDC=1", "USDT=1", "DAI=1"].map(String::from).to_vec()
+    }
+
+    pub fn review_auto_approve() -> Vec<String> {
+        vec!["all".to_string()]
+    }
+
+    pub fn program_labels() -> Vec<String> {
+        [
+            "hacktoberfest*=hacktoberfest",
+            "gsoc*=gsoc",
+            "google summer of code*=gsoc",
+            "google-summer-of-code*=gsoc",
+            "outreachy*=outreachy",
+            "*mentor*=mentorship",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    pub fn responsiveness_sample_size() -> usize {
+        10
+    }
+
+    pub fn taxonomy_cache_seconds() -> u64 {
+        300
+    }
+
+    pub fn github_rate_limit_warn_remaining() -> u64 {
+        500
+    }
+
+    pub fn resync_after_hours() -> i64 {
+        24
+    }
+
+    pub fn resync_batch_size() -> i64 {
+        20
+    }
+
+    pub fn import_deadline_margin_seconds() -> u64 {
+        60
+    }
+
+    pub fn repository_time_budget_seconds() -> u64 {
+        300
+    }
+
+    pub fn import_concurrency() -> usize {
+        4
+    }
+
+    pub fn sfn_heartbeat_seconds() -> u64 {
+        60
+    }
+
+    pub fn max_repositories_per_project() -> usize {
+        50
+    }
+
+    pub fn fanout_min_repositories() -> usize {
+        10
+    }
+
+    pub fn dlq_max_receives() -> i32 {
+        5
+    }
+
+    pub fn job_max_attempts() -> i32 {
+        5
+    }
+
+    pub fn job_batch_size() -> i64 {
+        10
+    }
+
+    pub fn import_report_prefix() -> String {
+        "reports/".to_string()
+    }
+
+    pub fn local_server_addr() -> String {
+        "127.0.0.1:9001".to_string()
+    }
+}
+
+/// Trims and lowercases the items of a comma-separated setting, dropping
+/// empty ones.
+fn normalize_list(values: Vec<String>) -> Vec<String> {
+    values
+        .iter()
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, ImportError> {
+        Config::from_vars(env::vars())
+    }
+
+    /// Reads the settings from `vars`, later values of a name overriding
+    /// earlier ones. Every missing or invalid setting is reported at once.
+    pub fn from_vars(
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ImportError> {
+        let vars: HashMap<String, String> = vars.into_iter().collect();
+
+        let is_set = |name: &str| vars.get(name).is_some_and(|value| !value.trim().is_empty());
+        let missing: Vec<String> = REQUIRED
+            .into_iter()
+            .filter(|(name, secret)| !is_set(name) && !is_set(secret))
+            .map(|(name, secret)| format!("{} (or {})", name, secret))
+            .collect();
+        if !missing.is_empty() {
+            return Err(ImportError::Config(format!(
+                "missing required settings: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let mut config: Config =
+            envy::from_iter(vars).map_err(|e| ImportError::Config(e.to_string()))?;
+        config.label_blocklist = normalize_list(config.label_blocklist);
+        config.beginner_friendly_labels = normalize_list(config.beginner_friendly_labels);
+        config.label_categories = normalize_list(config.label_categories);
+        config.reward_labels = normalize_list(config.reward_labels);
+        config.help_wanted_labels = normalize_list(config.help_wanted_labels);
+        config.program_labels = normalize_list(config.program_labels);
+        config.review_auto_approve = normalize_list(config.review_auto_approve);
+        config.reward_base_currency = config.reward_base_currency.trim().to_uppercase();
+        config.reward_rates = normalize_list(config.reward_rates);
+        config.gitlab_hosts = normalize_list(config.gitlab_hosts);
+        config.feature_flags = normalize_list(config.feature_flags);
+        config.taxonomy_purposes = normalize_list(config.taxonomy_purposes);
+        config.taxonomy_stack_levels = normalize_list(config.taxonomy_stack_levels);
+        config.taxonomy_technologies = normalize_list(config.taxonomy_technologies);
+        config.taxonomy_types = normalize_list(config.taxonomy_types);
+        // Base urls may be case-sensitive, entries are only lowercased once parsed.
+        config
+            .gitea_instances
+            .retain(|entry| !entry.trim().is_empty());
+        config
+            .technology_aliases
+            .retain(|entry| !entry.trim().is_empty());
+
+        let problems = config.problems();
+        if !problems.is_empty() {
+            return Err(ImportError::Config(problems.join(", ")));
+        }
+        Ok(config)
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut at_least_one = |name: &str, value: i64| {
+            if value < 1 {
+                problems.push(format!("{} must be at least 1", name));
+            }
+        };
+        at_least_one(
+            "GITHUB_DEGRADED_AFTER_FAILURES",
+            self.github_degraded_after_failures.into(),
+        );
+        at_least_one(
+            "BREAKER_FAILURE_THRESHOLD",
+            self.breaker_failure_threshold.into(),
+        );
+        at_least_one("RESYNC_AFTER_HOURS", self.resync_after_hours);
+        at_least_one("RESYNC_BATCH_SIZE", self.resync_batch_size);
+        at_least_one("SFN_HEARTBEAT_SECONDS", self.sfn_heartbeat_seconds as i64);
+        at_least_one(
+            "FANOUT_MIN_REPOSITORIES",
+            self.fanout_min_repositories as i64,
+        );
+        at_least_one(
+            "MAX_REPOSITORIES_PER_PROJECT",
+            self.max_repositories_per_project as i64,
+        );
+        at_least_one("DLQ_MAX_RECEIVES", self.dlq_max_receives.into());
+        at_least_one("JOB_MAX_ATTEMPTS", self.job_max_attempts.into());
+        at_least_one("JOB_BATCH_SIZE", self.job_batch_size);
+        if !(0.0..=1.0).contains(&self.issue_log_sample_rate) {
+            problems.push("ISSUE_LOG_SAMPLE_RATE must be between 0 and 1".to_string());
+        }
+        for entry in &self.gitea_instances {
+            if let Err(problem) = gitea::parse_instance(entry) {
+                problems.push(problem);
+            }
+        }
+        if let Some(headers) = &self.otel_exporter_otlp_headers {
+            if let Err(problem) = telemetry::parse_headers(headers) {
+                problems.push(problem);
+            }
+        }
+        for entry in &self.technology_aliases {
+            if let Err(problem) = taxonomy::parse_alias(entry) {
+                problems.push(problem);
+            }
+        }
+        for entry in &self.label_categories {
+            if let Err(problem) = classify::parse_category(entry) {
+                problems.push(problem);
+            }
+        }
+        for entry in &self.program_labels {
+            if let Err(problem) = classify::parse_program(entry) {
+                problems.push(problem);
+            }
+        }
+        if self.reward_base_currency.is_empty() {
+            problems.push("REWARD_BASE_CURRENCY can't be empty".to_string());
+        }
+        for entry in &self.reward_rates {
+            if let Err(problem) = classify::parse_rate(entry) {
+                problems.push(problem);
+            }
+        }
+        for rule in &self.review_auto_approve {
+            if ApprovalRule::from_name(rule).is_none() {
+                problems.push(format!("REVIEW_AUTO_APPROVE has unknown rule `{}`", rule));
+            }
+        }
+        for flag in &self.feature_flags {
+            if Flag::from_name(flag).is_none() {
+                problems.push(format!("FEATURE_FLAGS has unknown flag `{}`", flag));
+            }
+        }
+        problems
+    }
+}
+
+/// Makes `config` the one returned by [`get`]. Only the first call has an
+/// effect.
+pub fn init(config: Config) -> &'static Config {
+    CONFIG.get_or_init(|| config)
+}
+
+/// Loads the configuration from the environment, see [`Config::from_env`].
+pub fn load() -> Result<&'static Config, ImportError> {
+    Ok(init(Config::from_env()?))
+}
+
+/// The configuration loaded at startup.
+///
+/// # Panics
+///
+/// If neither [`load`] nor [`init`] was called first.
+pub fn get() -> &'static Config {
+    CONFIG
+        .get()
+        .expect("configuration must be loaded at startup")
+}
@@ -0,0 +1,103 @@
+//! Contributor statistics of a repository, for "active community" signals on
+//! project pages: its top contributors by commits, how many people
+//! committed to it lately and how many maintainers landed those commits.
+//! Fetched with the first page of every sync while the `contributor_stats`
+//! flag is on.
+
+use lambda_http::tracing::warn;
+use serde::Serialize;
+
+use crate::flags::{self, Flag};
+use crate::github::IssueSource;
+use crate::model::RepoInfo;
+use crate::store::Store;
+
+/// How many of the top contributors are kept.
+pub const TOP_CONTRIBUTORS: usize = 10;
+/// Recent contributors are the commit authors of this many last days.
+pub const RECENT_DAYS: i64 = 90;
+/// The account GitHub commits as for changes merged through its web
+/// interface.
+pub const WEB_COMMITTER: &str = "web-flow";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Contributor {
+    pub login: String,
+    /// Commits on the default branch.
+    pub contributions: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContributorStats {
+    /// Up to [`TOP_CONTRIBUTORS`], most commits first, bots left out.
+    pub top: Vec<Contributor>,
+    /// Distinct authors of the last 100 commits within [`RECENT_DAYS`], bots
+    /// left out.
+    pub recent_contributors: u32,
+    /// Distinct maintainers of those commits, see [`maintainer`].
+    pub active_maintainers: u32,
+}
+
+/// Whether a login is a bot account, such as `dependabot[bot]`.
+pub fn is_bot(login: &str) -> bool {
+    login.ends_with("[bot]")
+}
+
+/// The maintainer who landed a commit on the default branch, by its
+/// logins: its committer, or the author of a merge commit GitHub committed
+/// through its web interface. `None` for squashed and rebased pull requests
+/// GitHub committed, whose author may be anyone, and for bots.
+pub fn maintainer<'a>(
+    author: Option<&'a str>,
+    committer: Option<&'a str>,
+    merge: bool,
+) -> Option<&'a str> {
+    let maintainer = match committer {
+        Some(WEB_COMMITTER) if merge => author,
+        Some(WEB_COMMITTER) => None,
+        committer => committer,
+    };
+    maintainer.filter(|login| !is_bot(login))
+}
+
+/// Fetches and records the contributors of the repository while the flag is
+/// on. Not worth failing a sync over: errors are only logged.
+pub async fn record(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    repo_info: &RepoInfo,
+    repo_id: i32,
+) {
+    if !flags::is_enabled(store, Flag::ContributorStats).await {
+        return;
+    }
+    let result = match source.contributors(repo_info).await {
+        Ok(Some(stats)) => store.record_contributors(repo_id, &stats).await,
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(
+            "Couldn't fetch the contributors of {}: {}",
+            repo_info.url(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_maintainers() {
+        assert_eq!(maintainer(Some("dev"), Some("lead"), false), Some("lead"));
+        assert_eq!(
+            maintainer(Some("lead"), Some(WEB_COMMITTER), true),
+            Some("lead")
+        );
+        assert_eq!(maintainer(Some("dev"), Some(WEB_COMMITTER), false), None);
+        assert_eq!(maintainer(Some("dev"), None, false), None);
+        assert_eq!(maintainer(None, Some("github-actions[bot]"), false), None);
+    }
+}
@@ -0,0 +1,132 @@
+//! Replay of the import dead-letter queue. Each message is retried and, when
+//! it fails again, its failure is classified: transient ones (rate limits,
+//! database or GitHub errors) are left in the queue to be replayed later,
+//! up to [`Config::dlq_max_receives`](crate::config::Config) receives, while the others, and the ones out of
+//! attempts, are recorded in `import_failures` and removed from the queue.
+
+use aws_lambda_events::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent, SqsMessage};
+use lambda_http::{
+    tracing::{error, info, info_span, warn, Instrument},
+    Error,
+};
+
+use crate::config;
+use crate::error::ImportError;
+use crate::forge::ForgeRegistry;
+use crate::model::{FailureKind, FailureStatus, ImportFailure};
+use crate::sqs::{parse_message, process_payload};
+use crate::store::{PgStore, Store};
+
+/// Whether the event comes from the dead-letter queue given by the
+/// `IMPORT_DLQ_ARN` env var rather than the import queue.
+pub fn is_dlq_event(event: &SqsEvent) -> bool {
+    let Some(dlq_arn) = &config::get().import_dlq_arn else {
+        return false;
+    };
+    event
+        .records
+        .first()
+        .and_then(|record| record.event_source_arn.as_deref())
+        == Some(dlq_arn.as_str())
+}
+
+/// Tells what made an import fail, see [`ImportError::failure_kind`].
+pub fn classify(error: &Error) -> FailureKind {
+    error
+        .downcast_ref::<ImportError>()
+        .map_or(FailureKind::Other, ImportError::failure_kind)
+}
+
+fn receive_count(message: &SqsMessage) -> i32 {
+    message
+        .attributes
+        .get("ApproximateReceiveCount")
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(1)
+}
+
+/// Replays one message, returning whether it should stay in the queue.
+async fn replay_message(
+    message: &SqsMessage,
+    store: &PgStore,
+    source: &ForgeRegistry,
+    max_receives: i32,
+) -> Result<bool, Error> {
+    let body = message.body.as_deref().unwrap_or_default();
+    let receive_count = receive_count(message);
+
+    let (kind, error) = match parse_message(body) {
+        Ok(payload) => match process_payload(payload, store, source, None).await {
+            Ok(_) => {
+                info!("Replayed message imported");
+                return Ok(false);
+            }
+            Err(e) => (classify(&e), e.to_string()),
+        },
+        Err(problem) => (FailureKind::InvalidPayload, problem),
+    };
+
+    if kind.is_transient() && receive_count < max_receives {
+        warn!(
+            kind = kind.as_str(),
+            receive_count, "Replay failed, will retry: {}", error
+        );
+        return Ok(true);
+    }
+
+    let status = if kind.is_transient() {
+        FailureStatus::Exhausted
+    } else {
+        FailureStatus::Invalid
+    };
+    error!(
+        kind = kind.as_str(),
+        status = status.as_str(),
+        receive_count,
+        "Giving up on message: {}",
+        error
+    );
+    store
+        .record_failure(&ImportFailure {
+            message_id: message.message_id.clone().unwrap_or_default(),
+            body: body.to_string(),
+            kind,
+            status,
+            error,
+            receive_count,
+        })
+        .await?;
+    Ok(false)
+}
+
+/// Replays every message of a DLQ batch. Messages to retry, and those whose
+/// failure couldn't even be recorded, are reported as batch item failures so
+/// they stay in the queue.
+pub async fn handle_dlq_event(event: SqsEvent) -> Result<SqsBatchResponse, Error> {
+    let store = PgStore::connect().await?;
+    let source = ForgeRegistry::from_config().await?;
+    let max_receives = config::get().dlq_max_receives;
+
+    let mut batch_item_failures = Vec::new();
+    for message in &event.records {
+        let message_id = message.message_id.clone().unwrap_or_default();
+        let span = info_span!("dlq_message", message_id = %message_id);
+
+        let retry = replay_message(message, &store, &source, max_receives)
+            .instrument(span.clone())
+            .await
+            .unwrap_or_else(|e| {
+                span.in_scope(|| error!("Couldn't record failure: {}", e));
+                true
+            });
+        if retry {
+            batch_item_failures.push(BatchItemFailure {
+                item_identifier: message_id,
+            });
+        }
+    }
+
+    Ok(SqsBatchResponse {
+        batch_item_failures,
+    })
+}
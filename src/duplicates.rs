@@ -0,0 +1,124 @@
+//! Detection of issues cross-posted across a project's repositories, so the
+//! catalog shows each opportunity once.
+//!
+//! Two open issues of different repositories of a project are duplicates
+//! when their titles are the same, whatever their case and spacing, or when
+//! each one links to the other. The later one is marked as a duplicate of
+//! the earliest, and duplicates are left out of listings. Titles shorter
+//! than [`MIN_TITLE_CHARS`], e.g. `Add tests`, are too generic to tell.
+
+use std::sync::OnceLock;
+
+use lambda_http::tracing::{info, warn};
+use regex::Regex;
+
+use crate::store::Store;
+
+/// Length, in characters, of the shortest titles compared.
+pub const MIN_TITLE_CHARS: i32 = 20;
+
+/// Issue urls, e.g. `https://github.com/owner/name/issues/12`, GitLab's
+/// `/-/issues/12` among them.
+fn url_regex() -> &'static Regex {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    URL.get_or_init(|| {
+        Regex::new(r"https?://([\w.-]+)/([\w.-]+)/([\w.-]+)/(?:-/)?issues/(\d+)")
+            .expect("issue url regex is valid")
+    })
+}
+
+/// SourceHut ticket urls, e.g. `https://todo.sr.ht/~owner/tracker/12`.
+fn ticket_regex() -> &'static Regex {
+    static TICKET: OnceLock<Regex> = OnceLock::new();
+    TICKET.get_or_init(|| {
+        Regex::new(r"https?://todo\.sr\.ht/(~[\w.-]+)/([\w.-]+)/(\d+)")
+            .expect("ticket url regex is valid")
+    })
+}
+
+/// Cross-repository references, e.g. `owner/name#12`.
+fn shorthand_regex() -> &'static Regex {
+    static SHORTHAND: OnceLock<Regex> = OnceLock::new();
+    SHORTHAND.get_or_init(|| {
+        Regex::new(r"(?:^|[\s(\[])([\w.-]+)/([\w.-]+)#(\d+)\b")
+            .expect("issue reference regex is valid")
+    })
+}
+
+/// The issues `body` links to, as lowercase urls such as
+/// `https://github.com/owner/name/issues/12`, or SourceHut tickets'
+/// `https://todo.sr.ht/~owner/tracker/12`. References like
+/// `owner/name#12` are to issues on the host of `html_url`, the issue's own.
+pub fn referenced_issues(html_url: &str, body: Option<&str>) -> Vec<String> {
+    let Some(body) = body else {
+        return Vec::new();
+    };
+    let host = url::Url::parse(html_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string));
+
+    let mut references: Vec<String> = url_regex()
+        .captures_iter(body)
+        .map(|captures| {
+            format!(
+                "https://{}/{}/{}/issues/{}",
+                &captures[1], &captures[2], &captures[3], &captures[4]
+            )
+        })
+        .chain(ticket_regex().captures_iter(body).map(|captures| {
+            format!(
+                "https://todo.sr.ht/{}/{}/{}",
+                &captures[1], &captures[2], &captures[3]
+            )
+        }))
+        .collect();
+    if let Some(host) = host {
+        references.extend(shorthand_regex().captures_iter(body).map(|captures| {
+            format!(
+                "https://{}/{}/{}/issues/{}",
+                host, &captures[1], &captures[2], &captures[3]
+            )
+        }));
+    }
+    for reference in references.iter_mut() {
+        *reference = reference.to_lowercase();
+    }
+    let own_url = html_url.to_lowercase().replace("/-/issues/", "/issues/");
+    references.retain(|reference| *reference != own_url);
+    references.sort();
+    references.dedup();
+    references
+}
+
+/// Marks the duplicates among the open issues of the repository's project,
+/// and unmarks those that no longer are. Never fails the sync it's part of.
+pub async fn mark(store: &dyn Store, repo_id: i32) {
+    match store.mark_duplicates(repo_id).await {
+        Ok(0) => {}
+        Ok(changed) => info!(repository_id = repo_id, changed, "Duplicates marked"),
+        Err(e) => warn!("Couldn't mark duplicate issues: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_referenced_issues() {
+        let body = "Cross-posted from https://github.com/Owner/api/issues/12 and \
+                    (kudos-ink/portal#3), see https://gitlab.com/group/app/-/issues/4 \
+                    and https://todo.sr.ht/~Owner/tracker/5. \
+                    Not a reference: a#1, nor this issue owner/site#7.";
+        assert_eq!(
+            referenced_issues("https://github.com/owner/site/issues/7", Some(body)),
+            [
+                "https://github.com/kudos-ink/portal/issues/3",
+                "https://github.com/owner/api/issues/12",
+                "https://gitlab.com/group/app/issues/4",
+                "https://todo.sr.ht/~owner/tracker/5",
+            ]
+        );
+        assert!(referenced_issues("https://github.com/owner/site/issues/7", None).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+//! The error type of the import pipeline, and how each kind of failure is
+//! reported to HTTP callers and classified for retries.
+
+use serde_json::{json, Value};
+
+use crate::breaker::Upstream;
+use crate::model::FailureKind;
+use crate::validation::{PayloadError, ValidationError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("Request body is not valid JSON: {0}")]
+    InvalidJson(#[source] serde_json::Error),
+
+    #[error("Invalid project payload: {}", summarize(.0))]
+    Validation(Vec<ValidationError>),
+
+    #[error("{0}")]
+    BadRequest(String),
+
+    /// `{0}` names what wasn't found, e.g. `Project`.
+    #[error("{0} not found")]
+    NotFound(String),
+
+    /// `{0}` is the host no forge serves.
+    #[error("Repositories on `{0}` can't be imported")]
+    UnsupportedHost(String),
+
+    #[error("GitHub rate limit exceeded: {0}")]
+    RateLimited(#[source] Box<octocrab::Error>),
+
+    #[error("GitHub request failed: {0}")]
+    GitHub(#[source] Box<octocrab::Error>),
+
+    /// A code host other than GitHub failed; `status` is `None` when no
+    /// response was received.
+    #[error("{forge} request failed: {message}")]
+    Forge {
+        forge: &'static str,
+        status: Option<u16>,
+        message: String,
+    },
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Couldn't load secret: {0}")]
+    Secret(String),
+
+    /// A circuit breaker is open after repeated failures of the upstream.
+    #[error("{0} is unavailable")]
+    Unavailable(Upstream),
+
+    #[error("Couldn't queue messages: {0}")]
+    Queue(String),
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+}
+
+fn summarize(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("{}: {}", e.pointer, e.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl From<octocrab::Error> for ImportError {
+    fn from(error: octocrab::Error) -> Self {
+        match &error {
+            octocrab::Error::GitHub { source, .. }
+                if source.status_code == 429
+                    || (source.status_code == 403
+                        && source.message.to_lowercase().contains("rate limit")) =>
+            {
+                ImportError::RateLimited(Box::new(error))
+            }
+            _ => ImportError::GitHub(Box::new(error)),
+        }
+    }
+}
+
+impl From<PayloadError> for ImportError {
+    fn from(error: PayloadError) -> Self {
+        match error {
+            PayloadError::Syntax(e) => ImportError::InvalidJson(e),
+            PayloadError::Invalid(errors) => ImportError::Validation(errors),
+        }
+    }
+}
+
+impl ImportError {
+    /// HTTP status answered for this error.
+    pub fn status(&self) -> u16 {
+        match self {
+            ImportError::InvalidJson(_) | ImportError::BadRequest(_) => 400,
+            ImportError::NotFound(_) => 404,
+            ImportError::Validation(_) | ImportError::UnsupportedHost(_) => 422,
+            ImportError::RateLimited(_) => 503,
+            ImportError::Forge {
+                status: Some(429), ..
+            } => 503,
+            ImportError::GitHub(_) | ImportError::Forge { .. } => 502,
+            ImportError::Unavailable(_) => 503,
+            ImportError::Database(_)
+            | ImportError::Secret(_)
+            | ImportError::Queue(_)
+            | ImportError::Config(_) => 500,
+        }
+    }
+
+    /// JSON body answered for this error. Server-side details are only
+    /// logged, not sent to callers.
+    pub fn body(&self) -> Value {
+        match self {
+            ImportError::InvalidJson(e) => {
+                json!({ "error": "Request body is not valid JSON", "message": e.to_string() })
+            }
+            ImportError::Validation(errors) => {
+                json!({ "error": "Invalid project payload", "details": errors })
+            }
+            ImportError::BadRequest(_)
+            | ImportError::NotFound(_)
+            | ImportError::UnsupportedHost(_) => {
+                json!({ "error": self.to_string() })
+            }
+            ImportError::RateLimited(_) => {
+                json!({ "error": "GitHub rate limit exceeded, retry later" })
+            }
+            ImportError::GitHub(_) => json!({ "error": "GitHub request failed" }),
+            ImportError::Forge {
+                forge,
+                status: Some(429),
+                ..
+            } => json!({ "error": format!("{} rate limit exceeded, retry later", forge) }),
+            ImportError::Forge { forge, .. } => {
+                json!({ "error": format!("{} request failed", forge) })
+            }
+            ImportError::Unavailable(upstream) => json!({
+                "error": "Upstream unavailable, retry later",
+                "upstream": upstream.to_string(),
+            }),
+            ImportError::Database(_)
+            | ImportError::Secret(_)
+            | ImportError::Queue(_)
+            | ImportError::Config(_) => json!({ "error": "Internal server error" }),
+        }
+    }
+
+    /// Whether the error shows the upstream itself is failing, as opposed to
+    /// a request it rightly rejected.
+    pub fn is_upstream_failure(&self) -> bool {
+        match self {
+            ImportError::RateLimited(_) | ImportError::Unavailable(_) => true,
+            ImportError::GitHub(_) => self.failure_kind() == FailureKind::GitHub,
+            ImportError::Forge { status, .. } => status.is_none_or(|s| s == 429 || s >= 500),
+            ImportError::Database(error) => matches!(
+                error,
+                sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::Protocol(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+                    | sqlx::Error::WorkerCrashed
+            ),
+            _ => false,
+        }
+    }
+
+    /// What made the import fail, to tell whether retrying may help.
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            ImportError::InvalidJson(_)
+            | ImportError::Validation(_)
+            | ImportError::BadRequest(_) => FailureKind::InvalidPayload,
+            ImportError::NotFound(_) | ImportError::UnsupportedHost(_) => FailureKind::InvalidUrl,
+            ImportError::RateLimited(_) => FailureKind::RateLimited,
+            ImportError::GitHub(error) => match error.as_ref() {
+                // The repository path doesn't exist or isn't one.
+                octocrab::Error::GitHub { source, .. }
+                    if source.status_code == 404 || source.status_code == 422 =>
+                {
+                    FailureKind::InvalidUrl
+                }
+                _ => FailureKind::GitHub,
+            },
+            ImportError::Forge { status, .. } => match status {
+                Some(429) => FailureKind::RateLimited,
+                Some(404) => FailureKind::InvalidUrl,
+                _ => FailureKind::Forge,
+            },
+            ImportError::Database(_) => FailureKind::Database,
+            ImportError::Unavailable(Upstream::GitHub) => FailureKind::GitHub,
+            ImportError::Unavailable(Upstream::Postgres) => FailureKind::Database,
+            ImportError::Unavailable(Upstream::Forge(_)) => FailureKind::Forge,
+            ImportError::Secret(_) | ImportError::Queue(_) | ImportError::Config(_) => {
+                FailureKind::Other
+            }
+        }
+    }
+}
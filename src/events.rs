@@ -0,0 +1,92 @@
+//! Entry point of the Lambda: detects which kind of event invoked it from the
+//! shape of the payload and dispatches accordingly.
+
+use aws_lambda_events::{eventbridge::EventBridgeEvent, s3::S3Event, sqs::SqsEvent};
+use lambda_http::{request::LambdaRequest, service_fn, Adapter, Error, LambdaEvent, Service};
+use serde_json::{json, Value};
+
+use crate::dlq::{handle_dlq_event, is_dlq_event};
+use crate::error::ImportError;
+use crate::handler::handle_request;
+use crate::jobs::drain_jobs;
+use crate::rate_limit::InvocationBudget;
+use crate::reporting;
+use crate::resync::{handle_scheduled_event, is_scheduled_event};
+use crate::s3::handle_s3_event;
+use crate::sqs::handle_sqs_event;
+use crate::step_functions::{handle_task, is_task};
+use crate::telemetry;
+
+/// Returns the `eventSource` of the first record, e.g. `aws:sqs`, for events
+/// delivered in the `Records` envelope (SQS, S3).
+fn record_event_source(payload: &Value) -> Option<&str> {
+    payload.get("Records")?.get(0)?.get("eventSource")?.as_str()
+}
+
+/// EventBridge events carry a top level `detail-type`, which no other
+/// supported event has.
+fn is_eventbridge_event(payload: &Value) -> bool {
+    payload.get("detail-type").is_some() && payload.get("source").is_some()
+}
+
+/// Handles an invocation, reporting its failure (see [`reporting`]) and
+/// exporting its telemetry.
+pub async fn handle_event(event: LambdaEvent<Value>) -> Result<Value, Error> {
+    // Boxed, the dispatching future being too deeply nested to lay out inline.
+    let dispatched = Box::pin(dispatch(event));
+    let budget = InvocationBudget::start();
+    let result = reporting::isolate(async {
+        dispatched
+            .await
+            .inspect_err(|e| reporting::capture(e.as_ref()))
+    })
+    .await;
+    budget.log_summary();
+    telemetry::flush().await;
+    result
+}
+
+async fn dispatch(event: LambdaEvent<Value>) -> Result<Value, Error> {
+    let (payload, context) = event.into_parts();
+
+    if is_task(&payload) {
+        return handle_task(serde_json::from_value(payload)?).await;
+    }
+
+    if is_eventbridge_event(&payload) {
+        let event: EventBridgeEvent = serde_json::from_value(payload)?;
+        if !is_scheduled_event(&event) {
+            let message = format!(
+                "Unsupported EventBridge event `{}` from `{}`",
+                event.detail_type, event.source
+            );
+            return Err(ImportError::BadRequest(message).into());
+        }
+        let resync = handle_scheduled_event(event, Some(context.deadline())).await?;
+        let jobs = drain_jobs().await?;
+        return Ok(json!({ "resync": resync, "jobs": jobs }));
+    }
+
+    match record_event_source(&payload) {
+        Some("aws:sqs") => {
+            let sqs_event: SqsEvent = serde_json::from_value(payload)?;
+            let response = if is_dlq_event(&sqs_event) {
+                handle_dlq_event(sqs_event).await?
+            } else {
+                handle_sqs_event(sqs_event).await?
+            };
+            Ok(serde_json::to_value(response)?)
+        }
+        Some("aws:s3") => {
+            let s3_event: S3Event = serde_json::from_value(payload)?;
+            Ok(serde_json::to_value(handle_s3_event(s3_event).await?)?)
+        }
+        // Anything else is expected to be an API Gateway/ALB/function URL request.
+        _ => {
+            let request: LambdaRequest = serde_json::from_value(payload)?;
+            let mut adapter = Adapter::from(service_fn(handle_request));
+            let response = adapter.call(LambdaEvent::new(request, context)).await?;
+            Ok(serde_json::to_value(response)?)
+        }
+    }
+}
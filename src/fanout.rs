@@ -0,0 +1,118 @@
+//! Fan-out of imports of projects with many repositories: the project row is
+//! inserted right away and every repository is queued as its own
+//! `import_repository` task, so repositories are imported in parallel by
+//! separate invocations and one failing doesn't hold back the others.
+
+use aws_sdk_sqs::{error::DisplayErrorContext, types::SendMessageBatchRequestEntry, Client};
+use lambda_http::tracing::info;
+use serde::Serialize;
+
+use crate::aws::sdk_config;
+use crate::config;
+use crate::error::ImportError;
+use crate::import::{self, Retry};
+use crate::model::{ImportWarning, Project};
+use crate::step_functions::Task;
+use crate::store::Store;
+
+/// Most messages SQS accepts in one batch.
+const SEND_BATCH_SIZE: usize = 10;
+
+/// Response of a fanned out import; the repositories are imported afterwards.
+#[derive(Debug, Serialize)]
+pub struct FannedOutProject {
+    pub project_id: i32,
+    pub slug: String,
+    pub repositories_queued: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Returns the queue to fan the project out to, if `IMPORT_QUEUE_URL` is set
+/// and the project has at least `FANOUT_MIN_REPOSITORIES` repositories.
+pub fn fanout_queue(project: &Project) -> Option<String> {
+    let config = config::get();
+    let queue_url = config.import_queue_url.clone()?;
+    (project.links.repository.len() >= config.fanout_min_repositories).then_some(queue_url)
+}
+
+/// Inserts the project and queues one task per repository to `queue_url`,
+/// which must feed this function's SQS event source.
+///
+/// Retried under the same `token`, e.g. a job's, it queues the tasks of the
+/// project the first attempt inserted again, which reuse its repositories.
+pub async fn fan_out(
+    store: &dyn Store,
+    queue_url: &str,
+    mut project: Project,
+    retry: Option<Retry<'_>>,
+) -> Result<FannedOutProject, ImportError> {
+    let checkpoint = match retry {
+        Some(retry) => retry.checkpoint(store).await?,
+        None => None,
+    };
+    let state = match checkpoint.and_then(|checkpoint| checkpoint.state) {
+        Some(state) => {
+            info!(
+                project_id = state.response.project_id,
+                "Fanning out the project again"
+            );
+            project.slug = state.response.slug.clone();
+            state
+        }
+        None => import::start_import(store, &mut project, retry.map(|retry| retry.token)).await?,
+    };
+    let project_id = state.response.project_id;
+    let warnings = state.response.warnings;
+    let client = Client::new(sdk_config().await);
+
+    let repositories = project.links.repository;
+    let repositories_queued = repositories.len();
+    for (batch, chunk) in repositories.chunks(SEND_BATCH_SIZE).enumerate() {
+        let entries = chunk
+            .iter()
+            .enumerate()
+            .map(|(i, repository)| {
+                let task = Task::ImportRepository {
+                    project_id,
+                    repository: repository.clone(),
+                    filters: project.filters.clone(),
+                };
+                let body =
+                    serde_json::to_string(&task).map_err(|e| ImportError::Queue(e.to_string()))?;
+                SendMessageBatchRequestEntry::builder()
+                    .id((batch * SEND_BATCH_SIZE + i).to_string())
+                    .message_body(body)
+                    .build()
+                    .map_err(|e| ImportError::Queue(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let output = client
+            .send_message_batch()
+            .queue_url(queue_url)
+            .set_entries(Some(entries))
+            .send()
+            .await
+            .map_err(|e| ImportError::Queue(DisplayErrorContext(e).to_string()))?;
+        if let Some(failed) = output.failed.first() {
+            return Err(ImportError::Queue(format!(
+                "{} repository task(s) of project {} failed: {}",
+                output.failed.len(),
+                project_id,
+                failed.message.as_deref().unwrap_or(&failed.code)
+            )));
+        }
+    }
+
+    if let Some(retry) = retry {
+        store.delete_checkpoint(retry.token).await?;
+    }
+    info!(project_id, repositories_queued, "Project fanned out");
+    Ok(FannedOutProject {
+        project_id,
+        slug: project.slug,
+        repositories_queued,
+        warnings,
+    })
+}
@@ -0,0 +1,194 @@
+//! Which fetched issues are imported: issues are skipped when excluded by
+//! the repository's entry, when assigned
+//! (unless the payload's `skipAssigned` is `false`),
+//! carrying a label of `LABEL_BLOCKLIST`, outside the repository's scope or
+//! left out by the payload's `filters`, by title or by how long ago they
+//! were created.
+//!
+//! Title filters are regular expressions, limited in number, length and
+//! compiled size so a payload can't make the import spend its time matching.
+
+use chrono::{DateTime, Duration, Utc};
+use regex::{Regex, RegexBuilder};
+
+use crate::model::{ImportFilters, KudosIssue, RepoInfo, WarningReason};
+
+/// Most patterns each of `includeTitles` and `excludeTitles` can list.
+pub const MAX_TITLE_PATTERNS: usize = 20;
+
+/// Longest pattern allowed, in characters.
+pub const MAX_PATTERN_CHARS: usize = 200;
+
+/// Largest compiled pattern allowed, in bytes, see
+/// [`RegexBuilder::size_limit`].
+const PATTERN_SIZE_LIMIT: usize = 64 * 1024;
+
+/// Compiles a title pattern, describing the problem when it's invalid or
+/// too complex.
+pub fn title_regex(pattern: &str) -> Result<Regex, String> {
+    if pattern.chars().count() > MAX_PATTERN_CHARS {
+        return Err(format!(
+            "pattern is longer than {} characters",
+            MAX_PATTERN_CHARS
+        ));
+    }
+    RegexBuilder::new(pattern)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .dfa_size_limit(PATTERN_SIZE_LIMIT)
+        .build()
+        .map_err(|e| match e {
+            regex::Error::CompiledTooBig(_) => "pattern is too complex".to_string(),
+            e => format!("invalid pattern: {}", e),
+        })
+}
+
+/// The filters issues are checked against, with the payload's patterns
+/// compiled.
+#[derive(Debug)]
+pub struct IssueFilters {
+    skip_assigned: bool,
+    blocklist: Vec<String>,
+    include_titles: Vec<Regex>,
+    exclude_titles: Vec<Regex>,
+    min_age_days: Option<u32>,
+    max_age_days: Option<u32>,
+}
+
+impl IssueFilters {
+    pub fn new(blocklist: &[String], filters: &ImportFilters) -> Result<Self, String> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| title_regex(pattern))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(IssueFilters {
+            skip_assigned: filters.skip_assigned.unwrap_or(true),
+            blocklist: blocklist.to_vec(),
+            include_titles: compile(&filters.include_titles)?,
+            exclude_titles: compile(&filters.exclude_titles)?,
+            min_age_days: filters.min_age_days,
+            max_age_days: filters.max_age_days,
+        })
+    }
+
+    /// Filters of re-syncs, which don't have the payload's: assigned and
+    /// blocklisted issues are skipped.
+    pub fn blocklist(blocklist: &[String]) -> Self {
+        IssueFilters {
+            skip_assigned: true,
+            blocklist: blocklist.to_vec(),
+            include_titles: Vec::new(),
+            exclude_titles: Vec::new(),
+            min_age_days: None,
+            max_age_days: None,
+        }
+    }
+
+    /// Whether the title filters leave this title out: it matches none of
+    /// `includeTitles`, when there are some, or one of `excludeTitles`.
+    fn filters_title(&self, title: &str) -> bool {
+        let included = self.include_titles.is_empty()
+            || self
+                .include_titles
+                .iter()
+                .any(|regex| regex.is_match(title));
+        !included
+            || self
+                .exclude_titles
+                .iter()
+                .any(|regex| regex.is_match(title))
+    }
+
+    /// Whether the age filters leave out an issue created at `created_at`,
+    /// as of `now`.
+    fn filters_age(&self, created_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        let age = now - created_at;
+        let days = |days: u32| Duration::days(days.into());
+        self.min_age_days.is_some_and(|min| age < days(min))
+            || self.max_age_days.is_some_and(|max| age > days(max))
+    }
+
+    /// Returns why an issue should be left out, or `None` to import it.
+    pub fn skip_reason(&self, issue: &KudosIssue, repo_info: &RepoInfo) -> Option<WarningReason> {
+        if repo_info
+            .excluded_issues
+            .binary_search(&issue.number)
+            .is_ok()
+        {
+            return Some(WarningReason::IssueExcluded);
+        }
+        if self.skip_assigned && !issue.assignees.is_empty() {
+            return Some(WarningReason::IssueAssigned);
+        }
+        if issue
+            .labels
+            .iter()
+            .any(|label| self.blocklist.contains(&label.to_lowercase()))
+        {
+            return Some(WarningReason::IssueLabelBlocklisted);
+        }
+        if !repo_info.in_scope(&issue.labels) {
+            return Some(WarningReason::OutOfScope);
+        }
+        if self.filters_title(&issue.title) {
+            return Some(WarningReason::TitleFiltered);
+        }
+        self.filters_age(issue.issue_created_at, Utc::now())
+            .then_some(WarningReason::AgeFiltered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_titles() {
+        let filters = ImportFilters {
+            exclude_titles: vec![r"^\[Meta\]".to_string(), "(?i)tracking issue".to_string()],
+            ..Default::default()
+        };
+        let filters = IssueFilters::new(&[], &filters).unwrap();
+        assert!(filters.filters_title("[Meta] Roadmap"));
+        assert!(filters.filters_title("Tracking Issue for async closures"));
+        assert!(!filters.filters_title("Fix the [Meta] parser"));
+
+        let filters = ImportFilters {
+            include_titles: vec!["^(fix|feat)".to_string()],
+            exclude_titles: vec!["wip".to_string()],
+            ..Default::default()
+        };
+        let filters = IssueFilters::new(&[], &filters).unwrap();
+        assert!(!filters.filters_title("fix: crash on empty urls"));
+        assert!(filters.filters_title("docs: list endpoints"));
+        assert!(filters.filters_title("feat: wip gitea support"));
+    }
+
+    #[test]
+    fn filters_ages() {
+        let filters = ImportFilters {
+            min_age_days: Some(7),
+            max_age_days: Some(365),
+            ..Default::default()
+        };
+        let filters = IssueFilters::new(&[], &filters).unwrap();
+        let now = Utc::now();
+        let days_ago = |days| now - Duration::days(days);
+        assert!(filters.filters_age(days_ago(2), now));
+        assert!(!filters.filters_age(days_ago(7), now));
+        assert!(!filters.filters_age(days_ago(365), now));
+        assert!(filters.filters_age(days_ago(400), now));
+        assert!(!IssueFilters::blocklist(&[]).filters_age(days_ago(4000), now));
+    }
+
+    #[test]
+    fn limits_title_patterns() {
+        assert!(title_regex("(unclosed").is_err());
+        assert!(title_regex(&"a".repeat(MAX_PATTERN_CHARS + 1)).is_err());
+        assert_eq!(
+            title_regex(r"(\w{50}){50}").unwrap_err(),
+            "pattern is too complex"
+        );
+    }
+}
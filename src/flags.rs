@@ -0,0 +1,108 @@
+//! Runtime feature flags, so new behaviors can be rolled out per environment
+//! without a redeploy. A flag is enabled by listing it in `FEATURE_FLAGS`,
+//! and a row of the `feature_flags` table overrides that either way.
+//!
+//! Database rows are cached for `FEATURE_FLAGS_CACHE_SECONDS`, so toggling a
+//! flag takes effect within that delay.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use lambda_http::tracing::warn;
+use tokio::sync::Mutex;
+
+use crate::config;
+use crate::store::Store;
+
+/// Database overrides, with when they were read.
+static OVERRIDES: Mutex<Option<(HashMap<String, bool>, Instant)>> = Mutex::const_new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Flag {
+    /// Import closed issues too, not only open ones.
+    IncludeClosedIssues,
+    /// Import a project's repositories concurrently.
+    ParallelImports,
+    /// Keep issues in sync from GitHub webhooks between scheduled re-syncs.
+    WebhookSync,
+    /// Score repositories' health on sync, see [`crate::health`].
+    HealthScores,
+    /// Fetch repositories' contributors on sync, see [`crate::contributors`].
+    ContributorStats,
+}
+
+impl Flag {
+    pub const ALL: [Flag; 5] = [
+        Flag::IncludeClosedIssues,
+        Flag::ParallelImports,
+        Flag::WebhookSync,
+        Flag::HealthScores,
+        Flag::ContributorStats,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Flag::IncludeClosedIssues => "include_closed_issues",
+            Flag::ParallelImports => "parallel_imports",
+            Flag::WebhookSync => "webhook_sync",
+            Flag::HealthScores => "health_scores",
+            Flag::ContributorStats => "contributor_stats",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Flag::ALL.into_iter().find(|flag| flag.as_str() == name)
+    }
+}
+
+async fn overrides(store: &dyn Store) -> HashMap<String, bool> {
+    let ttl = Duration::from_secs(config::get().feature_flags_cache_seconds);
+    let mut cache = OVERRIDES.lock().await;
+    if let Some((overrides, read_at)) = cache.as_ref() {
+        if read_at.elapsed() < ttl {
+            return overrides.clone();
+        }
+    }
+
+    // Flags are never worth failing over: keep the last known overrides, or
+    // none, and try again after the TTL.
+    let overrides = match store.feature_flags().await {
+        Ok(rows) => rows.into_iter().collect(),
+        Err(e) => {
+            warn!(
+                "Couldn't read feature flags, using the previous ones: {}",
+                e
+            );
+            cache
+                .as_ref()
+                .map(|(overrides, _)| overrides.clone())
+                .unwrap_or_default()
+        }
+    };
+    *cache = Some((overrides.clone(), Instant::now()));
+    overrides
+}
+
+fn resolve(overrides: &HashMap<String, bool>, flag: Flag) -> bool {
+    overrides.get(flag.as_str()).copied().unwrap_or_else(|| {
+        config::get()
+            .feature_flags
+            .iter()
+            .any(|name| name == flag.as_str())
+    })
+}
+
+/// Whether `flag` is enabled, its `feature_flags` row winning over the
+/// `FEATURE_FLAGS` setting.
+pub async fn is_enabled(store: &dyn Store, flag: Flag) -> bool {
+    resolve(&overrides(store).await, flag)
+}
+
+/// Every flag with whether it's enabled.
+pub async fn all(store: &dyn Store) -> Vec<(Flag, bool)> {
+    let overrides = overrides(store).await;
+    Flag::ALL
+        .into_iter()
+        .map(|flag| (flag, resolve(&overrides, flag)))
+        .collect()
+}
@@ -0,0 +1,263 @@
+//! Registry of the code hosts ("forges") repositories can be imported from.
+//! Each forge is an [`IssueSource`] of one [`ForgeKind`], and the
+//! [`ForgeRegistry`] dispatches every call to the forge of the repository:
+//! the one its payload names, or else the one [`detect`]ed from its url's
+//! host. Supporting a new code host means adding a module implementing
+//! [`Forge`], a [`ForgeKind`] and its hosts in [`detect`], and registering
+//! it in [`ForgeRegistry::from_config`].
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+
+use crate::backfill::ClosedIssuePage;
+use crate::bitbucket::BitbucketSource;
+use crate::config;
+use crate::contributors::ContributorStats;
+use crate::error::ImportError;
+use crate::friendliness::Onboarding;
+use crate::gitea::{self, GiteaSource};
+use crate::github::{GitHubSource, IssueComment, IssuePage, IssueSource, RepositoryStatus};
+use crate::gitlab::GitLabSource;
+use crate::health::RepositoryActivity;
+use crate::languages::Languages;
+use crate::model::{ForgeKind, RepoInfo};
+use crate::sourcehut::SourceHutSource;
+
+pub trait Forge: IssueSource {
+    fn kind(&self) -> ForgeKind;
+}
+
+/// The forge serving repositories at `host` (lowercase), per the
+/// configuration.
+pub fn detect(host: &str) -> Option<ForgeKind> {
+    // Built-in hosts are told without the configuration.
+    let is_gitea_instance = || {
+        config::get()
+            .gitea_instances
+            .iter()
+            .filter_map(|entry| gitea::parse_instance(entry).ok())
+            .any(|(instance, _)| instance == host)
+    };
+
+    match host {
+        "github.com" | "www.github.com" => Some(ForgeKind::GitHub),
+        "bitbucket.org" | "www.bitbucket.org" => Some(ForgeKind::Bitbucket),
+        "todo.sr.ht" => Some(ForgeKind::SourceHut),
+        _ if config::get()
+            .gitlab_hosts
+            .iter()
+            .any(|gitlab| gitlab == host) =>
+        {
+            Some(ForgeKind::GitLab)
+        }
+        _ if is_gitea_instance() => Some(ForgeKind::Gitea),
+        _ => None,
+    }
+}
+
+/// Whether repositories may be imported from `host` (lowercase): one listed
+/// in `ALLOWED_REPOSITORY_HOSTS`, or when none is, one a configured forge
+/// serves.
+pub fn is_allowed_host(host: &str) -> bool {
+    let mut allowed = config::get()
+        .allowed_repository_hosts
+        .iter()
+        .map(|allowed| allowed.trim())
+        .filter(|allowed| !allowed.is_empty())
+        .peekable();
+    if allowed.peek().is_none() {
+        return detect(host).is_some();
+    }
+    allowed.any(|allowed| allowed.eq_ignore_ascii_case(host))
+}
+
+/// Why a forge can't be used in this environment, if it can't.
+pub fn unavailable_reason(kind: ForgeKind) -> Option<&'static str> {
+    let config = config::get();
+    let sourcehut_token =
+        config.sourcehut_token.is_some() || config.sourcehut_token_secret_arn.is_some();
+    (kind == ForgeKind::SourceHut && !sourcehut_token)
+        .then_some("SourceHut trackers can't be imported without SOURCEHUT_TOKEN")
+}
+
+/// Failure responses are cut to this many characters in errors.
+const MAX_ERROR_BODY_LEN: usize = 200;
+
+/// User agent sent to the forges' APIs, some of which reject requests
+/// without one.
+pub const USER_AGENT: &str = concat!("gh-import-issues/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP client of the forges' APIs.
+pub fn client() -> Result<reqwest::Client, ImportError> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| ImportError::Config(e.to_string()))
+}
+
+/// Sends a request to the REST API of `forge`, turning transport errors and
+/// unsuccessful responses into [`ImportError::Forge`].
+pub async fn send(
+    forge: &'static str,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, ImportError> {
+    let response = request.send().await.map_err(|e| ImportError::Forge {
+        forge,
+        status: None,
+        message: error_chain(&e),
+    })?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(ImportError::Forge {
+        forge,
+        status: Some(status.as_u16()),
+        message: format!(
+            "{}: {}",
+            status,
+            body.chars().take(MAX_ERROR_BODY_LEN).collect::<String>()
+        ),
+    })
+}
+
+/// Describes an error with its causes, which reqwest errors leave out of
+/// their message.
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// Reads the JSON body of a successful response of `forge`.
+pub async fn json<T: DeserializeOwned>(
+    forge: &'static str,
+    response: reqwest::Response,
+) -> Result<T, ImportError> {
+    response.json().await.map_err(|e| ImportError::Forge {
+        forge,
+        status: None,
+        message: format!("unexpected response: {}", e),
+    })
+}
+
+/// Whether the error is a `404` of a forge.
+pub fn is_not_found(error: &ImportError) -> bool {
+    matches!(
+        error,
+        ImportError::Forge {
+            status: Some(404),
+            ..
+        }
+    )
+}
+
+/// Every configured forge, itself an [`IssueSource`] for repositories of
+/// any of them.
+pub struct ForgeRegistry {
+    forges: Vec<Box<dyn Forge>>,
+}
+
+impl ForgeRegistry {
+    pub fn new(forges: Vec<Box<dyn Forge>>) -> Self {
+        ForgeRegistry { forges }
+    }
+
+    /// Builds every forge from the configuration.
+    pub async fn from_config() -> Result<Self, ImportError> {
+        let mut forges: Vec<Box<dyn Forge>> = vec![
+            Box::new(GitHubSource::from_config().await?),
+            Box::new(GitLabSource::from_config().await?),
+            Box::new(BitbucketSource::from_config().await?),
+            Box::new(GiteaSource::from_config().await?),
+        ];
+        // SourceHut's API can't be used anonymously.
+        if let Some(sourcehut) = SourceHutSource::from_config().await? {
+            forges.push(Box::new(sourcehut));
+        }
+        Ok(ForgeRegistry::new(forges))
+    }
+
+    /// The forge the repository is imported from, if it's supported and its
+    /// host is allowed.
+    pub fn forge_for(&self, repo_info: &RepoInfo) -> Option<&dyn Forge> {
+        if !is_allowed_host(&repo_info.host) {
+            return None;
+        }
+        let kind = repo_info.kind()?;
+        self.forges
+            .iter()
+            .find(|forge| forge.kind() == kind)
+            .map(|forge| forge.as_ref())
+    }
+
+    fn forge(&self, repo_info: &RepoInfo) -> Result<&dyn Forge, ImportError> {
+        self.forge_for(repo_info)
+            .ok_or_else(|| ImportError::UnsupportedHost(repo_info.host.clone()))
+    }
+}
+
+#[async_trait]
+impl IssueSource for ForgeRegistry {
+    async fn repository_status(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<RepositoryStatus, ImportError> {
+        self.forge(repo_info)?.repository_status(repo_info).await
+    }
+
+    async fn open_issues(&self, repo_info: &RepoInfo, page: u32) -> Result<IssuePage, ImportError> {
+        self.forge(repo_info)?.open_issues(repo_info, page).await
+    }
+
+    async fn issue_comments(
+        &self,
+        repo_info: &RepoInfo,
+        number: i64,
+    ) -> Result<Option<Vec<IssueComment>>, ImportError> {
+        self.forge(repo_info)?
+            .issue_comments(repo_info, number)
+            .await
+    }
+
+    async fn repository_activity(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<Option<RepositoryActivity>, ImportError> {
+        self.forge(repo_info)?.repository_activity(repo_info).await
+    }
+
+    async fn contributors(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<Option<ContributorStats>, ImportError> {
+        self.forge(repo_info)?.contributors(repo_info).await
+    }
+
+    async fn onboarding(&self, repo_info: &RepoInfo) -> Result<Option<Onboarding>, ImportError> {
+        self.forge(repo_info)?.onboarding(repo_info).await
+    }
+
+    async fn languages(&self, repo_info: &RepoInfo) -> Result<Option<Languages>, ImportError> {
+        self.forge(repo_info)?.languages(repo_info).await
+    }
+
+    async fn closed_issues(
+        &self,
+        repo_info: &RepoInfo,
+        since: DateTime<Utc>,
+        page: u32,
+    ) -> Result<Option<ClosedIssuePage>, ImportError> {
+        self.forge(repo_info)?
+            .closed_issues(repo_info, since, page)
+            .await
+    }
+}
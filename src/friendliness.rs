@@ -0,0 +1,95 @@
+//! How welcoming a repository is to first-time contributors: whether it has
+//! a contributing guide, issue templates and labels for beginner-friendly
+//! issues, as `BEGINNER_FRIENDLY_LABELS` matches them. Checked with the
+//! first page of every sync.
+
+use lambda_http::tracing::warn;
+
+use crate::github::IssueSource;
+use crate::model::RepoInfo;
+use crate::store::Store;
+
+/// What a repository offers newcomers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Onboarding {
+    /// A `CONTRIBUTING` file, at the root or in `docs/` or `.github/`.
+    pub has_contributing_guide: bool,
+    /// Issue forms or templates, in `.github/ISSUE_TEMPLATE/` or a single
+    /// `ISSUE_TEMPLATE` file.
+    pub has_issue_templates: bool,
+    /// A label matching `BEGINNER_FRIENDLY_LABELS`, whether or not issues
+    /// have it.
+    pub has_beginner_labels: bool,
+}
+
+impl Onboarding {
+    /// The share of the signals the repository has, between 0 and 1 and
+    /// rounded to two decimals.
+    pub fn score(&self) -> f64 {
+        let signals = [
+            self.has_contributing_guide,
+            self.has_issue_templates,
+            self.has_beginner_labels,
+        ];
+        let score = signals.iter().filter(|signal| **signal).count() as f64 / signals.len() as f64;
+        (score * 100.0).round() / 100.0
+    }
+
+    /// Whether the repository flags issues for beginners and tells them how
+    /// to contribute, with a guide or templates.
+    pub fn is_friendly(&self) -> bool {
+        self.has_beginner_labels && (self.has_contributing_guide || self.has_issue_templates)
+    }
+}
+
+/// Checks and records the friendliness of the repository. Not worth failing
+/// a sync over: errors are only logged.
+pub async fn record(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    repo_info: &RepoInfo,
+    repo_id: i32,
+) {
+    let result = match source.onboarding(repo_info).await {
+        Ok(Some(onboarding)) => store.record_onboarding(repo_id, &onboarding).await,
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(
+            "Couldn't check the friendliness of {}: {}",
+            repo_info.url(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_onboarding() {
+        let labels_only = Onboarding {
+            has_beginner_labels: true,
+            ..Onboarding::default()
+        };
+        assert_eq!(labels_only.score(), 0.33);
+        assert!(!labels_only.is_friendly());
+
+        let guided = Onboarding {
+            has_contributing_guide: true,
+            ..labels_only
+        };
+        assert_eq!(guided.score(), 0.67);
+        assert!(guided.is_friendly());
+
+        let unlabeled = Onboarding {
+            has_contributing_guide: true,
+            has_issue_templates: true,
+            has_beginner_labels: false,
+        };
+        assert!(!unlabeled.is_friendly());
+        assert_eq!(Onboarding::default().score(), 0.0);
+    }
+}
@@ -0,0 +1,268 @@
+//! Access to Gitea-compatible REST APIs (v1): Gitea, Forgejo and Codeberg,
+//! for repositories on the instances listed in `GITEA_INSTANCES`.
+//!
+//! An instance's API is at `https://{host}/api/v1` unless its entry gives
+//! another base url, as `host=base_url`, e.g. for instances served under a
+//! path.
+
+use std::collections::{BTreeMap, HashMap};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::breaker::{CircuitBreaker, Upstream};
+use crate::config;
+use crate::error::ImportError;
+use crate::forge::{self, Forge};
+use crate::github::{IssuePage, IssueSource, License, RepositoryMetadata, RepositoryStatus};
+use crate::languages::Languages;
+use crate::model::{ForgeKind, KudosIssue, RepoInfo};
+use crate::review::ReviewState;
+use crate::secrets;
+
+const FORGE: &str = "Gitea";
+
+/// Largest page Gitea returns by default (`MAX_RESPONSE_ITEMS`).
+const PAGE_LEN: u32 = 50;
+
+/// Parses a `GITEA_INSTANCES` entry into the instance's host and API base
+/// url.
+pub fn parse_instance(entry: &str) -> Result<(String, String), String> {
+    let (host, base_url) = match entry.split_once('=') {
+        Some((host, base_url)) => (host.trim(), base_url.trim().trim_end_matches('/')),
+        None => (entry.trim(), ""),
+    };
+    if host.is_empty() || host.contains('/') {
+        return Err(format!("GITEA_INSTANCES has an invalid host `{}`", host));
+    }
+
+    let host = host.to_lowercase();
+    if base_url.is_empty() {
+        let base_url = format!("https://{}/api/v1", host);
+        return Ok((host, base_url));
+    }
+    match url::Url::parse(base_url) {
+        Ok(url) if url.scheme() == "https" || url.scheme() == "http" => {
+            Ok((host, base_url.to_string()))
+        }
+        _ => Err(format!(
+            "GITEA_INSTANCES has an invalid base url `{}`",
+            base_url
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaRepository {
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    topics: Vec<String>,
+    /// SPDX identifiers of the licenses found, missing before Gitea 1.22.
+    licenses: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaUser {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaLabel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaIssue {
+    number: i64,
+    title: String,
+    html_url: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    user: GiteaUser,
+    #[serde(default)]
+    labels: Vec<GiteaLabel>,
+    /// `null` rather than empty when nobody is assigned.
+    assignees: Option<Vec<GiteaUser>>,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+impl From<GiteaIssue> for KudosIssue {
+    fn from(value: GiteaIssue) -> Self {
+        KudosIssue {
+            number: value.number,
+            title: value.title,
+            html_url: value.html_url,
+            issue_created_at: value.created_at,
+            issue_updated_at: value.updated_at,
+            user: value.user.login,
+            labels: value.labels.into_iter().map(|label| label.name).collect(),
+            assignees: value
+                .assignees
+                .unwrap_or_default()
+                .into_iter()
+                .map(|assignee| assignee.login)
+                .collect(),
+            body: value.body,
+            excerpt: None,
+            is_beginner_friendly: false,
+            categories: Vec::new(),
+            is_paid: false,
+            reward: None,
+            difficulty: None,
+            issue_type: None,
+            program_tags: Vec::new(),
+            staleness: None,
+            seeking_contributors: false,
+            review_state: ReviewState::Pending,
+            referenced_issues: Vec::new(),
+            reactions: 0,
+            ranking_score: None,
+        }
+    }
+}
+
+/// [`IssueSource`] backed by the REST API of Gitea-compatible instances.
+pub struct GiteaSource {
+    client: reqwest::Client,
+    token: Option<String>,
+    /// API base url by host.
+    instances: HashMap<String, String>,
+    breaker: CircuitBreaker,
+}
+
+impl GiteaSource {
+    pub fn new(
+        client: reqwest::Client,
+        token: Option<String>,
+        instances: HashMap<String, String>,
+    ) -> Self {
+        GiteaSource {
+            client,
+            token,
+            instances,
+            breaker: CircuitBreaker::new(Upstream::Forge(FORGE)),
+        }
+    }
+
+    /// Builds a client for the configured instances, authenticated with the
+    /// Gitea token when there's one.
+    pub async fn from_config() -> Result<Self, ImportError> {
+        let instances = config::get()
+            .gitea_instances
+            .iter()
+            .map(|entry| parse_instance(entry))
+            .collect::<Result<_, _>>()
+            .map_err(ImportError::Config)?;
+        let token = secrets::gitea_token().await?;
+        Ok(GiteaSource::new(forge::client()?, token, instances))
+    }
+
+    /// The API url of the repository. Hosts not listed in `GITEA_INSTANCES`,
+    /// imported as Gitea through the payload's `forge`, serve their API at
+    /// the usual path.
+    fn repository_url(&self, repo_info: &RepoInfo) -> String {
+        let path = format!("repos/{}/{}", repo_info.owner, repo_info.name);
+        match self.instances.get(&repo_info.host) {
+            Some(base_url) => format!("{}/{}", base_url, path),
+            None => format!("https://{}/api/v1/{}", repo_info.host, path),
+        }
+    }
+
+    /// A request to the repository's instance, never sending the token to
+    /// hosts not listed in `GITEA_INSTANCES`.
+    fn get(&self, repo_info: &RepoInfo, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(url);
+        match &self.token {
+            Some(token) if self.instances.contains_key(&repo_info.host) => {
+                request.header("Authorization", format!("token {}", token))
+            }
+            _ => request,
+        }
+    }
+}
+
+impl Forge for GiteaSource {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Gitea
+    }
+}
+
+#[async_trait]
+impl IssueSource for GiteaSource {
+    async fn repository_status(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<RepositoryStatus, ImportError> {
+        let url = self.repository_url(repo_info);
+        self.breaker
+            .call(async {
+                match forge::send(FORGE, self.get(repo_info, &url)).await {
+                    Ok(response) => {
+                        let repository: GiteaRepository = forge::json(FORGE, response).await?;
+                        Ok(if repository.archived {
+                            RepositoryStatus::Archived
+                        } else {
+                            let license = match repository.licenses {
+                                Some(licenses) => licenses
+                                    .into_iter()
+                                    .next()
+                                    .map_or(License::Unlicensed, License::Spdx),
+                                None => License::Unknown,
+                            };
+                            RepositoryStatus::Active(RepositoryMetadata {
+                                license,
+                                ..RepositoryMetadata::new(repository.topics)
+                            })
+                        })
+                    }
+                    Err(e) if forge::is_not_found(&e) => Ok(RepositoryStatus::NotFound),
+                    Err(e) => Err(e),
+                }
+            })
+            .await
+    }
+
+    async fn open_issues(&self, repo_info: &RepoInfo, page: u32) -> Result<IssuePage, ImportError> {
+        let url = format!("{}/issues", self.repository_url(repo_info));
+        self.breaker
+            .call(async {
+                // `type=issues` leaves pull requests out.
+                let request = self.get(repo_info, &url).query(&[
+                    ("state", "open".to_string()),
+                    ("type", "issues".to_string()),
+                    ("limit", PAGE_LEN.to_string()),
+                    ("page", page.to_string()),
+                ]);
+                let response = forge::send(FORGE, request).await?;
+
+                let has_next = response
+                    .headers()
+                    .get_all("link")
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .any(|value| value.contains("rel=\"next\""));
+                let issues: Vec<GiteaIssue> = forge::json(FORGE, response).await?;
+                Ok(IssuePage {
+                    issues: issues.into_iter().map(KudosIssue::from).collect(),
+                    has_next,
+                    pull_requests: 0,
+                })
+            })
+            .await
+    }
+
+    async fn languages(&self, repo_info: &RepoInfo) -> Result<Option<Languages>, ImportError> {
+        let url = format!("{}/languages", self.repository_url(repo_info));
+        self.breaker
+            .call(async {
+                let response = forge::send(FORGE, self.get(repo_info, &url)).await?;
+                // Bytes of code by language.
+                let sizes: BTreeMap<String, f64> = forge::json(FORGE, response).await?;
+                Ok(Some(Languages::from_sizes(sizes)))
+            })
+            .await
+    }
+}
@@ -0,0 +1,562 @@
+//! Access to the GitHub API through octocrab, behind the [`IssueSource`]
+//! trait so the import pipeline can be driven by other implementations.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use lambda_http::tracing::debug;
+use octocrab::models::issues::{Comment, Issue};
+use octocrab::models::repos::Release;
+use octocrab::models::AuthorAssociation;
+use octocrab::{FromResponse, Octocrab, Page};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+use crate::backfill::{ClosedIssue, ClosedIssuePage};
+use crate::breaker::{CircuitBreaker, Upstream};
+use crate::classify;
+use crate::contributors::{self, Contributor, ContributorStats};
+use crate::error::ImportError;
+use crate::forge::Forge;
+use crate::friendliness::Onboarding;
+use crate::health::{self, RepositoryActivity};
+use crate::languages::Languages;
+use crate::metrics;
+use crate::model::{self, ForgeKind, KudosIssue, RepoInfo, WarningReason};
+use crate::rate_limit;
+use crate::report;
+use crate::secrets;
+use crate::telemetry::ISSUE_EVENTS;
+
+/// Whether a repository can be imported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryStatus {
+    Active(RepositoryMetadata),
+    Archived,
+    NotFound,
+}
+
+/// What the forge says about an active repository, beyond its issues.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepositoryMetadata {
+    /// Lowercase topics, empty on forges without any.
+    pub topics: Vec<String>,
+    pub license: License,
+}
+
+impl RepositoryMetadata {
+    pub fn new(topics: Vec<String>) -> Self {
+        RepositoryMetadata {
+            topics: topics
+                .iter()
+                .map(|topic| topic.trim().to_lowercase())
+                .collect(),
+            license: License::Unknown,
+        }
+    }
+}
+
+/// A repository's license, as far as its forge tells.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum License {
+    /// The forge doesn't report licenses.
+    #[default]
+    Unknown,
+    /// The forge found no license in the repository.
+    Unlicensed,
+    /// SPDX identifier of the license, e.g. `MIT`.
+    Spdx(String),
+}
+
+impl License {
+    pub fn spdx_id(&self) -> Option<&str> {
+        match self {
+            License::Spdx(id) => Some(id),
+            License::Unknown | License::Unlicensed => None,
+        }
+    }
+}
+
+/// One page of a repository's open issues.
+#[derive(Debug)]
+pub struct IssuePage {
+    pub issues: Vec<KudosIssue>,
+    pub has_next: bool,
+    /// Pull requests the page listed along its issues, left out of them.
+    pub pull_requests: usize,
+}
+
+/// A comment on an issue, as much as tells when maintainers replied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssueComment {
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+    /// Whether the author owns, or is a member or collaborator of, the
+    /// repository.
+    pub by_maintainer: bool,
+}
+
+/// Where the import pipeline fetches repositories' issues from.
+#[async_trait]
+pub trait IssueSource: Send + Sync {
+    async fn repository_status(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<RepositoryStatus, ImportError>;
+
+    /// Fetches one page (1-based) of a repository's open issues, pull
+    /// requests excluded.
+    async fn open_issues(&self, repo_info: &RepoInfo, page: u32) -> Result<IssuePage, ImportError>;
+
+    /// Fetches the first comments on one of a repository's issues, oldest
+    /// first. `None` from forges that don't say which authors maintain the
+    /// repository, the default.
+    async fn issue_comments(
+        &self,
+        _repo_info: &RepoInfo,
+        _number: i64,
+    ) -> Result<Option<Vec<IssueComment>>, ImportError> {
+        Ok(None)
+    }
+
+    /// Fetches the recent activity of a repository its health is scored
+    /// from. `None` from forges it isn't fetched from, the default.
+    async fn repository_activity(
+        &self,
+        _repo_info: &RepoInfo,
+    ) -> Result<Option<RepositoryActivity>, ImportError> {
+        Ok(None)
+    }
+
+    /// Fetches the contributor statistics of a repository. `None` from
+    /// forges they aren't fetched from, the default.
+    async fn contributors(
+        &self,
+        _repo_info: &RepoInfo,
+    ) -> Result<Option<ContributorStats>, ImportError> {
+        Ok(None)
+    }
+
+    /// Checks what a repository offers first-time contributors. `None` from
+    /// forges it isn't checked on, the default.
+    async fn onboarding(&self, _repo_info: &RepoInfo) -> Result<Option<Onboarding>, ImportError> {
+        Ok(None)
+    }
+
+    /// Fetches the languages of a repository's code. `None` from forges that
+    /// don't report them, the default.
+    async fn languages(&self, _repo_info: &RepoInfo) -> Result<Option<Languages>, ImportError> {
+        Ok(None)
+    }
+
+    /// Fetches one page (1-based) of the issues a repository closed since
+    /// `since`, see [`crate::backfill`]. `None` from forges they aren't
+    /// fetched from, the default.
+    async fn closed_issues(
+        &self,
+        _repo_info: &RepoInfo,
+        _since: DateTime<Utc>,
+        _page: u32,
+    ) -> Result<Option<ClosedIssuePage>, ImportError> {
+        Ok(None)
+    }
+}
+
+/// [`IssueSource`] backed by the GitHub REST API.
+pub struct GitHubSource {
+    octocrab: Octocrab,
+    breaker: CircuitBreaker,
+}
+
+impl GitHubSource {
+    pub fn new(octocrab: Octocrab) -> Self {
+        GitHubSource {
+            octocrab,
+            breaker: CircuitBreaker::new(Upstream::GitHub),
+        }
+    }
+
+    /// Sends a `GET` to the API, recording the rate-limit budget its
+    /// response reports, which octocrab's typed requests don't expose.
+    async fn get<R: FromResponse>(&self, uri: String) -> octocrab::Result<R> {
+        metrics::count_github_api_call();
+        let response = self.octocrab._get(uri).await?;
+        rate_limit::record(response.headers());
+        R::from_response(octocrab::map_github_error(response).await?).await
+    }
+
+    /// Builds a client authenticated with the configured GitHub token.
+    pub async fn from_config() -> Result<Self, ImportError> {
+        let token = secrets::github_token().await?;
+        let octocrab = Octocrab::builder().personal_token(token).build()?;
+        Ok(GitHubSource::new(octocrab))
+    }
+}
+
+impl Forge for GitHubSource {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::GitHub
+    }
+}
+
+fn is_not_found(error: &octocrab::Error) -> bool {
+    matches!(error, octocrab::Error::GitHub { source, .. } if source.status_code == 404)
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubContributor {
+    login: String,
+    contributions: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubUser {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubCommit {
+    /// `null` when the commit's email isn't tied to any account.
+    author: Option<GitHubUser>,
+    committer: Option<GitHubUser>,
+    #[serde(default)]
+    parents: Vec<IgnoredAny>,
+}
+
+/// The repository's files GitHub's community profile found, `null` when
+/// missing.
+#[derive(Debug, Deserialize)]
+struct CommunityFiles {
+    contributing: Option<serde_json::Value>,
+    issue_template: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommunityProfile {
+    files: CommunityFiles,
+}
+
+/// An issue of GitHub's listing, with the reactions octocrab leaves out.
+#[derive(Debug, Deserialize)]
+struct GitHubIssue {
+    #[serde(flatten)]
+    issue: Issue,
+    reactions: Option<GitHubReactions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubReactions {
+    total_count: u32,
+}
+
+impl From<GitHubIssue> for KudosIssue {
+    fn from(value: GitHubIssue) -> Self {
+        KudosIssue {
+            reactions: value.reactions.map_or(0, |reactions| reactions.total_count),
+            ..KudosIssue::from(value.issue)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubLabel {
+    name: String,
+}
+
+/// Whether GitHub refused a request on a repository without any commit.
+fn is_empty_repository(error: &octocrab::Error) -> bool {
+    matches!(error, octocrab::Error::GitHub { source, .. } if source.status_code == 409)
+}
+
+impl GitHubSource {
+    /// How many of the repository's issues were closed, pull requests
+    /// excluded, as counted by the search API.
+    async fn closed_issue_count(&self, repo_info: &RepoInfo) -> octocrab::Result<u64> {
+        let uri = format!(
+            "/search/issues?q=repo:{}/{}+type:issue+state:closed&per_page=1",
+            repo_info.owner, repo_info.name
+        );
+        let page = self.get::<Page<Issue>>(uri).await?;
+        Ok(page.total_count.unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl IssueSource for GitHubSource {
+    async fn repository_status(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<RepositoryStatus, ImportError> {
+        let uri = format!("/repos/{}/{}", repo_info.owner, repo_info.name);
+        self.breaker
+            .call(async {
+                match self.get::<octocrab::models::Repository>(uri).await {
+                    Ok(repo) if repo.archived == Some(true) => Ok(RepositoryStatus::Archived),
+                    Ok(repo) => Ok(RepositoryStatus::Active(RepositoryMetadata {
+                        license: match repo.license {
+                            Some(license) => License::Spdx(license.spdx_id),
+                            None => License::Unlicensed,
+                        },
+                        ..RepositoryMetadata::new(repo.topics.unwrap_or_default())
+                    })),
+                    Err(e) if is_not_found(&e) => Ok(RepositoryStatus::NotFound),
+                    Err(e) => Err(e.into()),
+                }
+            })
+            .await
+    }
+
+    async fn open_issues(&self, repo_info: &RepoInfo, page: u32) -> Result<IssuePage, ImportError> {
+        let uri = format!(
+            "/repos/{}/{}/issues?state=open&per_page=100&page={}",
+            repo_info.owner, repo_info.name, page
+        );
+        let page = self
+            .breaker
+            .call(async { Ok(self.get::<Page<GitHubIssue>>(uri).await?) })
+            .await?;
+
+        // GitHub returns pull requests from the issues endpoint too.
+        let (pull_requests, issues): (Vec<_>, Vec<_>) = page
+            .items
+            .into_iter()
+            .partition(|issue| issue.issue.pull_request.is_some());
+        let repo_url = repo_info.url();
+        for pull_request in &pull_requests {
+            report::record(
+                &repo_url,
+                pull_request.issue.number as i64,
+                Some(WarningReason::PullRequest),
+            );
+            debug!(
+                target: ISSUE_EVENTS,
+                number = pull_request.issue.number,
+                skip_reason = WarningReason::PullRequest.as_str(),
+                "Issue fetched"
+            );
+        }
+        Ok(IssuePage {
+            has_next: page.next.is_some(),
+            issues: issues.into_iter().map(KudosIssue::from).collect(),
+            pull_requests: pull_requests.len(),
+        })
+    }
+
+    async fn issue_comments(
+        &self,
+        repo_info: &RepoInfo,
+        number: i64,
+    ) -> Result<Option<Vec<IssueComment>>, ImportError> {
+        let uri = format!(
+            "/repos/{}/{}/issues/{}/comments?per_page=100",
+            repo_info.owner, repo_info.name, number
+        );
+        let comments = self
+            .breaker
+            .call(async { Ok(self.get::<Vec<Comment>>(uri).await?) })
+            .await?;
+        Ok(Some(
+            comments
+                .into_iter()
+                .map(|comment| IssueComment {
+                    by_maintainer: matches!(
+                        comment.author_association,
+                        AuthorAssociation::Owner
+                            | AuthorAssociation::Member
+                            | AuthorAssociation::Collaborator
+                    ),
+                    author: comment.user.login,
+                    created_at: comment.created_at,
+                })
+                .collect(),
+        ))
+    }
+
+    async fn repository_activity(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<Option<RepositoryActivity>, ImportError> {
+        let since = (Utc::now() - Duration::days(health::COMMIT_DAYS))
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+        let repo_uri = format!("/repos/{}/{}", repo_info.owner, repo_info.name);
+        let commits_uri = format!(
+            "/repos/{}/{}/commits?since={}&per_page=100",
+            repo_info.owner, repo_info.name, since
+        );
+        let release_uri = format!(
+            "/repos/{}/{}/releases/latest",
+            repo_info.owner, repo_info.name
+        );
+        // Not through the breaker: the search API's own, much lower, rate
+        // limit mustn't stop the imports the breaker guards.
+        let repo = self.get::<octocrab::models::Repository>(repo_uri).await?;
+        let recent_commits = match self.get::<Vec<GitHubCommit>>(commits_uri).await {
+            Ok(commits) => commits.len() as u32,
+            Err(e) if is_empty_repository(&e) => 0,
+            Err(e) => return Err(e.into()),
+        };
+        let last_release_at = match self.get::<Release>(release_uri).await {
+            Ok(release) => release.published_at.or(release.created_at),
+            Err(e) if is_not_found(&e) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(RepositoryActivity {
+            recent_commits,
+            open_issues: repo.open_issues_count.unwrap_or_default().into(),
+            closed_issues: self.closed_issue_count(repo_info).await?,
+            last_release_at,
+        }))
+    }
+
+    async fn contributors(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<Option<ContributorStats>, ImportError> {
+        // Asks for a few more than kept, for the bots left out.
+        let contributors_uri = format!(
+            "/repos/{}/{}/contributors?per_page={}",
+            repo_info.owner,
+            repo_info.name,
+            contributors::TOP_CONTRIBUTORS * 2
+        );
+        let since = (Utc::now() - Duration::days(contributors::RECENT_DAYS))
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+        let commits_uri = format!(
+            "/repos/{}/{}/commits?since={}&per_page=100",
+            repo_info.owner, repo_info.name, since
+        );
+        self.breaker
+            .call(async {
+                // GitHub answers `204 No Content` rather than an empty list
+                // for the contributors of empty repositories: they're told
+                // apart by their commits first.
+                let commits = match self.get::<Vec<GitHubCommit>>(commits_uri).await {
+                    Ok(commits) => commits,
+                    Err(e) if is_empty_repository(&e) => {
+                        return Ok(Some(ContributorStats::default()))
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                let top = self.get::<Vec<GitHubContributor>>(contributors_uri).await?;
+                let mut recent: Vec<&str> = commits
+                    .iter()
+                    .filter_map(|commit| commit.author.as_ref())
+                    .map(|author| author.login.as_str())
+                    .filter(|login| !contributors::is_bot(login))
+                    .collect();
+                recent.sort();
+                recent.dedup();
+                let mut maintainers: Vec<&str> = commits
+                    .iter()
+                    .filter_map(|commit| {
+                        contributors::maintainer(
+                            commit.author.as_ref().map(|author| author.login.as_str()),
+                            commit
+                                .committer
+                                .as_ref()
+                                .map(|committer| committer.login.as_str()),
+                            commit.parents.len() > 1,
+                        )
+                    })
+                    .collect();
+                maintainers.sort();
+                maintainers.dedup();
+                Ok(Some(ContributorStats {
+                    top: top
+                        .into_iter()
+                        .filter(|contributor| !contributors::is_bot(&contributor.login))
+                        .take(contributors::TOP_CONTRIBUTORS)
+                        .map(|contributor| Contributor {
+                            login: contributor.login,
+                            contributions: contributor.contributions,
+                        })
+                        .collect(),
+                    recent_contributors: recent.len() as u32,
+                    active_maintainers: maintainers.len() as u32,
+                }))
+            })
+            .await
+    }
+
+    async fn onboarding(&self, repo_info: &RepoInfo) -> Result<Option<Onboarding>, ImportError> {
+        let repository = format!("/repos/{}/{}", repo_info.owner, repo_info.name);
+        self.breaker
+            .call(async {
+                let profile = self
+                    .get::<CommunityProfile>(format!("{}/community/profile", repository))
+                    .await?;
+                // The profile only knows of a single `ISSUE_TEMPLATE` file,
+                // not of the forms and templates of the directory.
+                let has_issue_templates = profile.files.issue_template.is_some()
+                    || match self
+                        .get::<serde_json::Value>(format!(
+                            "{}/contents/.github/ISSUE_TEMPLATE",
+                            repository
+                        ))
+                        .await
+                    {
+                        Ok(_) => true,
+                        Err(e) if is_not_found(&e) => false,
+                        Err(e) => return Err(e.into()),
+                    };
+                let labels: Vec<String> = self
+                    .get::<Vec<GitHubLabel>>(format!("{}/labels?per_page=100", repository))
+                    .await?
+                    .iter()
+                    .map(|label| model::normalize_label(&label.name))
+                    .collect();
+                Ok(Some(Onboarding {
+                    has_contributing_guide: profile.files.contributing.is_some(),
+                    has_issue_templates,
+                    has_beginner_labels: classify::is_beginner_friendly(&labels),
+                }))
+            })
+            .await
+    }
+
+    async fn languages(&self, repo_info: &RepoInfo) -> Result<Option<Languages>, ImportError> {
+        let uri = format!("/repos/{}/{}/languages", repo_info.owner, repo_info.name);
+        let sizes = self
+            .breaker
+            .call(async { Ok(self.get::<BTreeMap<String, f64>>(uri).await?) })
+            .await?;
+        Ok(Some(Languages::from_sizes(sizes)))
+    }
+
+    async fn closed_issues(
+        &self,
+        repo_info: &RepoInfo,
+        since: DateTime<Utc>,
+        page: u32,
+    ) -> Result<Option<ClosedIssuePage>, ImportError> {
+        // `since` is about updates, which closing an issue is one of: the
+        // issues closed earlier are left out below.
+        let uri = format!(
+            "/repos/{}/{}/issues?state=closed&since={}&per_page=100&page={}",
+            repo_info.owner,
+            repo_info.name,
+            since.to_rfc3339_opts(SecondsFormat::Secs, true),
+            page
+        );
+        let page = self
+            .breaker
+            .call(async { Ok(self.get::<Page<Issue>>(uri).await?) })
+            .await?;
+        Ok(Some(ClosedIssuePage {
+            has_next: page.next.is_some(),
+            issues: page
+                .items
+                .into_iter()
+                .filter(|issue| issue.pull_request.is_none())
+                .filter_map(|issue| {
+                    Some(ClosedIssue {
+                        number: issue.number as i64,
+                        created_at: issue.created_at,
+                        closed_at: issue.closed_at.filter(|closed_at| *closed_at >= since)?,
+                    })
+                })
+                .collect(),
+        }))
+    }
+}
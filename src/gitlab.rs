@@ -0,0 +1,208 @@
+//! Access to the GitLab REST API (v4), for repositories on `gitlab.com` and
+//! self-hosted instances, listed in `GITLAB_HOSTS` or named as GitLab ones
+//! by the payload.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+
+use crate::breaker::{CircuitBreaker, Upstream};
+use crate::config;
+use crate::error::ImportError;
+use crate::forge::{self, Forge};
+use crate::github::{IssuePage, IssueSource, RepositoryMetadata, RepositoryStatus};
+use crate::languages::Languages;
+use crate::model::{ForgeKind, KudosIssue, RepoInfo};
+use crate::review::ReviewState;
+use crate::secrets;
+
+const FORGE: &str = "GitLab";
+
+/// Characters escaped in a project path used as an id, `/` among them.
+const PROJECT_PATH: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.');
+
+#[derive(Debug, Deserialize)]
+struct GitLabProject {
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabUser {
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabIssue {
+    /// Number of the issue within its project, the one shown in urls.
+    iid: i64,
+    title: String,
+    web_url: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    author: GitLabUser,
+    labels: Vec<String>,
+    #[serde(default)]
+    assignees: Vec<GitLabUser>,
+    description: Option<String>,
+    #[serde(default)]
+    upvotes: u32,
+}
+
+impl From<GitLabIssue> for KudosIssue {
+    fn from(value: GitLabIssue) -> Self {
+        KudosIssue {
+            number: value.iid,
+            title: value.title,
+            html_url: value.web_url,
+            issue_created_at: value.created_at,
+            issue_updated_at: value.updated_at,
+            user: value.author.username,
+            labels: value.labels,
+            assignees: value
+                .assignees
+                .into_iter()
+                .map(|assignee| assignee.username)
+                .collect(),
+            body: value.description,
+            excerpt: None,
+            is_beginner_friendly: false,
+            categories: Vec::new(),
+            is_paid: false,
+            reward: None,
+            difficulty: None,
+            issue_type: None,
+            program_tags: Vec::new(),
+            staleness: None,
+            seeking_contributors: false,
+            review_state: ReviewState::Pending,
+            referenced_issues: Vec::new(),
+            reactions: value.upvotes,
+            ranking_score: None,
+        }
+    }
+}
+
+/// [`IssueSource`] backed by the GitLab REST API.
+pub struct GitLabSource {
+    client: reqwest::Client,
+    token: Option<String>,
+    /// Hosts the token is sent to, the configured GitLab instances.
+    hosts: Vec<String>,
+    breaker: CircuitBreaker,
+}
+
+impl GitLabSource {
+    pub fn new(client: reqwest::Client, token: Option<String>, hosts: Vec<String>) -> Self {
+        GitLabSource {
+            client,
+            token,
+            hosts,
+            breaker: CircuitBreaker::new(Upstream::Forge(FORGE)),
+        }
+    }
+
+    /// Builds a client authenticated with the GitLab token, when there's one,
+    /// on the configured hosts.
+    pub async fn from_config() -> Result<Self, ImportError> {
+        let token = secrets::gitlab_token().await?;
+        let hosts = config::get().gitlab_hosts.clone();
+        Ok(GitLabSource::new(forge::client()?, token, hosts))
+    }
+
+    /// The API url of the repository's project, identified by its path.
+    fn project_url(repo_info: &RepoInfo) -> String {
+        let path = format!("{}/{}", repo_info.owner, repo_info.name);
+        format!(
+            "https://{}/api/v4/projects/{}",
+            repo_info.host,
+            utf8_percent_encode(&path, PROJECT_PATH)
+        )
+    }
+
+    /// A request to the repository's instance, never sending the token to
+    /// hosts only the payload names as GitLab ones.
+    fn get(&self, repo_info: &RepoInfo, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(url);
+        match &self.token {
+            Some(token) if self.hosts.contains(&repo_info.host) => request.bearer_auth(token),
+            _ => request,
+        }
+    }
+}
+
+impl Forge for GitLabSource {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::GitLab
+    }
+}
+
+#[async_trait]
+impl IssueSource for GitLabSource {
+    async fn repository_status(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<RepositoryStatus, ImportError> {
+        self.breaker
+            .call(async {
+                let url = GitLabSource::project_url(repo_info);
+                match forge::send(FORGE, self.get(repo_info, &url)).await {
+                    Ok(response) => {
+                        let project: GitLabProject = forge::json(FORGE, response).await?;
+                        Ok(if project.archived {
+                            RepositoryStatus::Archived
+                        } else {
+                            RepositoryStatus::Active(RepositoryMetadata::new(project.topics))
+                        })
+                    }
+                    Err(e) if forge::is_not_found(&e) => Ok(RepositoryStatus::NotFound),
+                    Err(e) => Err(e),
+                }
+            })
+            .await
+    }
+
+    async fn open_issues(&self, repo_info: &RepoInfo, page: u32) -> Result<IssuePage, ImportError> {
+        self.breaker
+            .call(async {
+                let url = format!("{}/issues", GitLabSource::project_url(repo_info));
+                let request = self.get(repo_info, &url).query(&[
+                    ("state", "opened".to_string()),
+                    ("per_page", "100".to_string()),
+                    ("page", page.to_string()),
+                ]);
+                let response = forge::send(FORGE, request).await?;
+
+                // GitLab leaves `x-next-page` empty on the last page.
+                let has_next = response
+                    .headers()
+                    .get("x-next-page")
+                    .and_then(|value| value.to_str().ok())
+                    .is_some_and(|value| !value.trim().is_empty());
+                let issues: Vec<GitLabIssue> = forge::json(FORGE, response).await?;
+                Ok(IssuePage {
+                    issues: issues.into_iter().map(KudosIssue::from).collect(),
+                    has_next,
+                    pull_requests: 0,
+                })
+            })
+            .await
+    }
+
+    async fn languages(&self, repo_info: &RepoInfo) -> Result<Option<Languages>, ImportError> {
+        self.breaker
+            .call(async {
+                let url = format!("{}/languages", GitLabSource::project_url(repo_info));
+                let response = forge::send(FORGE, self.get(repo_info, &url)).await?;
+                // Percentages of the code by language.
+                let sizes: BTreeMap<String, f64> = forge::json(FORGE, response).await?;
+                Ok(Some(Languages::from_sizes(sizes)))
+            })
+            .await
+    }
+}
@@ -0,0 +1,495 @@
+//! HTTP routing and request/response handling for the Lambda function.
+
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+use flate2::{write::GzEncoder, Compression};
+use lambda_http::{
+    http::{
+        header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, VARY},
+        Method,
+    },
+    tracing::{error, info, info_span, Instrument},
+    Body, Error, Request, RequestExt, Response,
+};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::classify::{Difficulty, IssueType};
+use crate::error::ImportError;
+use crate::fanout::{fan_out, fanout_queue};
+use crate::flags;
+use crate::forge::ForgeRegistry;
+use crate::import;
+use crate::model::{normalize_label, IssueCuration, IssueReview, Page, Pagination};
+use crate::reporting;
+use crate::review::ReviewState;
+use crate::store::{IssueFilter, IssueSort, PgStore, Store};
+use crate::validation::parse_project;
+
+/// Bodies smaller than this are sent as-is, compressing them isn't worth it.
+const GZIP_MIN_BYTES: usize = 1024;
+
+/// Headers a caller may use to pass its own request id, in order of preference.
+const REQUEST_ID_HEADERS: [&str; 2] = ["x-request-id", "x-correlation-id"];
+
+/// Caller-supplied ids longer than this are ignored and replaced by a generated one.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Default and maximum page sizes of listing endpoints.
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 100;
+
+/// Returns the header name and id to correlate this request with, generating
+/// a fresh id when the caller didn't send a usable one.
+fn request_id(event: &Request) -> (&'static str, String) {
+    REQUEST_ID_HEADERS
+        .iter()
+        .find_map(|&name| {
+            let value = event.headers().get(name)?.to_str().ok()?.trim();
+            (!value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN)
+                .then(|| (name, value.to_string()))
+        })
+        .unwrap_or_else(|| (REQUEST_ID_HEADERS[0], Uuid::new_v4().to_string()))
+}
+
+/// Whether the client advertised gzip support, honoring `gzip;q=0` opt-outs.
+/// An explicit `gzip` entry overrides `*`, per RFC 9110.
+fn accepts_gzip(event: &Request) -> bool {
+    let encodings: Vec<(&str, f32)> = event
+        .headers()
+        .get_all(ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|encoding| {
+            let mut parts = encoding.split(';').map(str::trim);
+            let coding = parts.next().unwrap_or_default();
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (coding, quality)
+        })
+        .collect();
+    let quality = |matches: fn(&str) -> bool| {
+        encodings
+            .iter()
+            .find(|(coding, _)| matches(coding))
+            .map(|&(_, quality)| quality)
+    };
+    quality(|coding| coding.eq_ignore_ascii_case("gzip"))
+        .or_else(|| quality(|coding| coding == "*"))
+        .is_some_and(|quality| quality > 0.0)
+}
+
+fn gzip(body: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body)?;
+    Ok(encoder.finish()?)
+}
+
+/// Builds a response, gzip-compressing the body when the client accepts it
+/// and the payload is large enough to benefit.
+fn build_response(
+    event: &Request,
+    status: u16,
+    content_type: &str,
+    body: String,
+) -> Result<Response<Body>, Error> {
+    let builder = Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, content_type)
+        .header(VARY, "accept-encoding");
+
+    let resp = if body.len() >= GZIP_MIN_BYTES && accepts_gzip(event) {
+        builder
+            .header(CONTENT_ENCODING, "gzip")
+            .body(Body::Binary(gzip(body.as_bytes())?))
+    } else {
+        builder.body(Body::Text(body))
+    }
+    .map_err(Box::new)?;
+    Ok(resp)
+}
+
+fn json_response(
+    event: &Request,
+    status: u16,
+    body: &impl Serialize,
+) -> Result<Response<Body>, Error> {
+    build_response(
+        event,
+        status,
+        "application/json",
+        serde_json::to_string(body)?,
+    )
+}
+
+fn error_response(event: &Request, status: u16, message: &str) -> Result<Response<Body>, Error> {
+    json_response(event, status, &json!({ "error": message }))
+}
+
+/// Returns the query string parameter `key`, if present.
+fn query_param<'a>(event: &'a Request, key: &str) -> Option<&'a str> {
+    event
+        .query_string_parameters_ref()
+        .and_then(|params| params.first(key))
+}
+
+/// Parses an optional query string parameter, describing the problem on failure.
+fn parse_param<T: std::str::FromStr>(event: &Request, key: &str) -> Result<Option<T>, String> {
+    query_param(event, key)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid value `{}` for `{}`", value, key))
+        })
+        .transpose()
+}
+
+/// Parses an optional query string parameter taking one of the names
+/// `from_name` knows, describing the problem on failure.
+fn named_param<T>(
+    event: &Request,
+    key: &str,
+    from_name: impl Fn(&str) -> Option<T>,
+) -> Result<Option<T>, String> {
+    query_param(event, key)
+        .map(|value| {
+            from_name(value).ok_or_else(|| format!("Invalid value `{}` for `{}`", value, key))
+        })
+        .transpose()
+}
+
+fn pagination(event: &Request) -> Result<Pagination, String> {
+    let page = parse_param(event, "page")?.unwrap_or(1);
+    let per_page = parse_param(event, "per_page")?.unwrap_or(DEFAULT_PER_PAGE);
+    if page < 1 || !(1..=MAX_PER_PAGE).contains(&per_page) {
+        return Err(format!(
+            "`page` must be at least 1 and `per_page` between 1 and {}",
+            MAX_PER_PAGE
+        ));
+    }
+    Ok(Pagination { page, per_page })
+}
+
+/// `GET /projects/{slug}/issues`, optionally filtered by `label`, `repository`
+/// (slug), `since` (RFC 3339, matched against the issue creation date),
+/// `beginner_friendly` and `paid` (`true` or `false`), `category` (a
+/// `category:value` tag), `difficulty` (`easy`, `medium` or `hard`) and
+/// `type` (`bug`, `feature`, `docs`, `test` or `chore`), `program` (e.g.
+/// `hacktoberfest`), `max_staleness` (between 0 and 1) and
+/// `seeking_contributors`, paginated with `page` and `per_page`. Only
+/// approved issues are listed, see [`review_queue`] for the others.
+async fn list_issues(
+    event: &Request,
+    slug: &str,
+    review_state: ReviewState,
+) -> Result<Response<Body>, Error> {
+    let bad_request = ImportError::BadRequest;
+    let since = parse_param::<DateTime<Utc>>(event, "since").map_err(bad_request)?;
+    let beginner_friendly = parse_param::<bool>(event, "beginner_friendly").map_err(bad_request)?;
+    let paid = parse_param::<bool>(event, "paid").map_err(bad_request)?;
+    let seeking_contributors =
+        parse_param::<bool>(event, "seeking_contributors").map_err(bad_request)?;
+    let difficulty = named_param(event, "difficulty", Difficulty::from_db).map_err(bad_request)?;
+    let issue_type = named_param(event, "type", IssueType::from_db).map_err(bad_request)?;
+    let max_staleness = parse_param::<f64>(event, "max_staleness").map_err(bad_request)?;
+    let sort = named_param(event, "sort", IssueSort::from_name).map_err(bad_request)?;
+    if max_staleness.is_some_and(|staleness| !(0.0..=1.0).contains(&staleness)) {
+        let message = "`max_staleness` must be between 0 and 1".to_string();
+        return Err(bad_request(message).into());
+    }
+    let pagination = pagination(event).map_err(bad_request)?;
+
+    let store = PgStore::connect().await?;
+
+    let project_id = store
+        .find_project_id(slug)
+        .await?
+        .ok_or_else(|| ImportError::NotFound("Project".to_string()))?;
+
+    let label = query_param(event, "label").map(normalize_label);
+    let category = query_param(event, "category").map(|category| category.trim().to_lowercase());
+    let program = query_param(event, "program").map(|program| program.trim().to_lowercase());
+    let filter = IssueFilter {
+        label: label.as_deref(),
+        repository: query_param(event, "repository"),
+        since,
+        beginner_friendly,
+        category: category.as_deref(),
+        paid,
+        difficulty,
+        issue_type,
+        program: program.as_deref(),
+        max_staleness,
+        seeking_contributors,
+        review_state,
+        sort: sort.unwrap_or_default(),
+    };
+    let (items, total) = store.list_issues(project_id, &filter, pagination).await?;
+
+    let body = Page {
+        items,
+        page: pagination.page,
+        per_page: pagination.per_page,
+        total,
+    };
+    json_response(event, 200, &body)
+}
+
+/// `GET /projects/{slug}/stats`: issue counts by label and repository, average
+/// issue age and when the project was last synced.
+async fn project_stats(event: &Request, slug: &str) -> Result<Response<Body>, Error> {
+    let store = PgStore::connect().await?;
+
+    let project_id = store
+        .find_project_id(slug)
+        .await?
+        .ok_or_else(|| ImportError::NotFound("Project".to_string()))?;
+
+    let body = store.project_stats(project_id).await?;
+    json_response(event, 200, &body)
+}
+
+/// `PATCH /issues/{id}`: sets an issue's curation flags, `featured`,
+/// `hidden` and `curatorNote`, leaving out the ones not in the body.
+async fn curate_issue(event: &Request, id: &str) -> Result<Response<Body>, Error> {
+    let id = id
+        .parse()
+        .map_err(|_| ImportError::BadRequest("Invalid issue id".to_string()))?;
+    let Body::Text(json) = event.body() else {
+        let message = "Invalid request body type".to_string();
+        return Err(ImportError::BadRequest(message).into());
+    };
+    let curation: IssueCuration =
+        serde_json::from_str(json).map_err(|e| ImportError::BadRequest(e.to_string()))?;
+    if curation.is_empty() {
+        let message = "Expected at least one of `featured`, `hidden` and `curatorNote`";
+        return Err(ImportError::BadRequest(message.to_string()).into());
+    }
+
+    let store = PgStore::connect().await?;
+    let issue = store
+        .curate_issue(id, &curation)
+        .await?
+        .ok_or_else(|| ImportError::NotFound("Issue".to_string()))?;
+    info!(
+        issue_id = issue.id,
+        featured = issue.featured,
+        hidden = issue.hidden,
+        "Issue curated"
+    );
+    json_response(event, 200, &issue)
+}
+
+/// `GET /issues/review?project={slug}`: the project's issues of a
+/// `review_state`, `pending` unless given, filtered like [`list_issues`],
+/// for the Kudos team to review.
+async fn review_queue(event: &Request) -> Result<Response<Body>, Error> {
+    let bad_request = ImportError::BadRequest;
+    let slug = query_param(event, "project")
+        .ok_or_else(|| bad_request("`project` is required".to_string()))?;
+    let review_state =
+        named_param(event, "review_state", ReviewState::from_db).map_err(bad_request)?;
+    list_issues(event, slug, review_state.unwrap_or_default()).await
+}
+
+/// `PATCH /issues/{id}/review`: sets an issue's review state, `pending`,
+/// `approved` or `rejected`.
+async fn review_issue(event: &Request, id: &str) -> Result<Response<Body>, Error> {
+    let id = id
+        .parse()
+        .map_err(|_| ImportError::BadRequest("Invalid issue id".to_string()))?;
+    let Body::Text(json) = event.body() else {
+        let message = "Invalid request body type".to_string();
+        return Err(ImportError::BadRequest(message).into());
+    };
+    let review: IssueReview =
+        serde_json::from_str(json).map_err(|e| ImportError::BadRequest(e.to_string()))?;
+
+    let store = PgStore::connect().await?;
+    let issue = store
+        .review_issue(id, review.state)
+        .await?
+        .ok_or_else(|| ImportError::NotFound("Issue".to_string()))?;
+    info!(
+        issue_id = issue.id,
+        review_state = issue.review_state.as_str(),
+        "Issue reviewed"
+    );
+    json_response(event, 200, &issue)
+}
+
+/// `POST /`: imports a project, or queues it as a job with `?async=true`.
+/// Progress is checkpointed under `resumeToken` (generated and logged when not
+/// given), so an import cut off by the Lambda timeout can be continued by
+/// calling again with the same token, the body being optional then. Imports
+/// about to run out of time stop between repositories and answer `202` with
+/// the token to continue with.
+async fn import_project(event: &Request) -> Result<Response<Body>, Error> {
+    let resume_token = query_param(event, "resumeToken");
+    let (store, checkpoint) = match resume_token {
+        Some(token) => {
+            let store = PgStore::connect().await?;
+            let checkpoint = store.load_checkpoint(token).await?;
+            (Some(store), checkpoint)
+        }
+        None => (None, None),
+    };
+
+    let json_string = match (&checkpoint, event.body()) {
+        (Some(checkpoint), _) => &checkpoint.payload,
+        (None, Body::Text(json)) => json,
+        _ => {
+            let message = "Invalid request body type".to_string();
+            return Err(ImportError::BadRequest(message).into());
+        }
+    };
+
+    let strict = query_param(event, "strict") == Some("true");
+
+    let project = parse_project(json_string, strict).map_err(ImportError::from)?;
+
+    let store = match store {
+        Some(store) => store,
+        None => PgStore::connect().await?,
+    };
+
+    // A resumed import carries on inline, it was started that way.
+    if checkpoint.is_none() {
+        if query_param(event, "async") == Some("true") {
+            let job_id = store.enqueue_job(None, json_string).await?;
+            return json_response(event, 202, &json!({ "job_id": job_id }));
+        }
+        if let Some(queue_url) = fanout_queue(&project) {
+            let body = fan_out(&store, &queue_url, project, None).await?;
+            return json_response(event, 202, &body);
+        }
+    }
+    let source = ForgeRegistry::from_config().await?;
+
+    let token = match resume_token {
+        Some(token) => token.to_string(),
+        None => Uuid::new_v4().to_string(),
+    };
+    if checkpoint.is_some() {
+        info!(resume_token = %token, "Resuming import");
+    } else {
+        store.create_checkpoint(&token, json_string).await?;
+        info!(resume_token = %token, "Import checkpointed");
+    }
+
+    // Absent when not running on Lambda, e.g. behind the local server.
+    let deadline = event.lambda_context_ref().map(|context| context.deadline());
+    let body =
+        import::resume_import(&store, &source, project, &token, checkpoint, deadline).await?;
+    let status = if body.resume_token.is_some() {
+        202
+    } else {
+        200
+    };
+    json_response(event, status, &body)
+}
+
+/// `GET /jobs/{id}`: state of a queued import.
+async fn job_status(event: &Request, id: &str) -> Result<Response<Body>, Error> {
+    let id = id
+        .parse()
+        .map_err(|_| ImportError::BadRequest("Invalid job id".to_string()))?;
+
+    let store = PgStore::connect().await?;
+    let job = store
+        .find_job(id)
+        .await?
+        .ok_or_else(|| ImportError::NotFound("Job".to_string()))?;
+    json_response(event, 200, &job)
+}
+
+async fn feature_flags(event: &Request) -> Result<Response<Body>, Error> {
+    let store = PgStore::connect().await?;
+    let flags: serde_json::Map<String, serde_json::Value> = flags::all(&store)
+        .await
+        .into_iter()
+        .map(|(flag, enabled)| (flag.as_str().to_string(), enabled.into()))
+        .collect();
+    json_response(event, 200, &flags)
+}
+
+/// Answers an error of the pipeline with its status and body. Server-side
+/// failures are logged in full, and reported, since callers only get a
+/// generic message.
+fn import_error_response(event: &Request, e: &ImportError) -> Result<Response<Body>, Error> {
+    if e.status() >= 500 {
+        error!("Request failed: {}", e);
+        reporting::capture(e);
+    } else {
+        info!("Request rejected: {}", e);
+    }
+    json_response(event, e.status(), &e.body())
+}
+
+async fn function_handler(event: Request) -> Result<Response<Body>, Error> {
+    let path = event.uri().path().to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let result = match (event.method(), segments.as_slice()) {
+        (&Method::GET, ["projects", slug, "issues"]) => {
+            list_issues(&event, slug, ReviewState::Approved).await
+        }
+        (&Method::GET, ["projects", slug, "stats"]) => project_stats(&event, slug).await,
+        (&Method::GET, ["jobs", id]) => job_status(&event, id).await,
+        (&Method::GET, ["flags"]) => feature_flags(&event).await,
+        (&Method::GET, ["issues", "review"]) => review_queue(&event).await,
+        (&Method::PATCH, ["issues", id]) => curate_issue(&event, id).await,
+        (&Method::PATCH, ["issues", id, "review"]) => review_issue(&event, id).await,
+        // Imports predate routing, so any POST path is still accepted.
+        (&Method::POST, _) => import_project(&event).await,
+        _ => error_response(&event, 404, "Not found"),
+    };
+
+    // Handlers return pipeline errors as is, they're all answered here.
+    match result.map_err(|e| e.downcast::<ImportError>()) {
+        Err(Ok(e)) => import_error_response(&event, &e),
+        Err(Err(e)) => Err(e),
+        Ok(resp) => Ok(resp),
+    }
+}
+
+/// Runs the handler inside a span carrying the request id and echoes the id
+/// back so callers can match their logs with ours.
+pub async fn handle_request(event: Request) -> Result<Response<Body>, Error> {
+    let (header, request_id) = request_id(&event);
+    let span = info_span!("request", request_id = %request_id);
+
+    let mut resp = function_handler(event)
+        .instrument(span.clone())
+        .await
+        .inspect_err(|e| span.in_scope(|| error!("Request failed: {}", e)))?;
+    resp.headers_mut()
+        .insert(header, HeaderValue::from_str(&request_id)?);
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_gzip_unless_opted_out() {
+        let accepts = |header: &str| {
+            let event = lambda_http::http::Request::builder()
+                .header(ACCEPT_ENCODING, header)
+                .body(Body::Empty)
+                .unwrap();
+            accepts_gzip(&event)
+        };
+        assert!(accepts("gzip, deflate"));
+        assert!(accepts("br;q=1.0, *;q=0.5"));
+        assert!(!accepts("gzip;q=0, *"));
+        assert!(!accepts("*;q=0"));
+        assert!(!accepts("br"));
+    }
+}
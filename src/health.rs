@@ -0,0 +1,125 @@
+//! Health of a repository, to help rank projects on Kudos: recent commit
+//! activity, the share of its issues that get closed and how recent its
+//! last release is, combined into a score from 0 to 1. Refreshed with the
+//! first page of every sync while the `health_scores` flag is on, since it
+//! costs four more GitHub calls per repository, one of them a search.
+
+use chrono::{DateTime, Utc};
+use lambda_http::tracing::warn;
+
+use crate::flags::{self, Flag};
+use crate::github::IssueSource;
+use crate::model::RepoInfo;
+use crate::store::Store;
+
+/// Commits are counted over this many days.
+pub const COMMIT_DAYS: i64 = 90;
+/// Repositories with this many commits over [`COMMIT_DAYS`] or more get the
+/// whole commit part of the score.
+const ACTIVE_COMMITS: f64 = 30.0;
+/// Releases older than this many days count for nothing.
+const RELEASE_DAYS: f64 = 365.0;
+
+const COMMITS_WEIGHT: f64 = 0.4;
+const CLOSED_WEIGHT: f64 = 0.3;
+const RELEASE_WEIGHT: f64 = 0.3;
+
+/// What the forge says about a repository's recent activity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RepositoryActivity {
+    /// Commits of the last [`COMMIT_DAYS`] on the default branch, up to 100.
+    pub recent_commits: u32,
+    /// Open issues, GitHub counting open pull requests along them.
+    pub open_issues: u64,
+    pub closed_issues: u64,
+    pub last_release_at: Option<DateTime<Utc>>,
+}
+
+/// A repository's activity with the score it makes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RepositoryHealth {
+    pub score: f64,
+    pub activity: RepositoryActivity,
+}
+
+/// The health score of a repository with this activity as of `now`, between
+/// 0 and 1 and rounded to two decimals. Repositories without any issue or
+/// release get nothing for them.
+pub fn score(activity: &RepositoryActivity, now: DateTime<Utc>) -> f64 {
+    let commits = (activity.recent_commits as f64 / ACTIVE_COMMITS).min(1.0);
+    let issues = activity.open_issues + activity.closed_issues;
+    let closed = if issues == 0 {
+        0.0
+    } else {
+        activity.closed_issues as f64 / issues as f64
+    };
+    let release = activity.last_release_at.map_or(0.0, |released_at| {
+        let days = (now - released_at).num_seconds().max(0) as f64 / 86_400.0;
+        1.0 - (days / RELEASE_DAYS).min(1.0)
+    });
+    let score = COMMITS_WEIGHT * commits + CLOSED_WEIGHT * closed + RELEASE_WEIGHT * release;
+    (score * 100.0).round() / 100.0
+}
+
+/// Scores and records the health of the repository while the flag is on.
+/// Not worth failing a sync over: errors are only logged.
+pub async fn record(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    repo_info: &RepoInfo,
+    repo_id: i32,
+) {
+    if !flags::is_enabled(store, Flag::HealthScores).await {
+        return;
+    }
+    let result = match source.repository_activity(repo_info).await {
+        Ok(Some(activity)) => {
+            let health = RepositoryHealth {
+                score: score(&activity, Utc::now()),
+                activity,
+            };
+            store.record_health(repo_id, &health).await
+        }
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!("Couldn't score the health of {}: {}", repo_info.url(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn scores_health() {
+        let now = Utc::now();
+        let activity = RepositoryActivity {
+            recent_commits: 15,
+            open_issues: 25,
+            closed_issues: 75,
+            last_release_at: Some(now - Duration::days(73)),
+        };
+        // 0.4 * 0.5 + 0.3 * 0.75 + 0.3 * 0.8
+        assert_eq!(score(&activity, now), 0.67);
+
+        let abandoned = RepositoryActivity {
+            recent_commits: 0,
+            open_issues: 0,
+            closed_issues: 0,
+            last_release_at: Some(now - Duration::days(800)),
+        };
+        assert_eq!(score(&abandoned, now), 0.0);
+
+        let busy = RepositoryActivity {
+            recent_commits: 100,
+            open_issues: 0,
+            closed_issues: 10,
+            last_release_at: Some(now),
+        };
+        assert_eq!(score(&busy, now), 1.0);
+    }
+}
@@ -0,0 +1,888 @@
+//! The import pipeline: creates a project, its repositories and their open
+//! issues. Independent of how it's invoked so the Lambda handler and other
+//! binaries can share it.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Utc;
+use futures::future::join_all;
+use lambda_http::tracing::{debug, field, info, info_span, warn, Instrument, Span};
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::classify;
+use crate::completion::{self, CompletionEvent};
+use crate::config;
+use crate::contributors;
+use crate::duplicates;
+use crate::error::ImportError;
+use crate::filters::IssueFilters;
+use crate::flags::{self, Flag};
+use crate::forge;
+use crate::friendliness;
+use crate::github::{IssueSource, License, RepositoryStatus};
+use crate::health;
+use crate::languages;
+use crate::metrics::ImportMeter;
+use crate::model::{
+    FailureKind, ImportCheckpoint, ImportResponse, ImportWarning, ImportedRepository, Project,
+    RepoInfo, Repository, StoredCheckpoint, SyncCursor, SyncTimings, WarningReason,
+};
+use crate::ranking;
+use crate::report;
+use crate::reporting;
+use crate::responsiveness;
+use crate::slug;
+use crate::store::Store;
+use crate::taxonomy;
+use crate::telemetry::ISSUE_EVENTS;
+
+/// Whether there's too little time left before `deadline` to start syncing
+/// another repository.
+pub(crate) fn near_deadline(deadline: SystemTime, margin: Duration) -> bool {
+    deadline
+        .duration_since(SystemTime::now())
+        .map_or(true, |remaining| remaining < margin)
+}
+
+/// Outcome of syncing one repository's issues.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SyncedRepository {
+    pub issues_fetched: u64,
+    pub issues_upserted: u64,
+    pub issues_closed: u64,
+    pub timings: SyncTimings,
+    /// Whether the sync stopped over its time budget, pages being left.
+    pub stopped_early: bool,
+}
+
+/// Fetches a repository's open issues page by page from where `cursor` left
+/// off, upserts those passing the filters and marks previously imported ones
+/// that are gone as closed. Skipped issues are reported in `warnings`.
+///
+/// The next page is fetched while the last one is upserted. A sync that
+/// wouldn't get through another page within `REPOSITORY_TIME_BUDGET_SECONDS`,
+/// by its slowest page so far, stops there: the cursor is kept for the
+/// scheduled re-sync to continue from, and nothing is marked closed yet.
+///
+/// With a checkpoint token the cursor is saved after every upserted page.
+/// The counts are recorded on the current span as `issues_fetched` and
+/// `issues_inserted`, see [`repository_span`].
+pub async fn sync_repository(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    repo_info: &RepoInfo,
+    filters: &IssueFilters,
+    warnings: &mut Vec<ImportWarning>,
+    mut cursor: SyncCursor,
+    checkpoint: Option<&str>,
+) -> Result<SyncedRepository, ImportError> {
+    let repo_url = repo_info.url();
+    let category_rules = classify::category_rules(store).await;
+    // Read once, after the first page's signals refreshed it.
+    let mut repository_health: Option<Option<f64>> = None;
+    let budget = Some(config::get().repository_time_budget_seconds)
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs);
+    let sync_started = Instant::now();
+    let mut slowest_page = Duration::ZERO;
+    let mut stopped_early = false;
+
+    // Pages are fetched ahead of their upserts, the channel holding the
+    // next one, so the forge and the database work at the same time.
+    let (sender, receiver) = mpsc::channel(1);
+    let first_page = cursor.page + 1;
+    let fetch = async move {
+        let mut number = first_page;
+        loop {
+            let started = Instant::now();
+            let fetched = source
+                .open_issues(repo_info, number)
+                .await
+                .map(|page| (page, started.elapsed().as_millis() as u64));
+            let last = fetched.as_ref().map_or(true, |(page, _)| !page.has_next);
+            // Sending fails once the upserts stopped on an error.
+            if sender.send(fetched).await.is_err() || last {
+                break;
+            }
+            number += 1;
+        }
+    };
+    let upsert = async {
+        // Owned, so that stopping on an error drops it and stops the fetch.
+        let mut receiver = receiver;
+        let mut page_started = Instant::now();
+        while let Some(page) = receiver.recv().await {
+            let (page, fetch_ms) = page?;
+            cursor.fetch_ms += fetch_ms;
+            cursor.issues_fetched += page.issues.len() as u64;
+            cursor.skipped_pull_requests += page.pull_requests;
+            // Repository-level signals, refreshed once per sync. None of them
+            // fails it.
+            if cursor.page == 0 {
+                responsiveness::record(
+                    store,
+                    source,
+                    repo_info,
+                    cursor.repository_id,
+                    &page.issues,
+                )
+                .await;
+                health::record(store, source, repo_info, cursor.repository_id).await;
+                contributors::record(store, source, repo_info, cursor.repository_id).await;
+                friendliness::record(store, source, repo_info, cursor.repository_id).await;
+                languages::record(store, source, repo_info, cursor.repository_id).await;
+            }
+            let health_score = match repository_health {
+                Some(score) => score,
+                None => *repository_health
+                    .insert(ranking::repository_health(store, cursor.repository_id).await),
+            };
+
+            let mut filtered_issues = Vec::new();
+            for mut issue in page.issues {
+                issue.sanitize();
+                issue.classify(&category_rules, &repo_info.program_tags);
+                issue.ranking_score = Some(ranking::score(&issue, health_score, Utc::now()));
+                let reason = filters.skip_reason(&issue, repo_info);
+                report::record(&repo_url, issue.number, reason);
+                debug!(
+                    target: ISSUE_EVENTS,
+                    number = issue.number,
+                    skip_reason = reason.map(WarningReason::as_str),
+                    "Issue fetched"
+                );
+                match reason {
+                    Some(WarningReason::IssueAssigned) => cursor.skipped_assigned += 1,
+                    Some(WarningReason::OutOfScope) => cursor.skipped_out_of_scope += 1,
+                    Some(WarningReason::TitleFiltered) => cursor.skipped_title_filtered += 1,
+                    Some(WarningReason::AgeFiltered) => cursor.skipped_age_filtered += 1,
+                    Some(WarningReason::IssueExcluded) => cursor.skipped_excluded += 1,
+                    Some(_) => cursor.skipped_blocklisted += 1,
+                    None => filtered_issues.push(issue),
+                }
+            }
+            let started = Instant::now();
+            cursor.issues_upserted += store
+                .upsert_issues(cursor.repository_id, &filtered_issues)
+                .await?;
+            cursor.insert_ms += started.elapsed().as_millis() as u64;
+            cursor
+                .open_numbers
+                .extend(filtered_issues.iter().map(|issue| issue.number));
+            cursor.page += 1;
+            let span = Span::current();
+            span.record("issues_fetched", cursor.issues_fetched);
+            span.record(
+                "issues_filtered",
+                cursor.skipped_assigned
+                    + cursor.skipped_blocklisted
+                    + cursor.skipped_out_of_scope
+                    + cursor.skipped_title_filtered
+                    + cursor.skipped_age_filtered
+                    + cursor.skipped_excluded,
+            );
+            span.record("issues_inserted", cursor.issues_upserted);
+
+            if let Some(token) = checkpoint {
+                store.save_sync_cursor(token, &cursor).await?;
+            }
+            if !page.has_next {
+                break;
+            }
+            // Fetching and upserting, waits included.
+            slowest_page = slowest_page.max(page_started.elapsed());
+            page_started = Instant::now();
+            if budget.is_some_and(|budget| sync_started.elapsed() + slowest_page > budget) {
+                stopped_early = true;
+                break;
+            }
+        }
+        Ok::<_, ImportError>(())
+    };
+    let ((), upserted) = tokio::join!(fetch, upsert);
+    upserted?;
+
+    for (reason, count) in [
+        (WarningReason::IssueAssigned, cursor.skipped_assigned),
+        (
+            WarningReason::IssueLabelBlocklisted,
+            cursor.skipped_blocklisted,
+        ),
+        (WarningReason::PullRequest, cursor.skipped_pull_requests),
+        (WarningReason::OutOfScope, cursor.skipped_out_of_scope),
+        (WarningReason::TitleFiltered, cursor.skipped_title_filtered),
+        (WarningReason::AgeFiltered, cursor.skipped_age_filtered),
+        (WarningReason::IssueExcluded, cursor.skipped_excluded),
+    ] {
+        if count > 0 {
+            warnings.push(ImportWarning::issues(&repo_url, reason, count));
+        }
+    }
+
+    let timings = SyncTimings {
+        validation_ms: cursor.validation_ms,
+        ..SyncTimings::new(cursor.fetch_ms, cursor.insert_ms, cursor.issues_upserted)
+    };
+    if stopped_early {
+        info!(
+            pages = cursor.page,
+            slowest_page_ms = slowest_page.as_millis() as u64,
+            "Stopping the sync over its time budget"
+        );
+        let message = format!(
+            "Sync stopped after {} pages, over its time budget; the next re-sync continues it",
+            cursor.page
+        );
+        warnings.push(ImportWarning::repository(
+            &repo_url,
+            WarningReason::SyncPending,
+            message,
+        ));
+        let reason = "Over its time budget";
+        store
+            .mark_sync_pending(cursor.repository_id, reason, Some(&cursor))
+            .await?;
+        return Ok(SyncedRepository {
+            issues_fetched: cursor.issues_fetched,
+            issues_upserted: cursor.issues_upserted,
+            issues_closed: 0,
+            timings,
+            stopped_early,
+        });
+    }
+
+    let issues_closed = store
+        .mark_stale(cursor.repository_id, &cursor.open_numbers)
+        .await?;
+    store.mark_synced(cursor.repository_id).await?;
+    duplicates::mark(store, cursor.repository_id).await;
+
+    Ok(SyncedRepository {
+        issues_fetched: cursor.issues_fetched,
+        issues_upserted: cursor.issues_upserted,
+        issues_closed,
+        timings,
+        stopped_early,
+    })
+}
+
+/// Span of the import of one of a project's repositories, whose counts
+/// [`sync_repository`] fills in.
+fn repository_span(project_slug: &str, repo: &str) -> Span {
+    info_span!(
+        "repository",
+        project_slug,
+        repo,
+        issues_fetched = field::Empty,
+        issues_filtered = field::Empty,
+        issues_inserted = field::Empty,
+    )
+}
+
+/// Logs how the import of a repository ended, within its
+/// [`repository_span`]: with the counts recorded on the span, that one event
+/// describes the repository. `outcome` is `imported`, `skipped`, `deferred`
+/// or `failed`; the `timings` are those of an imported repository.
+fn log_repository_outcome(
+    span: &Span,
+    outcome: &str,
+    warnings: &[ImportWarning],
+    timings: Option<&SyncTimings>,
+    error: Option<&ImportError>,
+) {
+    let skip_reason = warnings
+        .iter()
+        .find(|warning| warning.count.is_none())
+        .map(|warning| warning.reason.as_str());
+    let error = error.map(|e| e.to_string());
+    span.in_scope(|| {
+        info!(
+            outcome,
+            skip_reason,
+            error,
+            validation_ms = timings.map(|timings| timings.validation_ms),
+            fetch_ms = timings.map(|timings| timings.fetch_ms),
+            insert_ms = timings.map(|timings| timings.insert_ms),
+            "Repository import finished"
+        )
+    });
+}
+
+/// Parses a repository's url, or reports in `warnings` why it can't be
+/// imported from.
+fn repository_info(repo: &Repository, warnings: &mut Vec<ImportWarning>) -> Option<RepoInfo> {
+    let message = match RepoInfo::from_repository(repo) {
+        Ok(repo_info) if forge::is_allowed_host(&repo_info.host) => return Some(repo_info),
+        Ok(repo_info) => format!("`{}` isn't an allowed repository host", repo_info.host),
+        Err(e) => format!("Couldn't extract repo info from url: {}", e),
+    };
+    warnings.push(ImportWarning::repository(
+        &repo.url,
+        WarningReason::InvalidUrl,
+        message,
+    ));
+    None
+}
+
+/// Imports one of a project's repositories and its issues. Repositories that
+/// can't be imported are reported in `warnings` and yield `None`.
+pub async fn import_repository(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    project_id: i32,
+    repo: Repository,
+    filters: &IssueFilters,
+    warnings: &mut Vec<ImportWarning>,
+    checkpoint: Option<&str>,
+) -> Result<Option<ImportedRepository>, ImportError> {
+    let started = Instant::now();
+    let Some(mut repo_info) = repository_info(&repo, warnings) else {
+        return Ok(None);
+    };
+
+    match source.repository_status(&repo_info).await? {
+        RepositoryStatus::Active(metadata) => {
+            repo_info.program_tags = classify::programs(&metadata.topics);
+            repo_info.license = metadata.license.spdx_id().map(String::from);
+            if metadata.license == License::Unlicensed && config::get().warn_unlicensed {
+                let message = "Repository has no license".to_string();
+                let reason = WarningReason::RepositoryUnlicensed;
+                warnings.push(ImportWarning::repository(&repo.url, reason, message));
+            }
+        }
+        RepositoryStatus::Archived => {
+            let message = "Repository is archived".to_string();
+            let reason = WarningReason::RepositoryArchived;
+            warnings.push(ImportWarning::repository(&repo.url, reason, message));
+            return Ok(None);
+        }
+        RepositoryStatus::NotFound => {
+            let message = format!("Repository not found on {}", repo_info.host);
+            let reason = WarningReason::RepositoryNotFound;
+            warnings.push(ImportWarning::repository(&repo.url, reason, message));
+            return Ok(None);
+        }
+    }
+    let validation_ms = started.elapsed().as_millis() as u64;
+
+    let repo_id = store
+        .insert_repository(&repo.label, project_id, &repo_info)
+        .await?;
+
+    let cursor = SyncCursor {
+        validation_ms,
+        ..SyncCursor::new(repo_id)
+    };
+    let synced = sync_repository(
+        store, source, &repo_info, filters, warnings, cursor, checkpoint,
+    )
+    .await?;
+
+    Ok(Some(ImportedRepository {
+        id: repo_id,
+        slug: repo.label,
+        url: repo_info.url(),
+        paths: repo_info.paths,
+        label_prefix: repo_info.label_prefix,
+        program_tags: repo_info.program_tags,
+        excluded_issues: repo_info.excluded_issues,
+        license: repo_info.license,
+        issues_imported: synced.issues_upserted,
+        timings: synced.timings,
+    }))
+}
+
+/// Whether the error means the code host, GitHub or another forge, is
+/// unavailable rather than the repository being unusable.
+fn is_forge_outage(error: &ImportError) -> bool {
+    matches!(
+        error.failure_kind(),
+        FailureKind::RateLimited | FailureKind::GitHub | FailureKind::Forge
+    )
+}
+
+/// Creates a repository row without calling GitHub, or reuses the one a
+/// failed attempt already created, and leaves its issue sync pending for the
+/// scheduled re-sync.
+pub async fn defer_repository(
+    store: &dyn Store,
+    project_id: i32,
+    repo: &Repository,
+    warnings: &mut Vec<ImportWarning>,
+    reason: &str,
+) -> Result<Option<ImportedRepository>, ImportError> {
+    let Some(repo_info) = repository_info(repo, warnings) else {
+        return Ok(None);
+    };
+
+    let url = repo_info.url();
+    let repo_id = store
+        .insert_repository(&repo.label, project_id, &repo_info)
+        .await?;
+    store.mark_sync_pending(repo_id, reason, None).await?;
+
+    let message = "GitHub is unavailable, issues will be imported by the next re-sync".to_string();
+    warnings.push(ImportWarning::repository(
+        &url,
+        WarningReason::SyncPending,
+        message,
+    ));
+    Ok(Some(ImportedRepository {
+        id: repo_id,
+        slug: repo.label.clone(),
+        url,
+        paths: repo_info.paths,
+        label_prefix: repo_info.label_prefix,
+        program_tags: repo_info.program_tags,
+        excluded_issues: repo_info.excluded_issues,
+        license: repo_info.license,
+        issues_imported: 0,
+        timings: SyncTimings::default(),
+    }))
+}
+
+/// Completes and checks a project before it's inserted: gives it a slug,
+/// normalizes its technologies and checks its attributes against the
+/// taxonomy. Values outside it are returned as warnings, or rejected with
+/// `TAXONOMY_STRICT`, along the payload's warnings (see [`Project::warnings`]).
+pub async fn prepare_project(
+    store: &dyn Store,
+    project: &mut Project,
+) -> Result<Vec<ImportWarning>, ImportError> {
+    slug::assign(store, project).await?;
+    taxonomy::normalize_technologies(&mut project.attributes.technologies);
+
+    let unknown = taxonomy::unknown_values(store, &project.attributes).await;
+    if config::get().taxonomy_strict && !unknown.is_empty() {
+        return Err(ImportError::Validation(unknown));
+    }
+    let mut warnings = std::mem::take(&mut project.warnings);
+    warnings.extend(unknown.into_iter().map(|error| {
+        ImportWarning::project(&error.field, WarningReason::UnknownAttribute, error.message)
+    }));
+    Ok(warnings)
+}
+
+/// Imports a project, then announces the outcome (see [`completion`]).
+pub async fn import_project(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    project: Project,
+) -> Result<ImportResponse, ImportError> {
+    let slug = project.slug.clone();
+    let meter = ImportMeter::start(None);
+    let mut project_id = None;
+    let result = run_import(store, source, project, None, None, None, &mut project_id).await;
+    let slug = result
+        .as_ref()
+        .map_or(slug, |response| response.slug.clone());
+    meter.emit(&slug, &result);
+    completion::publish(&CompletionEvent::new(&slug, project_id, &result)).await;
+    result
+}
+
+/// Imports a project, saving its progress under `token` as it goes. If a
+/// previous invocation already started the import under that token it is
+/// continued from its last checkpoint instead of starting over.
+///
+/// When a `deadline` is given and gets close, the remaining repositories are
+/// left for a follow-up call: the partial result carries `token` as its
+/// `resume_token` and the checkpoint is kept.
+///
+/// Repositories GitHub fails on are still created, with a pending sync. After
+/// `GITHUB_DEGRADED_AFTER_FAILURES` of them in a row, or always when
+/// `GITHUB_DEGRADED` is set, GitHub isn't called for the remaining ones.
+///
+/// The outcome is announced once the import is over, not after each call.
+pub async fn resume_import(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    project: Project,
+    token: &str,
+    checkpoint: Option<StoredCheckpoint>,
+    deadline: Option<SystemTime>,
+) -> Result<ImportResponse, ImportError> {
+    let slug = project.slug.clone();
+    let resumed = checkpoint
+        .as_ref()
+        .and_then(|checkpoint| checkpoint.state.as_ref());
+    let meter = ImportMeter::start(resumed.map(|state| &state.response));
+    let mut project_id = None;
+    let result = run_import(
+        store,
+        source,
+        project,
+        Some(token),
+        checkpoint,
+        deadline,
+        &mut project_id,
+    )
+    .await;
+    let slug = result
+        .as_ref()
+        .map_or(slug, |response| response.slug.clone());
+    meter.emit(&slug, &result);
+    if let Ok(response) = &result {
+        if response.resume_token.is_some() {
+            return result;
+        }
+        store.delete_checkpoint(token).await?;
+    }
+    completion::publish(&CompletionEvent::new(&slug, project_id, &result)).await;
+    result
+}
+
+/// Prepares and inserts the project of an import, saving it as the import's
+/// progress under `token`, so retries continue with it instead of inserting
+/// it again.
+pub async fn start_import(
+    store: &dyn Store,
+    project: &mut Project,
+    token: Option<&str>,
+) -> Result<ImportCheckpoint, ImportError> {
+    let warnings = prepare_project(store, project).await?;
+    let project_id = store.insert_project(project).await?;
+    let state = ImportCheckpoint {
+        response: ImportResponse {
+            project_id,
+            slug: project.slug.clone(),
+            repositories: Vec::new(),
+            total_issues_imported: 0,
+            warnings,
+            issues_skipped: BTreeMap::new(),
+            resume_token: None,
+        },
+        next_repository: 0,
+        imported_ahead: Vec::new(),
+    };
+    if let Some(token) = token {
+        store.save_checkpoint(token, &state).await?;
+    }
+    Ok(state)
+}
+
+/// An import that may be retried, e.g. a job's: its progress is checkpointed
+/// under `token`, the checkpoint being created with its `payload`.
+#[derive(Debug, Clone, Copy)]
+pub struct Retry<'a> {
+    pub token: &'a str,
+    pub payload: &'a str,
+}
+
+impl Retry<'_> {
+    /// The checkpoint of the earlier attempts, `None` on the first one, which
+    /// creates it.
+    pub async fn checkpoint(
+        self,
+        store: &dyn Store,
+    ) -> Result<Option<StoredCheckpoint>, ImportError> {
+        let checkpoint = store.load_checkpoint(self.token).await?;
+        if checkpoint.is_none() {
+            store.create_checkpoint(self.token, self.payload).await?;
+        }
+        Ok(checkpoint)
+    }
+}
+
+/// Runs an import, setting `inserted` to the id of its project as soon as
+/// there's one, for failures to be announced with it.
+///
+/// Repositories imported in parallel are futures of the calling task joined
+/// with `join_all`, not spawned tasks: they borrow the store and source, and
+/// record their issue decisions and error tags in the task's locals (see
+/// [`report`], [`reporting`]), which spawned tasks don't inherit. A panic
+/// fails the import, as it does importing them one by one.
+async fn run_import(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    mut project: Project,
+    token: Option<&str>,
+    checkpoint: Option<StoredCheckpoint>,
+    deadline: Option<SystemTime>,
+    inserted: &mut Option<i32>,
+) -> Result<ImportResponse, ImportError> {
+    let (state, mut cursor) = match checkpoint {
+        Some(checkpoint) => (checkpoint.state, checkpoint.cursor),
+        None => (None, None),
+    };
+
+    let mut state = match state {
+        Some(state) => {
+            // A slug derived by the first call, which later ones don't send.
+            if project.slug.is_empty() {
+                project.slug = state.response.slug.clone();
+            }
+            *inserted = Some(state.response.project_id);
+            state
+        }
+        None => {
+            let state = start_import(store, &mut project, token).await?;
+            *inserted = Some(state.response.project_id);
+            state
+        }
+    };
+    let project_id = state.response.project_id;
+    reporting::set_tag("project_slug", &project.slug);
+
+    let config = config::get();
+    let filters = IssueFilters::new(&config.label_blocklist, &project.filters)
+        .map_err(ImportError::BadRequest)?;
+    let margin = Duration::from_secs(config.import_deadline_margin_seconds);
+    let github_failures = AtomicU32::new(0);
+    let imported_ahead = state.imported_ahead.clone();
+    let repositories = project.links.repository.into_iter().enumerate();
+    let repositories = repositories
+        .skip(state.next_repository)
+        .filter(|(index, _)| !imported_ahead.contains(index));
+    let concurrency = config.import_concurrency.max(1);
+    if concurrency > 1 && flags::is_enabled(store, Flag::ParallelImports).await {
+        // Several syncs would share the checkpoint's page cursor, so none is
+        // saved: a repository cut short starts over when resumed.
+        let semaphore = Semaphore::new(concurrency);
+        let imports = repositories.map(|(index, repo)| {
+            let (semaphore, filters, github_failures) = (&semaphore, &filters, &github_failures);
+            let (slug, cursor) = (&project.slug, cursor.take());
+            async move {
+                let _permit = semaphore.acquire().await.ok()?;
+                if let (Some(_), Some(deadline)) = (token, deadline) {
+                    if near_deadline(deadline, margin) {
+                        return None;
+                    }
+                }
+                let span = repository_span(slug, &repo.url);
+                let imported = import_listed_repository(
+                    store,
+                    source,
+                    project_id,
+                    &repo,
+                    cursor,
+                    filters,
+                    None,
+                    github_failures,
+                    &span,
+                )
+                .await;
+                Some((index, repo.url, imported))
+            }
+        });
+        // Repositories are started in order, so those left over the deadline
+        // come last. They're recorded in order, those imported after one that
+        // failed as imported ahead, so resuming retries the failed one only.
+        let mut failure = None;
+        for imported in join_all(imports).await {
+            let Some((index, url, imported)) = imported else {
+                if failure.is_none() {
+                    stop_import(&mut state, token);
+                }
+                break;
+            };
+            match imported {
+                Ok((imported, warnings)) => {
+                    record_repository(&mut state.response, imported, warnings);
+                    if failure.is_none() {
+                        state.next_repository = index + 1;
+                    } else {
+                        state.imported_ahead.push(index);
+                    }
+                }
+                // The tag can't be set while the imports run, they share it.
+                Err(e) if failure.is_none() => {
+                    reporting::set_tag("repository", &url);
+                    failure = Some(e);
+                    continue;
+                }
+                // Already logged, like the first one.
+                Err(_) => continue,
+            }
+            if let Some(token) = token {
+                store.save_checkpoint(token, &state).await?;
+            }
+        }
+        return match failure {
+            Some(e) => Err(e),
+            None => Ok(state.response),
+        };
+    }
+
+    for (index, repo) in repositories {
+        // Stopping is only useful if there's a checkpoint to continue from.
+        if let (Some(_), Some(deadline)) = (token, deadline) {
+            if near_deadline(deadline, margin) {
+                stop_import(&mut state, token);
+                break;
+            }
+        }
+
+        reporting::set_tag("repository", &repo.url);
+        let span = repository_span(&project.slug, &repo.url);
+        let (imported, warnings) = import_listed_repository(
+            store,
+            source,
+            project_id,
+            &repo,
+            cursor.take(),
+            &filters,
+            token,
+            &github_failures,
+            &span,
+        )
+        .await?;
+        record_repository(&mut state.response, imported, warnings);
+        state.next_repository = index + 1;
+        if let Some(token) = token {
+            store.save_checkpoint(token, &state).await?;
+        }
+    }
+
+    Ok(state.response)
+}
+
+/// Leaves the remaining repositories to a follow-up call, resumed with the
+/// import's token.
+fn stop_import(state: &mut ImportCheckpoint, token: Option<&str>) {
+    info!(
+        resume_token = token,
+        next_repository = state.next_repository,
+        "Stopping import ahead of the invocation deadline"
+    );
+    state.response.resume_token = token.map(str::to_string);
+}
+
+/// Imports one of a project's repositories, continuing its sync from
+/// `cursor` when the previous invocation stopped within it, or defers it
+/// when GitHub is unavailable. Returns it, unless skipped, with its warnings.
+#[allow(clippy::too_many_arguments)]
+async fn import_listed_repository(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    project_id: i32,
+    repo: &Repository,
+    cursor: Option<SyncCursor>,
+    filters: &IssueFilters,
+    token: Option<&str>,
+    github_failures: &AtomicU32,
+    span: &Span,
+) -> Result<(Option<ImportedRepository>, Vec<ImportWarning>), ImportError> {
+    let config = config::get();
+    let mut warnings = Vec::new();
+    let resumed = cursor.and_then(|cursor| Some((RepoInfo::from_repository(repo).ok()?, cursor)));
+    let degraded = config.github_degraded
+        || github_failures.load(Ordering::Relaxed) >= config.github_degraded_after_failures;
+    let result = async {
+        match resumed {
+            _ if degraded => {
+                let reason = "GitHub unavailable";
+                defer_repository(store, project_id, repo, &mut warnings, reason).await
+            }
+            // The repository being synced when the previous invocation
+            // stopped is already inserted, only its remaining pages are left.
+            Some((mut repo_info, cursor)) => {
+                let repo_id = cursor.repository_id;
+                // The payload doesn't carry the topics programs are
+                // tagged from.
+                if let RepositoryStatus::Active(metadata) =
+                    source.repository_status(&repo_info).await?
+                {
+                    repo_info.program_tags = classify::programs(&metadata.topics);
+                }
+                sync_repository(
+                    store,
+                    source,
+                    &repo_info,
+                    filters,
+                    &mut warnings,
+                    cursor,
+                    token,
+                )
+                .await
+                .map(|synced| {
+                    Some(ImportedRepository {
+                        id: repo_id,
+                        slug: repo.label.clone(),
+                        url: repo_info.url(),
+                        paths: repo_info.paths.clone(),
+                        label_prefix: repo_info.label_prefix.clone(),
+                        program_tags: repo_info.program_tags.clone(),
+                        excluded_issues: repo_info.excluded_issues.clone(),
+                        license: repo_info.license.clone(),
+                        issues_imported: synced.issues_upserted,
+                        timings: synced.timings,
+                    })
+                })
+            }
+            None => {
+                import_repository(
+                    store,
+                    source,
+                    project_id,
+                    repo.clone(),
+                    filters,
+                    &mut warnings,
+                    token,
+                )
+                .await
+            }
+        }
+    }
+    .instrument(span.clone())
+    .await;
+    let imported = match result {
+        Ok(imported) => {
+            if !degraded {
+                github_failures.store(0, Ordering::Relaxed);
+            }
+            let outcome = match &imported {
+                _ if warnings
+                    .iter()
+                    .any(|warning| warning.reason == WarningReason::SyncPending) =>
+                {
+                    "deferred"
+                }
+                Some(_) => "imported",
+                None => "skipped",
+            };
+            log_repository_outcome(
+                span,
+                outcome,
+                &warnings,
+                imported.as_ref().map(|imported| &imported.timings),
+                None,
+            );
+            imported
+        }
+        Err(e) if is_forge_outage(&e) => {
+            github_failures.fetch_add(1, Ordering::Relaxed);
+            span.in_scope(|| warn!("GitHub failed, deferring the repository: {}", e));
+            warnings.clear();
+            log_repository_outcome(span, "deferred", &warnings, None, Some(&e));
+            defer_repository(store, project_id, repo, &mut warnings, &e.to_string())
+                .instrument(span.clone())
+                .await?
+        }
+        Err(e) => {
+            log_repository_outcome(span, "failed", &warnings, None, Some(&e));
+            return Err(e);
+        }
+    };
+    Ok((imported, warnings))
+}
+
+/// Adds an imported repository, and its warnings, to the import's response.
+fn record_repository(
+    response: &mut ImportResponse,
+    imported: Option<ImportedRepository>,
+    mut warnings: Vec<ImportWarning>,
+) {
+    for warning in &warnings {
+        if let Some(count) = warning.count {
+            *response.issues_skipped.entry(warning.reason).or_default() += count;
+        }
+    }
+    response.warnings.append(&mut warnings);
+    if let Some(imported) = imported {
+        response.total_issues_imported += imported.issues_imported;
+        response.repositories.push(imported);
+    }
+}
@@ -0,0 +1,142 @@
+//! Asynchronous imports queued in the `jobs` table. Queued SQS messages and
+//! `?async=true` HTTP imports are written there, then claimed with `FOR
+//! UPDATE SKIP LOCKED` so each runs once even when invocations overlap or
+//! messages are redelivered. Failed jobs are retried with a backoff by the
+//! scheduled run until they run out of attempts.
+
+use chrono::{Duration, Utc};
+use lambda_http::{
+    tracing::{error, info, info_span, warn, Instrument},
+    Error,
+};
+use serde::Serialize;
+
+use crate::config;
+use crate::dlq::classify;
+use crate::forge::ForgeRegistry;
+use crate::import::Retry;
+use crate::model::{ImportResponse, Job, JobState};
+use crate::report::{self, IssueDecision};
+use crate::sqs::{parse_message, process_payload};
+use crate::store::{PgStore, Store};
+
+/// Detailed report of a done job, see [`crate::report`].
+#[derive(Debug, Serialize)]
+struct JobReport<'a> {
+    job_id: i64,
+    result: &'a ImportResponse,
+    issues: Vec<IssueDecision>,
+}
+
+/// Writes the report of a job that imported a project, when there's a bucket
+/// for them, returning its key. A report that can't be written is only
+/// logged: the import itself is done.
+async fn write_report(
+    job: &Job,
+    result: &ImportResponse,
+    issues: Vec<IssueDecision>,
+) -> Option<String> {
+    let bucket = report::bucket()?;
+    let report = JobReport {
+        job_id: job.id,
+        result,
+        issues,
+    };
+    let name = format!("jobs/{}.report.json", job.id);
+    match report::write(&report::client().await, &bucket, &name, &report).await {
+        Ok(key) => Some(key),
+        Err(e) => {
+            warn!("Couldn't write the job's report: {}", e);
+            None
+        }
+    }
+}
+
+/// Runs a claimed job and records its outcome, returning the job's new state.
+/// Only failing to record the outcome is an error.
+pub async fn run_job(
+    store: &PgStore,
+    source: &ForgeRegistry,
+    job: &Job,
+) -> Result<JobState, Error> {
+    // Retries continue from the checkpoint of the earlier attempts.
+    let token = format!("job-{}", job.id);
+    let retry = Retry {
+        token: &token,
+        payload: &job.payload,
+    };
+    let (result, issues) = report::collect(async {
+        match parse_message(&job.payload) {
+            Ok(payload) => process_payload(payload, store, source, Some(retry))
+                .await
+                .map_err(|e| (e.to_string(), classify(&e).is_transient())),
+            Err(problem) => Err((problem, false)),
+        }
+    })
+    .await;
+
+    let (error, transient) = match result {
+        Ok(result) => {
+            let report_key = match &result {
+                Some(result) => write_report(job, result, issues).await,
+                None => None,
+            };
+            store.complete_job(job.id, report_key.as_deref()).await?;
+            info!(attempts = job.attempts, report_key, "Job done");
+            return Ok(JobState::Done);
+        }
+        Err(failure) => failure,
+    };
+
+    let max_attempts = config::get().job_max_attempts;
+    // Back off exponentially: 2, 4, 8... minutes.
+    let retry_at = (transient && job.attempts < max_attempts)
+        .then(|| Utc::now() + Duration::minutes(2_i64.pow(job.attempts.clamp(1, 10) as u32)));
+    store.fail_job(job.id, &error, retry_at).await?;
+
+    error!(
+        attempts = job.attempts,
+        retrying = retry_at.is_some(),
+        "Job failed: {}",
+        error
+    );
+    Ok(if retry_at.is_some() {
+        JobState::Queued
+    } else {
+        JobState::Failed
+    })
+}
+
+/// Outcome of a scheduled run over the queued jobs.
+#[derive(Debug, Default, Serialize)]
+pub struct JobsSummary {
+    pub jobs_done: usize,
+    pub jobs_retried: usize,
+    pub jobs_failed: usize,
+}
+
+/// Claims and runs up to `JOB_BATCH_SIZE` jobs due to run.
+pub async fn drain_jobs() -> Result<JobsSummary, Error> {
+    let store = PgStore::connect().await?;
+    let source = ForgeRegistry::from_config().await?;
+
+    let jobs = store.claim_jobs(config::get().job_batch_size).await?;
+
+    let mut summary = JobsSummary::default();
+    for job in &jobs {
+        let span = info_span!("job", job_id = job.id);
+        match run_job(&store, &source, job).instrument(span).await? {
+            JobState::Done => summary.jobs_done += 1,
+            JobState::Queued => summary.jobs_retried += 1,
+            _ => summary.jobs_failed += 1,
+        }
+    }
+
+    info!(
+        jobs_done = summary.jobs_done,
+        jobs_retried = summary.jobs_retried,
+        jobs_failed = summary.jobs_failed,
+        "Queued jobs processed"
+    );
+    Ok(summary)
+}
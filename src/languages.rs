@@ -0,0 +1,109 @@
+//! Languages of a repository, as its forge detects them from its files: the
+//! main one and the share of the code in each, so a project's technology
+//! facets can be checked against what its repositories contain. Fetched with
+//! the first page of every sync.
+//!
+//! Projects imported without technologies get their repositories' main
+//! languages, normalized through `TECHNOLOGY_ALIASES`.
+
+use std::collections::BTreeMap;
+
+use lambda_http::tracing::warn;
+
+use crate::github::IssueSource;
+use crate::model::RepoInfo;
+use crate::store::Store;
+use crate::taxonomy;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Languages {
+    /// The language with the most code.
+    pub primary: Option<String>,
+    /// Percentage of the code in each language, rounded to one decimal.
+    pub breakdown: BTreeMap<String, f64>,
+}
+
+impl Languages {
+    /// The languages of a repository from the amount of code in each, in
+    /// any unit: bytes on GitHub and Gitea, percentages on GitLab.
+    pub fn from_sizes(sizes: BTreeMap<String, f64>) -> Self {
+        let total: f64 = sizes.values().sum();
+        if total <= 0.0 {
+            return Languages::default();
+        }
+        // The first of the largest, by name, for ties.
+        let primary = sizes
+            .iter()
+            .fold(
+                None,
+                |largest: Option<(&String, f64)>, (language, size)| match largest {
+                    Some((_, largest_size)) if largest_size >= *size => largest,
+                    _ => Some((language, *size)),
+                },
+            )
+            .map(|(language, _)| language.clone());
+        let breakdown = sizes
+            .into_iter()
+            .map(|(language, size)| (language, (size / total * 1000.0).round() / 10.0))
+            .collect();
+        Languages { primary, breakdown }
+    }
+}
+
+/// Fetches and records the languages of the repository, adding its main
+/// one to its project's technologies when they come from languages. Not
+/// worth failing a sync over: errors are only logged.
+pub async fn record(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    repo_info: &RepoInfo,
+    repo_id: i32,
+) {
+    let languages = match source.languages(repo_info).await {
+        Ok(Some(languages)) => languages,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Couldn't fetch the languages of {}: {}", repo_info.url(), e);
+            return;
+        }
+    };
+    let mut technologies: Vec<String> = languages.primary.iter().cloned().collect();
+    taxonomy::normalize_technologies(&mut technologies);
+    let result = match store.record_languages(repo_id, &languages).await {
+        Ok(()) => match technologies.first() {
+            Some(technology) => store.backfill_technology(repo_id, technology).await,
+            None => Ok(()),
+        },
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(
+            "Couldn't record the languages of {}: {}",
+            repo_info.url(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaks_languages_down() {
+        let sizes = BTreeMap::from([
+            ("Rust".to_string(), 9_000.0),
+            ("Shell".to_string(), 750.0),
+            ("Dockerfile".to_string(), 250.0),
+        ]);
+        let languages = Languages::from_sizes(sizes);
+        assert_eq!(languages.primary.as_deref(), Some("Rust"));
+        assert_eq!(languages.breakdown["Rust"], 90.0);
+        assert_eq!(languages.breakdown["Shell"], 7.5);
+        assert_eq!(languages.breakdown["Dockerfile"], 2.5);
+
+        let tied = BTreeMap::from([("Go".to_string(), 50.0), ("C".to_string(), 50.0)]);
+        assert_eq!(Languages::from_sizes(tied).primary.as_deref(), Some("C"));
+        assert_eq!(Languages::from_sizes(BTreeMap::new()), Languages::default());
+    }
+}
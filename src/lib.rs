@@ -0,0 +1,57 @@
+//! Imports Kudos projects, their GitHub repositories and open issues into
+//! the Kudos database. The Lambda binary in `main.rs` is a thin adapter over
+//! [`events::handle_event`], which dispatches HTTP requests to
+//! [`handler::handle_request`]; the pipeline itself lives in [`import`].
+
+pub mod aws;
+pub mod backfill;
+pub mod bitbucket;
+pub mod breaker;
+pub mod classify;
+pub mod completion;
+pub mod config;
+pub mod contributors;
+pub mod dlq;
+pub mod duplicates;
+pub mod error;
+pub mod events;
+pub mod fanout;
+pub mod filters;
+pub mod flags;
+pub mod forge;
+pub mod friendliness;
+pub mod gitea;
+pub mod github;
+pub mod gitlab;
+pub mod handler;
+pub mod health;
+pub mod import;
+pub mod jobs;
+pub mod languages;
+pub mod metrics;
+pub mod model;
+pub mod panics;
+pub mod ranking;
+pub mod rate_limit;
+pub mod redact;
+pub mod report;
+pub mod reporting;
+pub mod responsiveness;
+pub mod resync;
+pub mod review;
+pub mod s3;
+pub mod sanitize;
+pub mod secrets;
+#[cfg(feature = "local-server")]
+pub mod server;
+pub mod slug;
+pub mod sourcehut;
+pub mod sqs;
+pub mod step_functions;
+pub mod store;
+pub mod taxonomy;
+pub mod telemetry;
+#[cfg(test)]
+mod test_db;
+pub mod validation;
+pub mod xray;
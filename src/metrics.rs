@@ -0,0 +1,291 @@
+//! CloudWatch metrics, written to stdout in the Embedded Metric Format (EMF)
+//! so CloudWatch extracts them from the function's logs, without API calls
+//! or log parsing. Import metrics are also exported over OTLP when that's
+//! set up, see [`crate::telemetry`], and are totalled for the local server's
+//! Prometheus `/metrics` (see [`prometheus`]).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+use crate::config;
+use crate::error::ImportError;
+use crate::model::{ImportResponse, SyncTimings, WarningReason};
+
+/// Requests sent to the GitHub API by this execution environment.
+static GITHUB_API_CALLS: AtomicU64 = AtomicU64::new(0);
+
+pub fn count_github_api_call() {
+    GITHUB_API_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn github_api_calls() -> u64 {
+    GITHUB_API_CALLS.load(Ordering::Relaxed)
+}
+
+/// Totals of the import calls measured by this process, see [`ImportMeter`].
+static IMPORTS_SUCCEEDED: AtomicU64 = AtomicU64::new(0);
+static IMPORTS_FAILED: AtomicU64 = AtomicU64::new(0);
+static ISSUES_IMPORTED: AtomicU64 = AtomicU64::new(0);
+static REPOS_FAILED: AtomicU64 = AtomicU64::new(0);
+static IMPORT_DURATION_MS: AtomicU64 = AtomicU64::new(0);
+static FETCH_DURATION_MS: AtomicU64 = AtomicU64::new(0);
+static INSERT_DURATION_MS: AtomicU64 = AtomicU64::new(0);
+
+/// The totals of this process in the Prometheus text exposition format,
+/// durations being summaries in seconds.
+pub fn prometheus() -> String {
+    let total = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    let imports = total(&IMPORTS_SUCCEEDED) + total(&IMPORTS_FAILED);
+    let mut text = format!(
+        "# HELP gh_import_issues_imports_total Import calls, by status.\n\
+         # TYPE gh_import_issues_imports_total counter\n\
+         gh_import_issues_imports_total{{status=\"succeeded\"}} {}\n\
+         gh_import_issues_imports_total{{status=\"failed\"}} {}\n",
+        total(&IMPORTS_SUCCEEDED),
+        total(&IMPORTS_FAILED),
+    );
+    let counters = [
+        ("issues_imported", "Issues upserted.", &ISSUES_IMPORTED),
+        (
+            "repos_failed",
+            "Repositories not imported, with an invalid url, not found or deferred.",
+            &REPOS_FAILED,
+        ),
+        (
+            "github_api_calls",
+            "Requests sent to the GitHub API.",
+            &GITHUB_API_CALLS,
+        ),
+    ];
+    for (name, help, counter) in counters {
+        text.push_str(&format!(
+            "# HELP gh_import_issues_{name}_total {help}\n\
+             # TYPE gh_import_issues_{name}_total counter\n\
+             gh_import_issues_{name}_total {}\n",
+            total(counter),
+        ));
+    }
+    let durations = [
+        (
+            "import",
+            "Duration of the import calls.",
+            &IMPORT_DURATION_MS,
+        ),
+        (
+            "fetch",
+            "Time spent fetching issue pages from the forges.",
+            &FETCH_DURATION_MS,
+        ),
+        (
+            "insert",
+            "Time spent upserting issues.",
+            &INSERT_DURATION_MS,
+        ),
+    ];
+    for (name, help, sum_ms) in durations {
+        text.push_str(&format!(
+            "# HELP gh_import_issues_{name}_duration_seconds {help}\n\
+             # TYPE gh_import_issues_{name}_duration_seconds summary\n\
+             gh_import_issues_{name}_duration_seconds_sum {}\n\
+             gh_import_issues_{name}_duration_seconds_count {imports}\n",
+            total(sum_ms) as f64 / 1000.0,
+        ));
+    }
+    text
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Unit {
+    Count,
+    CountPerSecond,
+    Milliseconds,
+}
+
+impl Unit {
+    fn as_str(self) -> &'static str {
+        match self {
+            Unit::Count => "Count",
+            Unit::CountPerSecond => "Count/Second",
+            Unit::Milliseconds => "Milliseconds",
+        }
+    }
+}
+
+/// Writes one EMF record of `metrics`, without dimensions so they
+/// aggregate over the whole function. `properties` are logged along, for
+/// Logs Insights, without becoming dimensions.
+pub fn emit(metrics: &[(&str, Unit, f64)], properties: &[(&str, &str)]) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64);
+    let definitions: Vec<Value> = metrics
+        .iter()
+        .map(|(name, unit, _)| json!({ "Name": name, "Unit": unit.as_str() }))
+        .collect();
+
+    let mut record = Map::new();
+    record.insert(
+        "_aws".to_string(),
+        json!({
+            "Timestamp": timestamp,
+            "CloudWatchMetrics": [{
+                "Namespace": config::get().metrics_namespace,
+                "Dimensions": [[]],
+                "Metrics": definitions,
+            }],
+        }),
+    );
+    for (name, _, value) in metrics {
+        record.insert(name.to_string(), json!(value));
+    }
+    for (name, value) in properties {
+        record.insert(name.to_string(), json!(value));
+    }
+    // EMF records must be lines of their own, which log events aren't.
+    println!("{}", Value::Object(record));
+}
+
+/// Measures one call of the import pipeline. A resumed import only counts
+/// what happened since its checkpoint, so the metrics of an import's calls
+/// add up to those of the whole import.
+pub struct ImportMeter {
+    started: Instant,
+    github_api_calls: u64,
+    issues_imported: u64,
+    repos_failed: usize,
+    fetch_ms: u64,
+    insert_ms: u64,
+}
+
+/// Time spent fetching and inserting the imported repositories' issues.
+fn sync_ms(response: &ImportResponse) -> (u64, u64) {
+    response
+        .repositories
+        .iter()
+        .fold((0, 0), |(fetch_ms, insert_ms), repository| {
+            (
+                fetch_ms + repository.timings.fetch_ms,
+                insert_ms + repository.timings.insert_ms,
+            )
+        })
+}
+
+/// Repositories the import couldn't import, as opposed to ones skipped on
+/// purpose like archived ones.
+fn repos_failed(response: &ImportResponse) -> usize {
+    response
+        .warnings
+        .iter()
+        .filter(|warning| {
+            matches!(
+                warning.reason,
+                WarningReason::InvalidUrl
+                    | WarningReason::RepositoryNotFound
+                    | WarningReason::SyncPending
+            )
+        })
+        .count()
+}
+
+impl ImportMeter {
+    /// Starts measuring, from the progress of `resumed` if given.
+    pub fn start(resumed: Option<&ImportResponse>) -> Self {
+        let (fetch_ms, insert_ms) = resumed.map_or((0, 0), sync_ms);
+        ImportMeter {
+            fetch_ms,
+            insert_ms,
+            started: Instant::now(),
+            github_api_calls: GITHUB_API_CALLS.load(Ordering::Relaxed),
+            issues_imported: resumed.map_or(0, |response| response.total_issues_imported),
+            repos_failed: resumed.map_or(0, repos_failed),
+        }
+    }
+
+    /// Emits the metrics of the call. A failed call counts one failed
+    /// repository, the one it stopped on.
+    pub fn emit(self, project_slug: &str, result: &Result<ImportResponse, ImportError>) {
+        let (issues_imported, repos_failed, fetch_ms, insert_ms) = match result {
+            Ok(response) => {
+                let (fetch_ms, insert_ms) = sync_ms(response);
+                (
+                    response.total_issues_imported - self.issues_imported,
+                    repos_failed(response) - self.repos_failed,
+                    fetch_ms - self.fetch_ms,
+                    insert_ms - self.insert_ms,
+                )
+            }
+            Err(_) => (0, 1, 0, 0),
+        };
+        let timings = SyncTimings::new(fetch_ms, insert_ms, issues_imported);
+        let github_api_calls = GITHUB_API_CALLS.load(Ordering::Relaxed) - self.github_api_calls;
+        let duration_ms = self.started.elapsed().as_millis() as f64;
+        let status = if result.is_ok() {
+            "succeeded"
+        } else {
+            "failed"
+        };
+
+        let imports = if result.is_ok() {
+            &IMPORTS_SUCCEEDED
+        } else {
+            &IMPORTS_FAILED
+        };
+        for (total, value) in [
+            (imports, 1),
+            (&ISSUES_IMPORTED, issues_imported),
+            (&REPOS_FAILED, repos_failed as u64),
+            (&IMPORT_DURATION_MS, duration_ms as u64),
+            (&FETCH_DURATION_MS, fetch_ms),
+            (&INSERT_DURATION_MS, insert_ms),
+        ] {
+            total.fetch_add(value, Ordering::Relaxed);
+        }
+
+        #[cfg(feature = "otlp")]
+        {
+            use opentelemetry::KeyValue;
+
+            let meter = opentelemetry::global::meter(env!("CARGO_PKG_NAME"));
+            let attributes = [KeyValue::new("status", status)];
+            let counters = [
+                ("issues_imported", issues_imported),
+                ("repos_failed", repos_failed as u64),
+                ("github_api_calls", github_api_calls),
+            ];
+            for (name, value) in counters {
+                meter.u64_counter(name).build().add(value, &attributes);
+            }
+            let durations = [
+                ("import_duration_ms", duration_ms),
+                ("fetch_duration_ms", fetch_ms as f64),
+                ("insert_duration_ms", insert_ms as f64),
+            ];
+            for (name, value) in durations {
+                meter
+                    .f64_histogram(name)
+                    .with_unit("ms")
+                    .build()
+                    .record(value, &attributes);
+            }
+        }
+
+        emit(
+            &[
+                ("issues_imported", Unit::Count, issues_imported as f64),
+                ("repos_failed", Unit::Count, repos_failed as f64),
+                ("github_api_calls", Unit::Count, github_api_calls as f64),
+                ("import_duration_ms", Unit::Milliseconds, duration_ms),
+                ("fetch_duration_ms", Unit::Milliseconds, fetch_ms as f64),
+                ("insert_duration_ms", Unit::Milliseconds, insert_ms as f64),
+                (
+                    "rows_per_second",
+                    Unit::CountPerSecond,
+                    timings.rows_per_second.unwrap_or_default(),
+                ),
+            ],
+            &[("project_slug", project_slug), ("status", status)],
+        );
+    }
+}
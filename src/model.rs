@@ -0,0 +1,1568 @@
+//! Payload, row and response types shared by the handler, the import
+//! pipeline and the database layer.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use octocrab::models::issues::Issue;
+use serde::{Deserialize, Serialize};
+
+use crate::classify::{self, CategoryRule, Difficulty, DifficultyEstimate, IssueType, Reward};
+use crate::duplicates;
+use crate::review::{self, ReviewState};
+use crate::sanitize;
+
+#[derive(Deserialize, Debug)]
+pub struct ProjectLinks {
+    pub repository: Vec<Repository>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectAttributes {
+    pub purposes: Vec<String>,
+    pub stack_levels: Vec<String>,
+    pub technologies: Vec<String>,
+    pub types: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Project {
+    pub name: String,
+    /// Derived from `name` when the payload has none, see [`crate::slug`].
+    #[serde(default)]
+    pub slug: String,
+    pub attributes: ProjectAttributes,
+    pub links: ProjectLinks,
+    #[serde(default)]
+    pub filters: ImportFilters,
+    /// Problems parsing the payload found without rejecting it, returned
+    /// with the import's warnings.
+    #[serde(skip)]
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// The payload's filters of the issues imported, see [`crate::filters`].
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFilters {
+    /// Regular expressions of the titles to import, every title when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_titles: Vec<String>,
+    /// Regular expressions of the titles to leave out, e.g. `^\[Meta\]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_titles: Vec<String>,
+    /// Days since an issue was created for it to be imported, leaving out
+    /// the ones still being triaged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_age_days: Option<u32>,
+    /// Days since an issue was created past which it's left out as unlikely
+    /// to be worked on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+    /// Whether assigned issues, usually taken already, are left out; they
+    /// are unless this is `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_assigned: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Repository {
+    pub label: String,
+    pub url: String,
+    /// Code host serving the repository, detected from the url's host when
+    /// not given, e.g. for self-hosted instances not configured as such.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forge: Option<ForgeKind>,
+    /// Scopes a monorepo's import to the issues with a label starting with
+    /// it, e.g. `A-`, see [`RepoInfo::in_scope`].
+    #[serde(
+        default,
+        rename = "labelPrefix",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub label_prefix: Option<String>,
+    /// Scopes a monorepo's import to the issues labelled for these
+    /// directories, along the one its url points to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Issues never imported, by number or url, e.g. known-bad ones.
+    #[serde(
+        default,
+        rename = "excludedIssueNumbers",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub excluded_issue_numbers: Vec<i64>,
+    #[serde(
+        default,
+        rename = "excludedIssueUrls",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub excluded_issue_urls: Vec<String>,
+}
+
+impl Repository {
+    /// Expands an `owner/name` shorthand url to the repository's canonical
+    /// url, leaving other urls as they are.
+    pub fn expand_shorthand(&mut self) {
+        if is_shorthand(&self.url) {
+            if let Ok(repo_info) = RepoInfo::from_url(&self.url) {
+                self.url = repo_info.url();
+            }
+        }
+    }
+}
+
+/// The code hosts repositories can be imported from.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    Gitea,
+    SourceHut,
+}
+
+impl ForgeKind {
+    pub const ALL: [ForgeKind; 5] = [
+        ForgeKind::GitHub,
+        ForgeKind::GitLab,
+        ForgeKind::Bitbucket,
+        ForgeKind::Gitea,
+        ForgeKind::SourceHut,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "github",
+            ForgeKind::GitLab => "gitlab",
+            ForgeKind::Bitbucket => "bitbucket",
+            ForgeKind::Gitea => "gitea",
+            ForgeKind::SourceHut => "sourcehut",
+        }
+    }
+
+    pub fn from_db(name: &str) -> Option<Self> {
+        ForgeKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == name)
+    }
+}
+
+/// The url of issue `number` of the repository at `repository_url` on
+/// `forge`, e.g. `https://github.com/org/repo/issues/12`, GitLab's
+/// `/-/issues/12`, or `https://todo.sr.ht/~owner/tracker/12` as SourceHut
+/// tickets are right under their tracker. GitHub's form when it's unknown.
+pub fn issue_url(forge: Option<ForgeKind>, repository_url: &str, number: i64) -> String {
+    match forge {
+        Some(ForgeKind::GitLab) => format!("{}/-/issues/{}", repository_url, number),
+        Some(ForgeKind::SourceHut) => format!("{}/{}", repository_url, number),
+        _ => format!("{}/issues/{}", repository_url, number),
+    }
+}
+
+/// Strict mirror of [`Project`] used for `?strict=true` imports: every field of
+/// the payload schema must be known, so typos are rejected instead of ignored.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StrictProject {
+    /// See [`crate::validation::SCHEMA_VERSIONS`].
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: Option<u64>,
+    pub name: String,
+    #[serde(default)]
+    pub slug: String,
+    pub description: Option<String>,
+    pub attributes: StrictProjectAttributes,
+    pub links: StrictProjectLinks,
+    /// Checked key by key by the validator.
+    #[serde(default)]
+    pub filters: ImportFilters,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StrictProjectLinks {
+    pub repository: Vec<StrictRepositoryLink>,
+    #[serde(default)]
+    pub website: Vec<StrictLink>,
+    #[serde(default)]
+    pub docs: Vec<StrictLink>,
+    #[serde(default)]
+    pub explorer: Vec<StrictLink>,
+    #[serde(default)]
+    pub social: Vec<StrictLink>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StrictProjectAttributes {
+    #[serde(default)]
+    pub networks: Vec<String>,
+    pub purposes: Vec<String>,
+    pub stack_levels: Vec<String>,
+    pub technologies: Vec<String>,
+    pub types: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StrictLink {
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StrictRepositoryLink {
+    pub label: String,
+    pub url: String,
+    pub forge: Option<ForgeKind>,
+    #[serde(rename = "labelPrefix")]
+    pub label_prefix: Option<String>,
+    #[serde(default)]
+    pub paths: Vec<String>,
+    #[serde(default, rename = "excludedIssueNumbers")]
+    pub excluded_issue_numbers: Vec<i64>,
+    #[serde(default, rename = "excludedIssueUrls")]
+    pub excluded_issue_urls: Vec<String>,
+}
+
+impl From<StrictProject> for Project {
+    fn from(value: StrictProject) -> Self {
+        Project {
+            name: value.name,
+            slug: value.slug,
+            attributes: ProjectAttributes {
+                purposes: value.attributes.purposes,
+                stack_levels: value.attributes.stack_levels,
+                technologies: value.attributes.technologies,
+                types: value.attributes.types,
+            },
+            links: ProjectLinks {
+                repository: value
+                    .links
+                    .repository
+                    .into_iter()
+                    .map(|link| Repository {
+                        label: link.label,
+                        url: link.url,
+                        forge: link.forge,
+                        label_prefix: link.label_prefix,
+                        paths: link.paths,
+                        excluded_issue_numbers: link.excluded_issue_numbers,
+                        excluded_issue_urls: link.excluded_issue_urls,
+                    })
+                    .collect(),
+            },
+            filters: value.filters,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RepoInfo {
+    /// Host of the forge, lowercase, e.g. `github.com`.
+    pub host: String,
+    /// User or organization, or the groups of a GitLab project, e.g.
+    /// `group/subgroup`.
+    pub owner: String,
+    pub name: String,
+    /// Forge to use instead of the one serving `host`.
+    #[serde(default)]
+    pub forge: Option<ForgeKind>,
+    /// Directories of a monorepo the import is scoped to, e.g. `crates/foo`
+    /// from a `/tree/main/crates/foo` url: only issues carrying a label
+    /// naming one of them are imported. Empty to import every issue.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Lowercase prefix of the labels of the issues the import is scoped
+    /// to, along those of `paths`.
+    #[serde(default)]
+    pub label_prefix: Option<String>,
+    /// Programs the repository takes part in, from its topics, see
+    /// [`classify::programs`].
+    #[serde(default)]
+    pub program_tags: Vec<String>,
+    /// Numbers of the issues never imported, sorted, from the payload's
+    /// `excludedIssueNumbers` and `excludedIssueUrls`.
+    #[serde(default)]
+    pub excluded_issues: Vec<i64>,
+    /// SPDX identifier of the repository's license, as its forge detected
+    /// it at import.
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+/// Why a repository url couldn't be parsed.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum RepoUrlError {
+    #[error("url is empty")]
+    Empty,
+    #[error("url isn't valid: {0}")]
+    Malformed(#[from] url::ParseError),
+    #[error("`{0}` urls aren't supported")]
+    UnsupportedScheme(String),
+    #[error("url has no host")]
+    MissingHost,
+    #[error("url has no repository owner")]
+    MissingOwner,
+    #[error("url has no repository name")]
+    MissingName,
+}
+
+/// Schemes repositories are cloned or browsed with.
+const REPO_URL_SCHEMES: &[&str] = &["https", "http", "ssh", "git", "git+ssh"];
+
+impl RepoInfo {
+    /// Parses the owner and name of a repository from its url: a web url,
+    /// possibly pointing within the repository (e.g. `/tree/main/src`), or a
+    /// clone one, e.g. `git@github.com:owner/name.git`. Urls without a host,
+    /// e.g. `owner/name`, are taken as GitHub ones.
+    ///
+    /// GitLab urls within a project, e.g. `/group/subgroup/project/-/issues`,
+    /// name it up to their `/-/` separator, its groups making up the owner.
+    /// See [`RepoInfo::from_forge_url`] for the bare urls of nested ones.
+    pub fn from_url(url: &str) -> Result<Self, RepoUrlError> {
+        RepoInfo::parse(url, |_| false)
+    }
+
+    /// [`RepoInfo::from_url`], for a repository of `forge`, or else of the
+    /// forge detected from its host: a GitLab url's whole path is then the
+    /// project's, e.g. `https://gitlab.com/group/subgroup/project`.
+    pub fn from_forge_url(url: &str, forge: Option<ForgeKind>) -> Result<Self, RepoUrlError> {
+        let gitlab =
+            |host: &str| forge.or_else(|| crate::forge::detect(host)) == Some(ForgeKind::GitLab);
+        let mut repo_info = RepoInfo::parse(url, gitlab)?;
+        repo_info.forge = forge;
+        Ok(repo_info)
+    }
+
+    /// [`RepoInfo::from_url`], `is_gitlab` telling whether urls of a host
+    /// with more than two path segments are those of nested GitLab projects.
+    fn parse(url: &str, is_gitlab: impl FnOnce(&str) -> bool) -> Result<Self, RepoUrlError> {
+        let url = url.trim();
+        if url.is_empty() {
+            return Err(RepoUrlError::Empty);
+        }
+
+        let parsed = if url.contains("://") {
+            url::Url::parse(url)?
+        } else if let Some((host, path)) = scp_like(url) {
+            url::Url::parse(&format!("ssh://{}/{}", host, path))?
+        } else if is_shorthand(url) {
+            url::Url::parse(&format!("https://github.com/{}", url))?
+        } else {
+            url::Url::parse(&format!("https://{}", url))?
+        };
+        if !REPO_URL_SCHEMES.contains(&parsed.scheme()) {
+            return Err(RepoUrlError::UnsupportedScheme(parsed.scheme().to_string()));
+        }
+        let host = match parsed.host_str() {
+            Some(host) if !host.is_empty() => host.to_lowercase(),
+            _ => return Err(RepoUrlError::MissingHost),
+        };
+
+        // The first two segments name the repository, the others point
+        // within it, but for GitLab projects nested in groups.
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .into_iter()
+            .flatten()
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let project_len = match segments.iter().position(|segment| *segment == "-") {
+            Some(separator) if separator >= 2 => separator,
+            _ if segments.len() > 2 && is_gitlab(&host) => segments.len(),
+            _ => segments.len().min(2),
+        };
+        let (project, rest) = segments.split_at(project_len);
+        let (namespace, name) = match project {
+            [] => return Err(RepoUrlError::MissingOwner),
+            [_] => return Err(RepoUrlError::MissingName),
+            [namespace @ .., name] => (namespace.join("/"), name),
+        };
+        let name = name.strip_suffix(".git").unwrap_or(name);
+        if name.is_empty() {
+            return Err(RepoUrlError::MissingName);
+        }
+        // Forges match owners and names case-insensitively, lowercase keeps a
+        // repository's url the same however the payload spells it.
+        Ok(RepoInfo {
+            host,
+            owner: namespace.to_lowercase(),
+            name: name.to_lowercase(),
+            forge: None,
+            paths: tree_path(rest).into_iter().collect(),
+            label_prefix: None,
+            program_tags: Vec::new(),
+            excluded_issues: Vec::new(),
+            license: None,
+        })
+    }
+
+    /// Parses a payload repository's url, keeping its forge override, scope
+    /// and excluded issues, leaving out the urls not of its issues.
+    pub fn from_repository(repo: &Repository) -> Result<Self, RepoUrlError> {
+        let mut repo_info = RepoInfo::from_forge_url(&repo.url, repo.forge)?;
+        for path in &repo.paths {
+            let path = path.trim().trim_matches('/');
+            if !path.is_empty() && !repo_info.paths.iter().any(|known| known == path) {
+                repo_info.paths.push(path.to_string());
+            }
+        }
+        repo_info.label_prefix = repo
+            .label_prefix
+            .as_deref()
+            .map(|prefix| prefix.trim().to_lowercase())
+            .filter(|prefix| !prefix.is_empty());
+        let urls = repo.excluded_issue_urls.iter();
+        repo_info.excluded_issues = urls
+            .filter_map(|url| repo_info.issue_number(url))
+            .chain(repo.excluded_issue_numbers.iter().copied())
+            .collect();
+        repo_info.excluded_issues.sort_unstable();
+        repo_info.excluded_issues.dedup();
+        Ok(repo_info)
+    }
+
+    /// The number of the issue of this repository `url` points to, e.g. 12
+    /// for `https://github.com/org/repo/issues/12`, GitLab's `/-/issues/12`
+    /// or SourceHut's `https://todo.sr.ht/~owner/tracker/12`. `None` for urls
+    /// of other repositories or pages.
+    pub fn issue_number(&self, url: &str) -> Option<i64> {
+        let issue_repo = RepoInfo::from_url(url).ok()?;
+        if issue_repo.url() != self.url() {
+            return None;
+        }
+        let path = url::Url::parse(url.trim()).ok()?;
+        let segments: Vec<&str> = path
+            .path_segments()?
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let number = match segments.iter().position(|segment| *segment == "issues") {
+            Some(issues) => segments.get(issues + 1),
+            None if self.kind() == Some(ForgeKind::SourceHut) => segments.get(2),
+            None => None,
+        };
+        number?.parse().ok().filter(|&number| number > 0)
+    }
+
+    /// The url of issue `number` of the repository, see [`issue_url`].
+    pub fn issue_url(&self, number: i64) -> String {
+        issue_url(self.kind(), &self.url(), number)
+    }
+
+    /// The forge the repository is imported from: the override, or else the
+    /// one serving its host, if any.
+    pub fn kind(&self) -> Option<ForgeKind> {
+        self.forge.or_else(|| crate::forge::detect(&self.host))
+    }
+
+    /// The canonical url of the repository on its forge.
+    pub fn url(&self) -> String {
+        format!("https://{}/{}/{}", self.host, self.owner, self.name)
+    }
+
+    /// Whether an issue with these labels is within the repository's scope:
+    /// one of them starts with its `label_prefix` or names one of its
+    /// `paths`. Every issue is when it has neither.
+    pub fn in_scope(&self, labels: &[String]) -> bool {
+        if self.paths.is_empty() && self.label_prefix.is_none() {
+            return true;
+        }
+        labels.iter().any(|label| {
+            self.label_prefix
+                .as_deref()
+                .is_some_and(|prefix| label.to_lowercase().starts_with(prefix))
+                || self.paths.iter().any(|path| names_path(label, path))
+        })
+    }
+}
+
+/// The directory a web url points to within a repository, from the segments
+/// following its owner and name: `tree/{branch}/{path}` on GitHub and GitLab
+/// (after a `-`), `src/branch/{branch}/{path}` on Gitea and
+/// `src/{branch}/{path}` on Bitbucket. Branches are taken to be one segment,
+/// as urls don't tell where a `feature/x` branch ends.
+fn tree_path(segments: &[&str]) -> Option<String> {
+    let segments = segments.strip_prefix(&["-"]).unwrap_or(segments);
+    let path = match segments {
+        ["tree", _, path @ ..] => path,
+        ["src", "branch" | "tag" | "commit", _, path @ ..] => path,
+        ["src", _, path @ ..] => path,
+        _ => return None,
+    };
+    let path = path
+        .iter()
+        .map(|segment| percent_encoding::percent_decode_str(segment).decode_utf8_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    (!path.is_empty()).then_some(path)
+}
+
+/// Whether `label` names the component at `path`: its last directory, alone
+/// or after a prefix, e.g. `A-runtime` or `pallet-staking` for
+/// `substrate/frame/staking`.
+fn names_path(label: &str, path: &str) -> bool {
+    let Some(component) = path.rsplit('/').find(|segment| !segment.is_empty()) else {
+        return false;
+    };
+    let label = label.to_lowercase();
+    let component = component.to_lowercase();
+    match label.strip_suffix(&component) {
+        Some("") => true,
+        Some(prefix) => prefix.ends_with(['-', '_', '/', ':', ' ']),
+        None => false,
+    }
+}
+
+/// Whether `url` is an `owner/name` shorthand of a GitHub repository: a url
+/// without a scheme whose first segment isn't a host, as hosts have a dot and
+/// owners can't.
+fn is_shorthand(url: &str) -> bool {
+    let url = url.trim();
+    let first = url.split('/').next().unwrap_or_default();
+    !url.contains("://") && scp_like(url).is_none() && !first.contains('.')
+}
+
+/// Splits an scp-like clone url, `user@host:path`, into its host and path.
+fn scp_like(url: &str) -> Option<(&str, &str)> {
+    let (authority, path) = url.split_once(':')?;
+    let (_, host) = authority.rsplit_once('@')?;
+    if host.is_empty() || authority.contains('/') {
+        return None;
+    }
+    Some((host, path.trim_start_matches('/')))
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct KudosIssue {
+    pub number: i64,
+    pub title: String,
+    pub html_url: String,
+    pub issue_created_at: DateTime<Utc>,
+    pub issue_updated_at: DateTime<Utc>,
+    pub user: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    /// The issue's description, markdown, as the forge returns it.
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Plain-text excerpt of the body, set by [`KudosIssue::sanitize`].
+    #[serde(default)]
+    pub excerpt: Option<String>,
+    /// Set by [`KudosIssue::classify`], see [`crate::classify`].
+    #[serde(default)]
+    pub is_beginner_friendly: bool,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Whether the issue carries a reward, along the amount when it's given.
+    #[serde(default)]
+    pub is_paid: bool,
+    #[serde(default)]
+    pub reward: Option<Reward>,
+    /// `None` until classified.
+    #[serde(default)]
+    pub difficulty: Option<DifficultyEstimate>,
+    #[serde(default)]
+    pub issue_type: Option<IssueType>,
+    /// Programs of the issue's labels and of its repository.
+    #[serde(default)]
+    pub program_tags: Vec<String>,
+    /// `None` until classified, see [`classify::staleness`].
+    #[serde(default)]
+    pub staleness: Option<f64>,
+    /// Whether the issue asks for outside help, see
+    /// [`classify::seeking_contributors`].
+    #[serde(default)]
+    pub seeking_contributors: bool,
+    /// Set by [`KudosIssue::classify`], see [`review::initial_state`].
+    #[serde(default)]
+    pub review_state: ReviewState,
+    /// Urls of the issues the body links to, see
+    /// [`duplicates::referenced_issues`].
+    #[serde(default)]
+    pub referenced_issues: Vec<String>,
+    /// Reactions to the issue, or upvotes on GitLab; 0 from forges that
+    /// don't count them.
+    #[serde(default)]
+    pub reactions: u32,
+    /// `None` until ranked, see [`crate::ranking`].
+    #[serde(default)]
+    pub ranking_score: Option<f64>,
+}
+
+impl From<Issue> for KudosIssue {
+    fn from(value: Issue) -> Self {
+        KudosIssue {
+            number: value.number as i64,
+            title: value.title,
+            html_url: value.html_url.to_string(),
+            issue_created_at: value.created_at,
+            issue_updated_at: value.updated_at,
+            user: value.user.login,
+            labels: value
+                .labels
+                .iter()
+                .map(|label| label.name.clone())
+                .collect::<Vec<String>>(),
+            // `assignee` is the legacy single-assignee field, only used as a fallback.
+            assignees: if value.assignees.is_empty() {
+                value.assignee.into_iter().collect()
+            } else {
+                value.assignees
+            }
+            .into_iter()
+            .map(|assignee| assignee.login)
+            .collect(),
+            body: value.body,
+            excerpt: None,
+            is_beginner_friendly: false,
+            categories: Vec::new(),
+            is_paid: false,
+            reward: None,
+            difficulty: None,
+            issue_type: None,
+            program_tags: Vec::new(),
+            staleness: None,
+            seeking_contributors: false,
+            review_state: ReviewState::Pending,
+            referenced_issues: Vec::new(),
+            reactions: 0,
+            ranking_score: None,
+        }
+    }
+}
+
+/// A label as stored: sanitized (see [`sanitize::line`]) and lowercase, so
+/// `Good  First Issue ` and `good first issue` are the same label.
+pub fn normalize_label(label: &str) -> String {
+    sanitize::line(label, sanitize::MAX_LABEL_CHARS).to_lowercase()
+}
+
+impl KudosIssue {
+    /// Sanitizes the title and labels before they're stored, and derives
+    /// the excerpt from the body, which isn't stored.
+    pub fn sanitize(&mut self) {
+        self.title = sanitize::line(&self.title, sanitize::MAX_TITLE_CHARS);
+        self.normalize_labels();
+        self.excerpt = self
+            .body
+            .as_deref()
+            .map(|body| sanitize::excerpt(body, crate::config::get().excerpt_image_alt))
+            .filter(|excerpt| !excerpt.is_empty());
+    }
+
+    /// Derives the issue's facets from its sanitized labels and its body,
+    /// categorizing them by `rules`, see [`crate::classify`].
+    pub fn classify(&mut self, rules: &[CategoryRule], repo_programs: &[String]) {
+        self.is_beginner_friendly = classify::is_beginner_friendly(&self.labels);
+        self.seeking_contributors =
+            classify::seeking_contributors(&self.labels, self.body.as_deref());
+        self.categories = classify::categories(&self.labels, rules);
+        self.is_paid = classify::is_paid(&self.labels);
+        self.reward = classify::reward(&self.labels, self.body.as_deref()).map(|mut reward| {
+            reward.value = classify::configured_reward_value(&reward);
+            reward
+        });
+        self.is_paid |= self.reward.is_some();
+        self.difficulty = Some(classify::difficulty(
+            &self.labels,
+            &self.title,
+            self.body.as_deref(),
+        ));
+        self.issue_type = classify::issue_type(&self.labels, &self.title);
+        let mut programs = classify::programs(&self.labels);
+        programs.extend(repo_programs.iter().cloned());
+        programs.sort();
+        programs.dedup();
+        self.program_tags = programs;
+        self.staleness = Some(classify::staleness(
+            self.issue_created_at,
+            self.issue_updated_at,
+            Utc::now(),
+        ));
+        self.review_state = review::initial_state(self);
+        self.referenced_issues =
+            duplicates::referenced_issues(&self.html_url, self.body.as_deref());
+    }
+
+    /// Normalizes the labels (see [`normalize_label`]), dropping the empty
+    /// and repeated ones.
+    fn normalize_labels(&mut self) {
+        let mut labels: Vec<String> = Vec::with_capacity(self.labels.len());
+        for label in self.labels.iter().map(|label| normalize_label(label)) {
+            if !label.is_empty() && !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        self.labels = labels;
+    }
+}
+
+/// A repository row created by an import.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportedRepository {
+    pub id: i32,
+    pub slug: String,
+    pub url: String,
+    /// Paths the repository's issues are scoped to, see [`RepoInfo::paths`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_prefix: Option<String>,
+    /// See [`RepoInfo::program_tags`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub program_tags: Vec<String>,
+    /// See [`RepoInfo::excluded_issues`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_issues: Vec<i64>,
+    /// See [`RepoInfo::license`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    pub issues_imported: u64,
+    #[serde(default)]
+    pub timings: SyncTimings,
+}
+
+/// Where a repository's import spent its time, phase by phase, to tune page
+/// sizes and concurrency by and to spot performance regressions.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct SyncTimings {
+    /// Spent checking the repository before syncing it: parsing its url and
+    /// asking the forge whether it exists and isn't archived.
+    #[serde(default)]
+    pub validation_ms: u64,
+    /// Spent fetching issue pages from the forge.
+    pub fetch_ms: u64,
+    /// Spent upserting issues into the database.
+    pub insert_ms: u64,
+    /// Issues upserted per second of `insert_ms`, `None` when nothing was
+    /// upserted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows_per_second: Option<f64>,
+}
+
+impl SyncTimings {
+    pub fn new(fetch_ms: u64, insert_ms: u64, rows: u64) -> Self {
+        SyncTimings {
+            validation_ms: 0,
+            fetch_ms,
+            insert_ms,
+            rows_per_second: (rows > 0).then(|| rows as f64 * 1000.0 / insert_ms.max(1) as f64),
+        }
+    }
+}
+
+/// Why part of a payload was left out of the import.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningReason {
+    InvalidUrl,
+    RepositoryNotFound,
+    RepositoryArchived,
+    IssueAssigned,
+    IssueLabelBlocklisted,
+    /// Listed as an issue by the forge, GitHub listing pull requests along
+    /// issues.
+    PullRequest,
+    SyncPending,
+    /// A project attribute value outside the catalog's taxonomy.
+    UnknownAttribute,
+    /// A repository listed more than once in the payload.
+    DuplicateRepository,
+    /// An issue without a label naming one of the repository's paths or
+    /// starting with its label prefix.
+    OutOfScope,
+    /// An issue whose title the payload's title filters leave out.
+    TitleFiltered,
+    /// An issue younger than the payload's `minAgeDays` or older than its
+    /// `maxAgeDays`.
+    AgeFiltered,
+    /// An issue listed in the repository's `excludedIssueNumbers` or
+    /// `excludedIssueUrls`.
+    IssueExcluded,
+    /// A repository without a license, imported all the same, with
+    /// `WARN_UNLICENSED`.
+    RepositoryUnlicensed,
+}
+
+impl WarningReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WarningReason::InvalidUrl => "invalid_url",
+            WarningReason::RepositoryNotFound => "repository_not_found",
+            WarningReason::RepositoryArchived => "repository_archived",
+            WarningReason::IssueAssigned => "issue_assigned",
+            WarningReason::IssueLabelBlocklisted => "issue_label_blocklisted",
+            WarningReason::PullRequest => "pull_request",
+            WarningReason::SyncPending => "sync_pending",
+            WarningReason::UnknownAttribute => "unknown_attribute",
+            WarningReason::DuplicateRepository => "duplicate_repository",
+            WarningReason::OutOfScope => "out_of_scope",
+            WarningReason::TitleFiltered => "title_filtered",
+            WarningReason::AgeFiltered => "age_filtered",
+            WarningReason::IssueExcluded => "issue_excluded",
+            WarningReason::RepositoryUnlicensed => "repository_unlicensed",
+        }
+    }
+}
+
+/// Something the import skipped, returned so curators can tell why counts are
+/// lower than they expected.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportWarning {
+    /// Url of the repository the warning is about, empty for project-level
+    /// warnings.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub repository: String,
+    /// Dotted path of the payload field a project-level warning is about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub reason: WarningReason,
+    pub message: String,
+    /// Number of issues skipped, for issue-level warnings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+}
+
+impl ImportWarning {
+    pub fn project(field: &str, reason: WarningReason, message: String) -> Self {
+        ImportWarning {
+            repository: String::new(),
+            field: Some(field.to_string()),
+            reason,
+            message,
+            count: None,
+        }
+    }
+
+    pub fn repository(repository: &str, reason: WarningReason, message: String) -> Self {
+        ImportWarning {
+            repository: repository.to_string(),
+            field: None,
+            reason,
+            message,
+            count: None,
+        }
+    }
+
+    pub fn issues(repository: &str, reason: WarningReason, count: usize) -> Self {
+        let what = match reason {
+            WarningReason::IssueAssigned => "already assigned",
+            WarningReason::IssueLabelBlocklisted => "carrying a blocklisted label",
+            WarningReason::PullRequest => "pull requests",
+            WarningReason::OutOfScope => "outside the repository's scope",
+            WarningReason::TitleFiltered => "left out by the title filters",
+            WarningReason::AgeFiltered => "too new or too old for the age filters",
+            WarningReason::IssueExcluded => "excluded by the payload",
+            _ => "filtered out",
+        };
+        ImportWarning {
+            repository: repository.to_string(),
+            field: None,
+            reason,
+            message: format!("{} issue(s) skipped as {}", count, what),
+            count: Some(count),
+        }
+    }
+}
+
+/// Response body of a successful import, carrying the ids of every row
+/// created so callers can link records without querying back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportResponse {
+    pub project_id: i32,
+    /// The project's slug, the payload's or the one derived from its name.
+    #[serde(default)]
+    pub slug: String,
+    pub repositories: Vec<ImportedRepository>,
+    pub total_issues_imported: u64,
+    pub warnings: Vec<ImportWarning>,
+    /// Issues left out, by reason, summed over the issue-level `warnings`.
+    #[serde(default)]
+    pub issues_skipped: BTreeMap<WarningReason, usize>,
+    /// Set when the import stopped before its last repository to stay within
+    /// the invocation's deadline; pass it back as `resumeToken` to continue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_token: Option<String>,
+}
+
+/// How far a repository's sync got: issues are fetched and upserted a page at
+/// a time, so an interrupted sync can continue after the last synced page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncCursor {
+    pub repository_id: i32,
+    /// Number of pages synced so far.
+    pub page: u32,
+    /// Issues seen open so far, the rest are marked closed once done.
+    pub open_numbers: Vec<i64>,
+    /// Issues fetched so far, skipped ones included.
+    #[serde(default)]
+    pub issues_fetched: u64,
+    /// Time spent checking the repository, and fetching and upserting so
+    /// far, see [`SyncTimings`].
+    #[serde(default)]
+    pub validation_ms: u64,
+    #[serde(default)]
+    pub fetch_ms: u64,
+    #[serde(default)]
+    pub insert_ms: u64,
+    pub issues_upserted: u64,
+    pub skipped_assigned: usize,
+    pub skipped_blocklisted: usize,
+    #[serde(default)]
+    pub skipped_pull_requests: usize,
+    #[serde(default)]
+    pub skipped_out_of_scope: usize,
+    #[serde(default)]
+    pub skipped_title_filtered: usize,
+    #[serde(default)]
+    pub skipped_age_filtered: usize,
+    #[serde(default)]
+    pub skipped_excluded: usize,
+}
+
+impl SyncCursor {
+    pub fn new(repository_id: i32) -> Self {
+        SyncCursor {
+            repository_id,
+            ..Default::default()
+        }
+    }
+}
+
+/// Progress of a checkpointed import at repository granularity: the result so
+/// far and the index of the next repository of the payload to import.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportCheckpoint {
+    pub response: ImportResponse,
+    pub next_repository: usize,
+    /// Indexes of the repositories after `next_repository` already imported,
+    /// by a parallel import one of whose earlier repositories failed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imported_ahead: Vec<usize>,
+}
+
+/// A checkpoint row: the payload being imported and, once the import has
+/// started, its progress and the cursor of the repository being synced.
+#[derive(Debug)]
+pub struct StoredCheckpoint {
+    pub payload: String,
+    pub state: Option<ImportCheckpoint>,
+    pub cursor: Option<SyncCursor>,
+}
+
+/// What made a dead-lettered import fail.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    RateLimited,
+    InvalidPayload,
+    InvalidUrl,
+    Database,
+    GitHub,
+    /// A code host other than GitHub failed.
+    Forge,
+    Other,
+}
+
+impl FailureKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FailureKind::RateLimited => "rate_limited",
+            FailureKind::InvalidPayload => "invalid_payload",
+            FailureKind::InvalidUrl => "invalid_url",
+            FailureKind::Database => "database",
+            FailureKind::GitHub => "github",
+            FailureKind::Forge => "forge",
+            FailureKind::Other => "other",
+        }
+    }
+
+    /// Whether replaying the message may succeed later.
+    pub fn is_transient(self) -> bool {
+        !matches!(self, FailureKind::InvalidPayload | FailureKind::InvalidUrl)
+    }
+}
+
+/// Why a dead-lettered message was given up on.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureStatus {
+    /// The message can never succeed as is.
+    Invalid,
+    /// The failure looked transient but kept happening.
+    Exhausted,
+}
+
+impl FailureStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FailureStatus::Invalid => "invalid",
+            FailureStatus::Exhausted => "exhausted",
+        }
+    }
+}
+
+/// A dead-lettered import given up on, as recorded in `import_failures`.
+#[derive(Debug, Serialize)]
+pub struct ImportFailure {
+    pub message_id: String,
+    pub body: String,
+    pub kind: FailureKind,
+    pub status: FailureStatus,
+    pub error: String,
+    pub receive_count: i32,
+}
+
+/// Lifecycle of a row of the `jobs` table.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+impl JobState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+        }
+    }
+
+    pub fn from_db(state: &str) -> Option<Self> {
+        match state {
+            "queued" => Some(JobState::Queued),
+            "running" => Some(JobState::Running),
+            "done" => Some(JobState::Done),
+            "failed" => Some(JobState::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// An asynchronous import, its payload being a project or a task.
+#[derive(Debug, Serialize)]
+pub struct Job {
+    pub id: i64,
+    pub state: JobState,
+    #[serde(skip_serializing)]
+    pub payload: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// S3 key of the detailed report of a done job, in `IMPORT_REPORT_BUCKET`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A repository row, as needed to refresh its issues.
+#[derive(Debug, Clone)]
+pub struct StoredRepository {
+    pub id: i32,
+    pub url: String,
+    /// Forge it was imported from, `None` for repositories imported before
+    /// forges were recorded.
+    pub forge: Option<ForgeKind>,
+    /// See [`RepoInfo::paths`], [`RepoInfo::label_prefix`],
+    /// [`RepoInfo::program_tags`] and [`RepoInfo::excluded_issues`].
+    pub paths: Vec<String>,
+    pub label_prefix: Option<String>,
+    pub program_tags: Vec<String>,
+    pub excluded_issues: Vec<i64>,
+    /// The project's, as imported.
+    pub filters: ImportFilters,
+    /// Where its last sync stopped, over its time budget, `None` once a
+    /// sync completes.
+    pub sync_cursor: Option<SyncCursor>,
+}
+
+impl StoredRepository {
+    /// The repository's info as imported, from its stored url and scope.
+    pub fn repo_info(&self) -> Result<RepoInfo, RepoUrlError> {
+        let mut repo_info = RepoInfo::from_forge_url(&self.url, self.forge)?;
+        repo_info.paths = self.paths.clone();
+        repo_info.label_prefix = self.label_prefix.clone();
+        repo_info.program_tags = self.program_tags.clone();
+        repo_info.excluded_issues = self.excluded_issues.clone();
+        Ok(repo_info)
+    }
+}
+
+/// `page`/`per_page` parameters of listing endpoints.
+#[derive(Debug, Clone, Copy)]
+pub struct Pagination {
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl Pagination {
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+}
+
+/// A page of a listing endpoint.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+/// Body of `PATCH /issues/{id}`: the curation flags to change, the others
+/// being left as they are.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct IssueCuration {
+    /// Listed first.
+    pub featured: Option<bool>,
+    /// Left out of listings.
+    pub hidden: Option<bool>,
+    /// Why the issue was curated, for the Kudos team; blank to clear it.
+    pub curator_note: Option<String>,
+}
+
+impl IssueCuration {
+    pub fn is_empty(&self) -> bool {
+        self.featured.is_none() && self.hidden.is_none() && self.curator_note.is_none()
+    }
+}
+
+/// Body of `PATCH /issues/{id}/review`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IssueReview {
+    pub state: ReviewState,
+}
+
+/// An issue's review state, as set by `PATCH /issues/{id}/review`.
+#[derive(Debug, Serialize)]
+pub struct ReviewedIssue {
+    pub id: i32,
+    pub review_state: ReviewState,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+/// An issue's curation flags, as set by `PATCH /issues/{id}`.
+#[derive(Debug, Serialize)]
+pub struct CuratedIssue {
+    pub id: i32,
+    pub featured: bool,
+    pub hidden: bool,
+    pub curator_note: Option<String>,
+    pub curated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedIssue {
+    pub id: i32,
+    pub number: i64,
+    pub title: String,
+    pub html_url: String,
+    pub labels: Vec<String>,
+    pub repository: String,
+    pub issue_created_at: DateTime<Utc>,
+    /// Plain-text excerpt of the issue's description, see [`crate::sanitize::excerpt`].
+    pub excerpt: Option<String>,
+    pub is_beginner_friendly: bool,
+    /// `category:value` tags derived from the labels, see [`crate::classify`].
+    pub categories: Vec<String>,
+    pub is_paid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Reward>,
+    /// `None` for issues imported before difficulties were estimated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<Difficulty>,
+    /// What the difficulty was estimated from, see [`DifficultyEstimate`].
+    pub difficulty_signals: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue_type: Option<IssueType>,
+    pub program_tags: Vec<String>,
+    /// `None` for issues imported before staleness was scored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staleness: Option<f64>,
+    pub seeking_contributors: bool,
+    pub review_state: ReviewState,
+    pub reactions: i32,
+    /// `None` for issues imported before they were ranked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranking_score: Option<f64>,
+    /// Promoted by the Kudos team, see [`IssueCuration`].
+    pub featured: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LabelStats {
+    pub label: String,
+    pub issues: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepositoryStats {
+    pub slug: String,
+    pub issues: i64,
+    pub last_synced_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectStats {
+    pub total_issues: i64,
+    pub average_issue_age_days: Option<f64>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub by_label: Vec<LabelStats>,
+    pub by_repository: Vec<RepositoryStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(url: &str) -> (String, String, String) {
+        let repo_info = RepoInfo::from_url(url).unwrap();
+        (repo_info.host, repo_info.owner, repo_info.name)
+    }
+
+    fn github(owner: &str, name: &str) -> (String, String, String) {
+        (
+            "github.com".to_string(),
+            owner.to_string(),
+            name.to_string(),
+        )
+    }
+
+    #[test]
+    fn parses_web_urls() {
+        let expected = github("paritytech", "polkadot-sdk");
+        for url in [
+            "https://github.com/paritytech/polkadot-sdk",
+            "https://github.com/paritytech/polkadot-sdk/",
+            "http://github.com/paritytech/polkadot-sdk",
+            "https://GitHub.com/paritytech/polkadot-sdk",
+            "  https://github.com/paritytech/polkadot-sdk  ",
+            "https://github.com//paritytech//polkadot-sdk",
+            "github.com/paritytech/polkadot-sdk",
+        ] {
+            assert_eq!(parse(url), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn ignores_query_fragment_and_paths_within_the_repository() {
+        let expected = github("paritytech", "polkadot-sdk");
+        for url in [
+            "https://github.com/paritytech/polkadot-sdk?tab=readme-ov-file",
+            "https://github.com/paritytech/polkadot-sdk#readme",
+            "https://github.com/paritytech/polkadot-sdk/tree/main",
+            "https://github.com/paritytech/polkadot-sdk/tree/master/substrate/frame",
+            "https://github.com/paritytech/polkadot-sdk/issues?q=is%3Aopen",
+        ] {
+            assert_eq!(parse(url), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn parses_gitlab_subgroup_urls() {
+        let url = "https://gitlab.example.org/Group/subgroup/project";
+        let repo_info = RepoInfo::from_forge_url(url, Some(ForgeKind::GitLab)).unwrap();
+        assert_eq!(
+            (repo_info.owner.as_str(), repo_info.name.as_str()),
+            ("group/subgroup", "project")
+        );
+        assert_eq!(repo_info.forge, Some(ForgeKind::GitLab));
+        assert_eq!(
+            repo_info.url(),
+            "https://gitlab.example.org/group/subgroup/project"
+        );
+
+        let url = "https://gitlab.com/group/subgroup/project/-/tree/main/crates/foo";
+        let repo_info = RepoInfo::from_url(url).unwrap();
+        assert_eq!(
+            (repo_info.owner.as_str(), repo_info.name.as_str()),
+            ("group/subgroup", "project")
+        );
+        assert_eq!(repo_info.paths, ["crates/foo"]);
+        let issue = "https://gitlab.com/group/subgroup/project/-/issues/9";
+        assert_eq!(repo_info.issue_number(issue), Some(9));
+
+        // Other forges' repositories are still named by two segments.
+        let url = "https://github.com/org/repo/tree/main";
+        let repo_info = RepoInfo::from_forge_url(url, Some(ForgeKind::GitHub)).unwrap();
+        assert_eq!(repo_info.url(), "https://github.com/org/repo");
+    }
+
+    #[test]
+    fn scopes_tree_urls_to_their_path() {
+        for (url, paths) in [
+            (
+                "https://github.com/org/repo/tree/main/crates/foo",
+                vec!["crates/foo"],
+            ),
+            (
+                "https://gitlab.com/org/repo/-/tree/main/crates/foo/",
+                vec!["crates/foo"],
+            ),
+            (
+                "https://codeberg.org/org/repo/src/branch/main/crates/foo",
+                vec!["crates/foo"],
+            ),
+            (
+                "https://bitbucket.org/org/repo/src/main/crates/foo",
+                vec!["crates/foo"],
+            ),
+            ("https://github.com/org/repo/tree/main", vec![]),
+            ("https://github.com/org/repo/issues/1", vec![]),
+        ] {
+            let repo_info = RepoInfo::from_url(url).unwrap();
+            assert_eq!(
+                (repo_info.owner.as_str(), repo_info.name.as_str()),
+                ("org", "repo")
+            );
+            assert_eq!(repo_info.paths, paths, "{}", url);
+        }
+
+        let repo_info = RepoInfo::from_url("github.com/org/repo/tree/main/frame/staking").unwrap();
+        let labels = |labels: &[&str]| labels.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert!(repo_info.in_scope(&labels(&["bug", "pallet-staking"])));
+        assert!(repo_info.in_scope(&labels(&["A-Staking"])));
+        assert!(repo_info.in_scope(&labels(&["staking"])));
+        assert!(!repo_info.in_scope(&labels(&["unstaking", "bug"])));
+        assert!(!repo_info.in_scope(&[]));
+
+        let repo = Repository {
+            label: "staking".to_string(),
+            url: "paritytech/polkadot-sdk".to_string(),
+            forge: None,
+            label_prefix: Some(" T2-".to_string()),
+            paths: vec!["substrate/frame/staking/".to_string()],
+            excluded_issue_numbers: Vec::new(),
+            excluded_issue_urls: Vec::new(),
+        };
+        let repo_info = RepoInfo::from_repository(&repo).unwrap();
+        assert_eq!(repo_info.paths, ["substrate/frame/staking"]);
+        assert!(repo_info.in_scope(&labels(&["t2-pallets"])));
+        assert!(repo_info.in_scope(&labels(&["pallet-staking"])));
+        assert!(!repo_info.in_scope(&labels(&["t1-runtime"])));
+    }
+
+    #[test]
+    fn parses_clone_urls() {
+        let expected = github("paritytech", "polkadot-sdk");
+        for url in [
+            "https://github.com/paritytech/polkadot-sdk.git",
+            "git@github.com:paritytech/polkadot-sdk.git",
+            "git@github.com:paritytech/polkadot-sdk",
+            "git@github.com:/paritytech/polkadot-sdk.git",
+            "ssh://git@github.com/paritytech/polkadot-sdk.git",
+            "git://github.com/paritytech/polkadot-sdk.git",
+        ] {
+            assert_eq!(parse(url), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn lowercases_owners_and_names() {
+        assert_eq!(
+            parse("https://github.com/ParityTech/Polkadot-SDK"),
+            github("paritytech", "polkadot-sdk")
+        );
+    }
+
+    #[test]
+    fn keeps_dots_within_names() {
+        assert_eq!(
+            parse("https://github.com/owner/name.js.git"),
+            github("owner", "name.js")
+        );
+    }
+
+    #[test]
+    fn takes_urls_without_a_host_as_github_ones() {
+        assert_eq!(
+            parse("paritytech/polkadot-sdk"),
+            github("paritytech", "polkadot-sdk")
+        );
+    }
+
+    #[test]
+    fn excludes_issues_by_number_and_url() {
+        let repo = Repository {
+            label: "sdk".to_string(),
+            url: "https://github.com/paritytech/polkadot-sdk".to_string(),
+            forge: None,
+            label_prefix: None,
+            paths: Vec::new(),
+            excluded_issue_numbers: vec![40, 12],
+            excluded_issue_urls: vec![
+                "https://github.com/ParityTech/polkadot-sdk/issues/7".to_string(),
+                "https://github.com/paritytech/polkadot-sdk/issues/12#issuecomment-1".to_string(),
+                "https://github.com/paritytech/other/issues/3".to_string(),
+                "https://github.com/paritytech/polkadot-sdk/pull/5".to_string(),
+            ],
+        };
+        let repo_info = RepoInfo::from_repository(&repo).unwrap();
+        assert_eq!(repo_info.excluded_issues, [7, 12, 40]);
+
+        let gitlab = RepoInfo::from_url("https://gitlab.com/group/project").unwrap();
+        let url = "https://gitlab.com/group/project/-/issues/9";
+        assert_eq!(gitlab.issue_number(url), Some(9));
+        assert_eq!(gitlab.issue_number("group/project/-/issues/9"), None);
+
+        let tracker = RepoInfo::from_url("https://todo.sr.ht/~owner/tracker").unwrap();
+        assert_eq!(
+            tracker.issue_number("https://todo.sr.ht/~owner/tracker/5"),
+            Some(5)
+        );
+        assert_eq!(tracker.issue_url(5), "https://todo.sr.ht/~owner/tracker/5");
+    }
+
+    #[test]
+    fn expands_shorthands_only() {
+        let expand = |url: &str| {
+            let mut repo = Repository {
+                label: "repo".to_string(),
+                url: url.to_string(),
+                forge: None,
+                label_prefix: None,
+                paths: Vec::new(),
+                excluded_issue_numbers: Vec::new(),
+                excluded_issue_urls: Vec::new(),
+            };
+            repo.expand_shorthand();
+            repo.url
+        };
+        assert_eq!(
+            expand(" paritytech/polkadot-sdk "),
+            "https://github.com/paritytech/polkadot-sdk"
+        );
+        assert_eq!(
+            expand("ParityTech/polkadot-sdk.git"),
+            "https://github.com/paritytech/polkadot-sdk"
+        );
+        for url in [
+            "https://github.com/paritytech/polkadot-sdk/tree/main",
+            "git@github.com:paritytech/polkadot-sdk.git",
+            "gitlab.com/group/project",
+            "paritytech",
+        ] {
+            assert_eq!(expand(url), url);
+        }
+    }
+
+    #[test]
+    fn parses_other_forges() {
+        assert_eq!(
+            parse("https://gitlab.com/group/project/-/tree/main"),
+            (
+                "gitlab.com".to_string(),
+                "group".to_string(),
+                "project".to_string()
+            )
+        );
+        assert_eq!(
+            parse("https://todo.sr.ht/~owner/tracker"),
+            (
+                "todo.sr.ht".to_string(),
+                "~owner".to_string(),
+                "tracker".to_string()
+            )
+        );
+        assert_eq!(
+            parse("https://git.example.org:3000/team/tool"),
+            (
+                "git.example.org".to_string(),
+                "team".to_string(),
+                "tool".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_urls_without_a_repository() {
+        for (url, error) in [
+            ("", RepoUrlError::Empty),
+            ("   ", RepoUrlError::Empty),
+            ("https://github.com", RepoUrlError::MissingOwner),
+            ("https://github.com/", RepoUrlError::MissingOwner),
+            ("https://github.com/paritytech", RepoUrlError::MissingName),
+            ("https://github.com/paritytech/", RepoUrlError::MissingName),
+            (
+                "https://github.com/paritytech/.git",
+                RepoUrlError::MissingName,
+            ),
+            ("github.com/paritytech", RepoUrlError::MissingName),
+            ("paritytech", RepoUrlError::MissingName),
+            ("git@github.com:paritytech", RepoUrlError::MissingName),
+        ] {
+            assert_eq!(RepoInfo::from_url(url).unwrap_err(), error, "{:?}", url);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        assert_eq!(
+            RepoInfo::from_url("ftp://github.com/owner/name").unwrap_err(),
+            RepoUrlError::UnsupportedScheme("ftp".to_string())
+        );
+        assert_eq!(
+            RepoInfo::from_url("mailto:someone@example.org").unwrap_err(),
+            RepoUrlError::MissingOwner
+        );
+        assert!(matches!(
+            RepoInfo::from_url("https://exa mple.org/owner/name"),
+            Err(RepoUrlError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn sanitizes_titles_and_labels() {
+        let mut issue = KudosIssue {
+            number: 1,
+            title: " Fix\u{202e} the\u{0}\r\nparser\u{fffd} ".to_string(),
+            html_url: "https://github.com/owner/name/issues/1".to_string(),
+            issue_created_at: Utc::now(),
+            issue_updated_at: Utc::now(),
+            user: "user".to_string(),
+            labels: ["Good First Issue", " good  first\tissue ", "  ", "C-bug"]
+                .map(String::from)
+                .to_vec(),
+            assignees: Vec::new(),
+            body: None,
+            excerpt: None,
+            is_beginner_friendly: false,
+            categories: Vec::new(),
+            is_paid: false,
+            reward: None,
+            difficulty: None,
+            issue_type: None,
+            program_tags: Vec::new(),
+            staleness: None,
+            seeking_contributors: false,
+            review_state: ReviewState::Pending,
+            referenced_issues: Vec::new(),
+            reactions: 0,
+            ranking_score: None,
+        };
+        issue.sanitize();
+        assert_eq!(issue.title, "Fix the parser");
+        assert_eq!(issue.labels, ["good first issue", "c-bug"]);
+
+        // `e` and a combining acute accent, composed by NFC.
+        issue.title = format!("Caf\u{65}\u{301} {}", "a".repeat(400));
+        issue.sanitize();
+        assert!(issue.title.starts_with("Caf\u{e9} a"));
+        assert_eq!(issue.title.chars().count(), sanitize::MAX_TITLE_CHARS);
+        assert!(issue.title.ends_with('…'));
+    }
+}
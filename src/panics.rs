@@ -0,0 +1,41 @@
+//! Logging of panics, which the runtime otherwise reports with little more
+//! than their message.
+
+use std::backtrace::Backtrace;
+use std::panic::{self, PanicHookInfo};
+
+use lambda_http::tracing::error;
+
+use crate::reporting;
+
+/// The message a panic was raised with, when it's a string.
+fn message<'a>(info: &'a PanicHookInfo) -> &'a str {
+    match info.payload().downcast_ref::<&str>() {
+        Some(message) => message,
+        None => info
+            .payload()
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .unwrap_or("Box<dyn Any>"),
+    }
+}
+
+/// Logs every panic as an error event with its message, location, backtrace
+/// and the project and repository being imported (see
+/// [`reporting::set_tag`]), before the hook installed earlier, e.g. Sentry's,
+/// runs. To be installed once the logs are set up.
+pub fn install_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let tags = reporting::tags();
+        error!(
+            panic.message = message(info),
+            panic.location = info.location().map(|location| location.to_string()),
+            panic.backtrace = %Backtrace::force_capture(),
+            project_slug = tags.get("project_slug").map(String::as_str),
+            repository = tags.get("repository").map(String::as_str),
+            "Panicked"
+        );
+        previous(info);
+    }));
+}
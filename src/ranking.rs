@@ -0,0 +1,84 @@
+//! Ranking of issues for the Kudos feed: how recent an issue is, how many
+//! reactions it got, whether it suits newcomers and how healthy its
+//! repository is, combined into a score from 0 to 1 stored with the issue,
+//! so the feed is one indexed sort. Scores are computed on sync, recency
+//! decaying in between.
+
+use chrono::{DateTime, Utc};
+use lambda_http::tracing::warn;
+
+use crate::model::KudosIssue;
+use crate::store::Store;
+
+/// Days after which the recency part of the score halves.
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+/// Issues with this many reactions or more get the whole reactions part.
+const POPULAR_REACTIONS: f64 = 50.0;
+/// Health counted for repositories not scored, see [`crate::health`].
+const UNKNOWN_HEALTH: f64 = 0.5;
+
+const RECENCY_WEIGHT: f64 = 0.4;
+const REACTIONS_WEIGHT: f64 = 0.25;
+const BEGINNER_WEIGHT: f64 = 0.15;
+const HEALTH_WEIGHT: f64 = 0.2;
+
+/// The ranking score of a classified issue as of `now`, between 0 and 1 and
+/// rounded to three decimals, in a repository of this health score.
+/// Reactions count logarithmically, so a few of them already matter.
+pub fn score(issue: &KudosIssue, health: Option<f64>, now: DateTime<Utc>) -> f64 {
+    let days = (now - issue.issue_created_at).num_seconds().max(0) as f64 / 86_400.0;
+    let recency = 0.5_f64.powf(days / RECENCY_HALF_LIFE_DAYS);
+    let reactions = ((1.0 + issue.reactions as f64).ln() / (1.0 + POPULAR_REACTIONS).ln()).min(1.0);
+    let beginner = if issue.is_beginner_friendly { 1.0 } else { 0.0 };
+    let health = health.unwrap_or(UNKNOWN_HEALTH).clamp(0.0, 1.0);
+    let score = RECENCY_WEIGHT * recency
+        + REACTIONS_WEIGHT * reactions
+        + BEGINNER_WEIGHT * beginner
+        + HEALTH_WEIGHT * health;
+    (score * 1000.0).round() / 1000.0
+}
+
+/// The repository's last health score, `None` when it has none or it can't
+/// be read, which isn't worth failing a sync over.
+pub async fn repository_health(store: &dyn Store, repo_id: i32) -> Option<f64> {
+    match store.health_score(repo_id).await {
+        Ok(score) => score,
+        Err(e) => {
+            warn!("Couldn't read the health of repository {}: {}", repo_id, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn ranks_issues() {
+        let now = Utc::now();
+        let mut issue: KudosIssue = serde_json::from_value(serde_json::json!({
+            "number": 1,
+            "title": "Fix the parser",
+            "html_url": "https://github.com/owner/name/issues/1",
+            "issue_created_at": now - Duration::days(30),
+            "issue_updated_at": now,
+            "user": "user",
+            "labels": [],
+            "assignees": [],
+        }))
+        .unwrap();
+        // 0.4 * 0.5 + 0.2 * 0.5
+        assert_eq!(score(&issue, None, now), 0.3);
+
+        issue.is_beginner_friendly = true;
+        issue.reactions = 50;
+        // 0.4 * 0.5 + 0.25 + 0.15 + 0.2 * 0.8
+        assert_eq!(score(&issue, Some(0.8), now), 0.76);
+
+        issue.issue_created_at = now;
+        assert_eq!(score(&issue, Some(1.0), now), 1.0);
+    }
+}
@@ -0,0 +1,95 @@
+//! The GitHub rate-limit budget, as reported by the `x-ratelimit-*` headers
+//! of every GitHub response. Each invocation ends with a summary of the
+//! calls it made and the quota left, a warning when that's running low, so
+//! imports exhausting the token's quota are noticed before they fail.
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use lambda_http::tracing::{debug, info, warn};
+use reqwest::header::HeaderMap;
+
+use crate::config;
+use crate::metrics;
+
+/// The budget reported by the last GitHub response.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset: DateTime<Utc>,
+}
+
+impl RateLimit {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| -> Option<u64> { headers.get(name)?.to_str().ok()?.parse().ok() };
+        Some(RateLimit {
+            limit: header("x-ratelimit-limit")?,
+            remaining: header("x-ratelimit-remaining")?,
+            reset: DateTime::from_timestamp(header("x-ratelimit-reset")? as i64, 0)?,
+        })
+    }
+}
+
+static LAST: Mutex<Option<RateLimit>> = Mutex::new(None);
+
+/// Records the budget reported by a GitHub response, if it reports one.
+pub fn record(headers: &HeaderMap) {
+    let Some(rate_limit) = RateLimit::from_headers(headers) else {
+        return;
+    };
+    debug!(
+        remaining = rate_limit.remaining,
+        reset = %rate_limit.reset,
+        "GitHub rate limit"
+    );
+    *LAST.lock().unwrap() = Some(rate_limit);
+}
+
+pub fn last() -> Option<RateLimit> {
+    *LAST.lock().unwrap()
+}
+
+/// Calls made by one invocation, from when it started.
+pub struct InvocationBudget {
+    calls_before: u64,
+}
+
+impl InvocationBudget {
+    pub fn start() -> Self {
+        InvocationBudget {
+            calls_before: metrics::github_api_calls(),
+        }
+    }
+
+    /// Logs the calls made since the start and the budget left, warning
+    /// when less than `GITHUB_RATE_LIMIT_WARN_REMAINING` calls are left.
+    pub fn log_summary(self) {
+        let calls = metrics::github_api_calls() - self.calls_before;
+        if calls == 0 {
+            return;
+        }
+        let Some(rate_limit) = last() else {
+            info!(github_api_calls = calls, "GitHub budget unknown");
+            return;
+        };
+
+        if rate_limit.remaining < config::get().github_rate_limit_warn_remaining {
+            warn!(
+                github_api_calls = calls,
+                remaining = rate_limit.remaining,
+                limit = rate_limit.limit,
+                reset = %rate_limit.reset,
+                "GitHub rate limit nearly exhausted"
+            );
+        } else {
+            info!(
+                github_api_calls = calls,
+                remaining = rate_limit.remaining,
+                limit = rate_limit.limit,
+                reset = %rate_limit.reset,
+                "GitHub rate limit budget"
+            );
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Detailed reports of bulk and asynchronous imports, written to S3 so the
+//! responses and job records stay small. On top of the import's result, a
+//! report lists the decision taken on every issue fetched, collected while
+//! the import runs (see [`collect`]).
+
+use std::cell::RefCell;
+use std::future::Future;
+
+use aws_sdk_s3::{primitives::ByteStream, Client};
+use lambda_http::Error;
+use serde::Serialize;
+
+use crate::aws::sdk_config;
+use crate::config;
+use crate::model::WarningReason;
+
+/// What the import did with one of the issues it fetched.
+#[derive(Debug, Clone, Serialize)]
+pub struct IssueDecision {
+    /// Url of the issue's repository.
+    pub repository: String,
+    pub number: i64,
+    pub imported: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<WarningReason>,
+}
+
+tokio::task_local! {
+    /// Decisions recorded within the current [`collect`].
+    static DECISIONS: RefCell<Vec<IssueDecision>>;
+}
+
+/// Runs an import, returning the decisions it took on issues along its
+/// output.
+pub async fn collect<F: Future>(import: F) -> (F::Output, Vec<IssueDecision>) {
+    DECISIONS
+        .scope(RefCell::default(), async {
+            let output = import.await;
+            (output, DECISIONS.with(|decisions| decisions.take()))
+        })
+        .await
+}
+
+/// Records the decision taken on an issue, imported unless a `skip_reason`
+/// is given. Only imports run within [`collect`] keep them.
+pub fn record(repository: &str, number: i64, skip_reason: Option<WarningReason>) {
+    let _ = DECISIONS.try_with(|decisions| {
+        decisions.borrow_mut().push(IssueDecision {
+            repository: repository.to_string(),
+            number,
+            imported: skip_reason.is_none(),
+            skip_reason,
+        })
+    });
+}
+
+/// `IMPORT_REPORT_BUCKET`, where reports go.
+pub fn bucket() -> Option<String> {
+    config::get().import_report_bucket.clone()
+}
+
+/// Writes `report` as JSON to `bucket`, under `IMPORT_REPORT_PREFIX` and
+/// `name`, returning its key.
+pub async fn write<T: Serialize>(
+    client: &Client,
+    bucket: &str,
+    name: &str,
+    report: &T,
+) -> Result<String, Error> {
+    let key = format!("{}{}", config::get().import_report_prefix, name);
+    client
+        .put_object()
+        .bucket(bucket)
+        .key(&key)
+        .content_type("application/json")
+        .body(ByteStream::from(serde_json::to_vec_pretty(report)?))
+        .send()
+        .await?;
+    Ok(key)
+}
+
+/// Client of the report buckets.
+pub async fn client() -> Client {
+    Client::new(sdk_config().await)
+}
@@ -0,0 +1,93 @@
+//! Reporting of errors and panics to Sentry, or a compatible service, when
+//! built with the `error-reporting` feature and `SENTRY_DSN` is set. Without
+//! either, nothing is reported.
+//!
+//! Each invocation reports through its own hub (see [`isolate`]), so the
+//! context tagged by one, e.g. the project slug, never leaks into reports of
+//! the next. The tags are also kept without the feature, for the panic hook
+//! to log (see [`crate::panics`]).
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+#[cfg(feature = "error-reporting")]
+use sentry::SentryFutureExt;
+
+use crate::config;
+
+tokio::task_local! {
+    /// Tags of the current invocation, by key.
+    static TAGS: RefCell<BTreeMap<String, String>>;
+}
+
+/// Keeps the client alive, flushing pending reports when dropped.
+pub struct ReportingGuard {
+    #[cfg(feature = "error-reporting")]
+    _guard: Option<sentry::ClientInitGuard>,
+}
+
+/// Starts the client, which also reports panics, if a DSN is configured.
+pub fn init() -> ReportingGuard {
+    let config = config::get();
+    #[cfg(feature = "error-reporting")]
+    {
+        let guard = config.sentry_dsn.as_deref().map(|dsn| {
+            sentry::init((
+                dsn,
+                sentry::ClientOptions {
+                    environment: config.sentry_environment.clone().map(Into::into),
+                    release: sentry::release_name!(),
+                    ..Default::default()
+                },
+            ))
+        });
+        ReportingGuard { _guard: guard }
+    }
+    #[cfg(not(feature = "error-reporting"))]
+    {
+        let _ = config;
+        ReportingGuard {}
+    }
+}
+
+/// Runs an invocation with its own hub, flushing its reports before the
+/// Lambda gets frozen.
+pub async fn isolate<F: Future>(invocation: F) -> F::Output {
+    let invocation = TAGS.scope(RefCell::default(), invocation);
+    #[cfg(feature = "error-reporting")]
+    {
+        let hub = std::sync::Arc::new(sentry::Hub::new_from_top(sentry::Hub::current()));
+        let output = invocation.bind_hub(hub.clone()).await;
+        if let Some(client) = hub.client() {
+            client.flush(Some(std::time::Duration::from_secs(2)));
+        }
+        output
+    }
+    #[cfg(not(feature = "error-reporting"))]
+    invocation.await
+}
+
+/// Attaches `value` to the reports of the current invocation, e.g. the
+/// slug of the project being imported.
+pub fn set_tag(key: &str, value: &str) {
+    let _ = TAGS.try_with(|tags| tags.borrow_mut().insert(key.to_string(), value.to_string()));
+    #[cfg(feature = "error-reporting")]
+    sentry::configure_scope(|scope| scope.set_tag(key, value));
+}
+
+/// The tags of the current invocation, none outside of [`isolate`].
+pub fn tags() -> BTreeMap<String, String> {
+    TAGS.try_with(|tags| tags.try_borrow().map(|tags| tags.clone()).ok())
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Reports an error, with the tags of the current invocation.
+pub fn capture(error: &(dyn std::error::Error + 'static)) {
+    #[cfg(feature = "error-reporting")]
+    sentry::capture_error(error);
+    #[cfg(not(feature = "error-reporting"))]
+    let _ = error;
+}
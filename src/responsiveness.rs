@@ -0,0 +1,147 @@
+//! How quickly maintainers reply to a repository's issues, since
+//! contributors care whether theirs will be answered. Measured when a sync
+//! fetches the repository's first page, on up to `RESPONSIVENESS_SAMPLE_SIZE`
+//! of its most recent issues old enough to have had an answer.
+//!
+//! The first response to an issue is the first comment by an owner, member
+//! or collaborator of the repository other than the issue's author. Forges
+//! that don't tell maintainers apart aren't measured.
+
+use chrono::{DateTime, Duration, Utc};
+use lambda_http::tracing::warn;
+
+use crate::config;
+use crate::error::ImportError;
+use crate::github::{IssueComment, IssueSource};
+use crate::model::{KudosIssue, RepoInfo};
+use crate::store::Store;
+
+/// Issues answered within this many days count as answered in time, and
+/// issues younger than this aren't sampled.
+pub const RESPONSE_DAYS: i64 = 7;
+
+/// A repository's measured responsiveness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Responsiveness {
+    /// Share of the sampled issues first answered by a maintainer within
+    /// [`RESPONSE_DAYS`], between 0 and 1 and rounded to two decimals.
+    pub score: f64,
+    /// Median hours until the first answer, among the answered issues.
+    pub median_first_response_hours: Option<f64>,
+    pub sampled: usize,
+}
+
+/// How long after the issue's creation a maintainer first commented on it.
+pub fn first_response(issue: &KudosIssue, comments: &[IssueComment]) -> Option<Duration> {
+    comments
+        .iter()
+        .filter(|comment| comment.by_maintainer && comment.author != issue.user)
+        .map(|comment| comment.created_at)
+        .min()
+        .map(|answered_at| (answered_at - issue.issue_created_at).max(Duration::zero()))
+}
+
+/// The responsiveness of a repository from the first responses to its
+/// sampled issues, `None` for unanswered ones. `None` without any sample.
+pub fn score(first_responses: &[Option<Duration>]) -> Option<Responsiveness> {
+    if first_responses.is_empty() {
+        return None;
+    }
+    let mut hours: Vec<f64> = first_responses
+        .iter()
+        .flatten()
+        .map(|response| response.num_seconds() as f64 / 3600.0)
+        .collect();
+    hours.sort_by(f64::total_cmp);
+    let in_time = hours
+        .iter()
+        .filter(|hours| **hours <= (RESPONSE_DAYS * 24) as f64)
+        .count();
+    let median = match hours.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(hours[len / 2]),
+        len => Some((hours[len / 2 - 1] + hours[len / 2]) / 2.0),
+    };
+
+    let score = in_time as f64 / first_responses.len() as f64;
+    Some(Responsiveness {
+        score: (score * 100.0).round() / 100.0,
+        median_first_response_hours: median.map(|hours| (hours * 10.0).round() / 10.0),
+        sampled: first_responses.len(),
+    })
+}
+
+/// Measures the responsiveness of the repository on a sample of `issues`,
+/// as fetched. `None` when none can be sampled or the forge doesn't tell
+/// maintainers apart.
+pub async fn measure(
+    source: &dyn IssueSource,
+    repo_info: &RepoInfo,
+    issues: &[KudosIssue],
+    now: DateTime<Utc>,
+) -> Result<Option<Responsiveness>, ImportError> {
+    let answerable_before = now - Duration::days(RESPONSE_DAYS);
+    let mut sample: Vec<&KudosIssue> = issues
+        .iter()
+        .filter(|issue| issue.issue_created_at <= answerable_before)
+        .collect();
+    sample.sort_by_key(|issue| std::cmp::Reverse(issue.issue_created_at));
+    sample.truncate(config::get().responsiveness_sample_size);
+
+    let mut first_responses = Vec::with_capacity(sample.len());
+    for issue in sample {
+        let Some(comments) = source.issue_comments(repo_info, issue.number).await? else {
+            return Ok(None);
+        };
+        first_responses.push(first_response(issue, &comments));
+    }
+    Ok(score(&first_responses))
+}
+
+/// Measures and records the responsiveness of the repository, unless it's
+/// turned off. Not worth failing a sync over: errors are only logged.
+pub async fn record(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    repo_info: &RepoInfo,
+    repo_id: i32,
+    issues: &[KudosIssue],
+) {
+    if config::get().responsiveness_sample_size == 0 {
+        return;
+    }
+    let result = match measure(source, repo_info, issues, Utc::now()).await {
+        Ok(Some(responsiveness)) => store.record_responsiveness(repo_id, &responsiveness).await,
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(
+            "Couldn't measure the responsiveness of {}: {}",
+            repo_info.url(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hours(hours: i64) -> Option<Duration> {
+        Some(Duration::hours(hours))
+    }
+
+    #[test]
+    fn scores_first_responses() {
+        let responsiveness = score(&[hours(2), hours(30), None, hours(24 * 10)]).unwrap();
+        assert_eq!(responsiveness.score, 0.5);
+        assert_eq!(responsiveness.median_first_response_hours, Some(30.0));
+        assert_eq!(responsiveness.sampled, 4);
+
+        let unanswered = score(&[None, None]).unwrap();
+        assert_eq!(unanswered.score, 0.0);
+        assert_eq!(unanswered.median_first_response_hours, None);
+        assert_eq!(score(&[]), None);
+    }
+}
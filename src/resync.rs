@@ -0,0 +1,138 @@
+//! Scheduled re-sync of repositories whose issues haven't been refreshed in a
+//! while, triggered by an EventBridge schedule. Each run refreshes the
+//! stalest repositories it has time for and records every attempt in
+//! `repo_sync_state`, so the schedule alone keeps the issues fresh.
+
+use std::time::{Duration as StdDuration, SystemTime};
+
+use aws_lambda_events::eventbridge::EventBridgeEvent;
+use chrono::{Duration, Utc};
+use lambda_http::{
+    tracing::{error, field, info, info_span, warn, Instrument},
+    Error,
+};
+use serde::Serialize;
+
+use crate::config;
+use crate::error::ImportError;
+use crate::filters::IssueFilters;
+use crate::forge::ForgeRegistry;
+use crate::import::{near_deadline, sync_repository};
+use crate::model::SyncCursor;
+use crate::store::{PgStore, Store};
+
+/// Whether the event is an EventBridge schedule rather than another event.
+pub fn is_scheduled_event(event: &EventBridgeEvent) -> bool {
+    event.source == "aws.events" && event.detail_type == "Scheduled Event"
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ResyncSummary {
+    pub repositories_refreshed: usize,
+    pub repositories_failed: usize,
+    /// Stale repositories of the batch left for the next run, for lack of time.
+    pub repositories_remaining: usize,
+    pub issues_upserted: u64,
+    pub issues_closed: u64,
+}
+
+/// Refreshes the stalest repositories, up to `RESYNC_BATCH_SIZE` of those
+/// neither synced nor attempted for `RESYNC_AFTER_HOURS`, stopping ahead of
+/// the invocation's `deadline`. A failing repository is logged, recorded and
+/// left until it's stale again.
+pub async fn handle_scheduled_event(
+    _event: EventBridgeEvent,
+    deadline: Option<SystemTime>,
+) -> Result<ResyncSummary, Error> {
+    let config = config::get();
+
+    let store = PgStore::connect().await?;
+    let source = ForgeRegistry::from_config().await?;
+    let margin = StdDuration::from_secs(config.import_deadline_margin_seconds);
+
+    let synced_before = Utc::now() - Duration::hours(config.resync_after_hours);
+    let repositories = store
+        .stale_repositories(synced_before, config.resync_batch_size)
+        .await?;
+
+    let mut summary = ResyncSummary::default();
+    let total = repositories.len();
+    for (index, repo) in repositories.into_iter().enumerate() {
+        if deadline.is_some_and(|deadline| near_deadline(deadline, margin)) {
+            summary.repositories_remaining = total - index;
+            info!(
+                repositories_remaining = summary.repositories_remaining,
+                "Stopping the re-sync ahead of the invocation deadline"
+            );
+            break;
+        }
+
+        // The counts are filled in by `sync_repository`.
+        let span = info_span!(
+            "resync",
+            repository_id = repo.id,
+            repo = %repo.url,
+            issues_fetched = field::Empty,
+            issues_filtered = field::Empty,
+            issues_inserted = field::Empty,
+        );
+
+        let repo_info = match repo.repo_info() {
+            Ok(repo_info) => repo_info,
+            Err(e) => {
+                span.in_scope(|| error!(error = %e, "Couldn't extract repo info from stored url"));
+                summary.repositories_failed += 1;
+                continue;
+            }
+        };
+        let filters =
+            IssueFilters::new(&config.label_blocklist, &repo.filters).unwrap_or_else(|e| {
+                span.in_scope(|| warn!(error = %e, "Ignoring the project's invalid filters"));
+                IssueFilters::blocklist(&config.label_blocklist)
+            });
+
+        let mut warnings = Vec::new();
+        let synced = sync_repository(
+            &store,
+            &source,
+            &repo_info,
+            &filters,
+            &mut warnings,
+            repo.sync_cursor.unwrap_or_else(|| SyncCursor::new(repo.id)),
+            None,
+        )
+        .instrument(span.clone())
+        .await;
+
+        match synced {
+            Ok(synced) => {
+                summary.repositories_refreshed += 1;
+                summary.issues_upserted += synced.issues_upserted;
+                summary.issues_closed += synced.issues_closed;
+            }
+            Err(e @ ImportError::Unavailable(_)) => {
+                // The remaining repositories would fail the same way, and
+                // recording it may well fail too.
+                error!("Stopping the re-sync: {}", e);
+                summary.repositories_failed += 1;
+                summary.repositories_remaining = total - index - 1;
+                break;
+            }
+            Err(e) => {
+                span.in_scope(|| error!("Re-sync failed: {}", e));
+                summary.repositories_failed += 1;
+                if let Err(e) = store.record_sync_failure(repo.id, &e.to_string()).await {
+                    span.in_scope(|| error!("Couldn't record the failed re-sync: {}", e));
+                }
+            }
+        }
+    }
+
+    info!(
+        repositories_refreshed = summary.repositories_refreshed,
+        repositories_failed = summary.repositories_failed,
+        repositories_remaining = summary.repositories_remaining,
+        "Scheduled re-sync finished"
+    );
+    Ok(summary)
+}
@@ -0,0 +1,144 @@
+//! Review of imported issues before they're listed: each one is `pending`,
+//! `approved` or `rejected`, and only approved issues are listed by default.
+//!
+//! Imported issues are approved when they match one of the rules of
+//! `REVIEW_AUTO_APPROVE`, and pending otherwise, until the Kudos team
+//! reviews them through `PATCH /issues/{id}/review`. Re-syncs apply the
+//! rules again to the pending issues nobody reviewed, so an issue labeled
+//! beginner-friendly later on is approved then.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::model::KudosIssue;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewState {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ReviewState {
+    pub const ALL: [ReviewState; 3] = [
+        ReviewState::Pending,
+        ReviewState::Approved,
+        ReviewState::Rejected,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReviewState::Pending => "pending",
+            ReviewState::Approved => "approved",
+            ReviewState::Rejected => "rejected",
+        }
+    }
+
+    pub fn from_db(name: &str) -> Option<Self> {
+        ReviewState::ALL
+            .into_iter()
+            .find(|state| state.as_str() == name)
+    }
+}
+
+/// A rule of `REVIEW_AUTO_APPROVE`, approving the issues it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalRule {
+    /// Every issue, skipping the review.
+    All,
+    BeginnerFriendly,
+    SeekingContributors,
+    Paid,
+}
+
+impl ApprovalRule {
+    pub const ALL: [ApprovalRule; 4] = [
+        ApprovalRule::All,
+        ApprovalRule::BeginnerFriendly,
+        ApprovalRule::SeekingContributors,
+        ApprovalRule::Paid,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApprovalRule::All => "all",
+            ApprovalRule::BeginnerFriendly => "beginner_friendly",
+            ApprovalRule::SeekingContributors => "seeking_contributors",
+            ApprovalRule::Paid => "paid",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ApprovalRule::ALL
+            .into_iter()
+            .find(|rule| rule.as_str() == name)
+    }
+
+    fn matches(self, issue: &KudosIssue) -> bool {
+        match self {
+            ApprovalRule::All => true,
+            ApprovalRule::BeginnerFriendly => issue.is_beginner_friendly,
+            ApprovalRule::SeekingContributors => issue.seeking_contributors,
+            ApprovalRule::Paid => issue.is_paid,
+        }
+    }
+}
+
+/// The review state of a classified issue nobody reviewed, by the rules of
+/// `REVIEW_AUTO_APPROVE`.
+pub fn initial_state(issue: &KudosIssue) -> ReviewState {
+    let rules: Vec<ApprovalRule> = config::get()
+        .review_auto_approve
+        .iter()
+        .filter_map(|name| ApprovalRule::from_name(name))
+        .collect();
+    state_by(&rules, issue)
+}
+
+/// [`initial_state`], with the rules approving issues.
+fn state_by(rules: &[ApprovalRule], issue: &KudosIssue) -> ReviewState {
+    if rules.iter().any(|rule| rule.matches(issue)) {
+        ReviewState::Approved
+    } else {
+        ReviewState::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approves_by_rule() {
+        let mut issue: KudosIssue = serde_json::from_value(serde_json::json!({
+            "number": 1,
+            "title": "Fix the parser",
+            "html_url": "https://github.com/owner/name/issues/1",
+            "issue_created_at": "2024-01-01T00:00:00Z",
+            "issue_updated_at": "2024-01-01T00:00:00Z",
+            "user": "user",
+            "labels": [],
+            "assignees": [],
+        }))
+        .unwrap();
+        assert_eq!(state_by(&[], &issue), ReviewState::Pending);
+        assert_eq!(
+            state_by(&[ApprovalRule::All], &issue),
+            ReviewState::Approved
+        );
+
+        let rules = [ApprovalRule::BeginnerFriendly, ApprovalRule::Paid];
+        assert_eq!(state_by(&rules, &issue), ReviewState::Pending);
+        issue.is_paid = true;
+        assert_eq!(state_by(&rules, &issue), ReviewState::Approved);
+
+        assert_eq!(
+            ReviewState::from_db("rejected"),
+            Some(ReviewState::Rejected)
+        );
+        assert_eq!(ApprovalRule::from_name("paid"), Some(ApprovalRule::Paid));
+        assert_eq!(ApprovalRule::from_name("featured"), None);
+    }
+}
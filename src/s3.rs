@@ -0,0 +1,228 @@
+//! Bulk imports of project definitions dropped in S3, so large catalog
+//! updates don't go through the HTTP path.
+//!
+//! Objects are either NDJSON (`.ndjson`/`.jsonl`, one project per line, read
+//! as a stream) or JSON holding a single project or an array of them. A
+//! report of every project's outcome, down to the decision taken on each of
+//! its issues, is written back next to the source, or to
+//! `IMPORT_REPORT_BUCKET` (see [`crate::report`]).
+
+use aws_lambda_events::s3::{S3Event, S3EventRecord};
+use aws_sdk_s3::Client;
+use lambda_http::{
+    tracing::{error, info, info_span, Instrument},
+    Error,
+};
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::AsyncBufReadExt;
+
+use crate::aws::sdk_config;
+use crate::error::ImportError;
+use crate::forge::ForgeRegistry;
+use crate::import;
+use crate::model::ImportResponse;
+use crate::report::{self, IssueDecision};
+use crate::store::PgStore;
+use crate::validation::{parse_project, PayloadError};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectOutcome {
+    Imported,
+    Invalid,
+    Failed,
+}
+
+/// What happened to one project of the file.
+#[derive(Debug, Serialize)]
+pub struct ProjectReport {
+    /// Position of the project in the file: its line for NDJSON, its index
+    /// in the array for JSON, both 1-based.
+    pub position: usize,
+    pub slug: Option<String>,
+    pub outcome: ProjectOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<ImportResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<IssueDecision>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub bucket: String,
+    pub key: String,
+    pub imported: usize,
+    pub invalid: usize,
+    pub failed: usize,
+    pub projects: Vec<ProjectReport>,
+    /// Bucket the report is written to.
+    pub report_bucket: String,
+    /// Key of the report, only in the invocation's result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report_key: Option<String>,
+}
+
+/// S3 notifications url-encode object keys, with `+` for spaces.
+fn decode_key(key: &str) -> String {
+    percent_decode_str(&key.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+async fn import_one(
+    json: &str,
+    position: usize,
+    store: &PgStore,
+    source: &ForgeRegistry,
+) -> ProjectReport {
+    let slug = serde_json::from_str::<Value>(json)
+        .ok()
+        .and_then(|value| value.get("slug")?.as_str().map(str::to_string));
+    let mut report = ProjectReport {
+        position,
+        slug,
+        outcome: ProjectOutcome::Invalid,
+        result: None,
+        error: None,
+        issues: Vec::new(),
+    };
+
+    let project = match parse_project(json, false) {
+        Ok(project) => project,
+        Err(PayloadError::Syntax(e)) => {
+            report.error = Some(Value::String(e.to_string()));
+            return report;
+        }
+        Err(PayloadError::Invalid(errors)) => {
+            report.error = serde_json::to_value(errors).ok();
+            return report;
+        }
+    };
+
+    let (result, issues) = report::collect(import::import_project(store, source, project)).await;
+    report.issues = issues;
+    match result {
+        Ok(result) => {
+            report.outcome = ProjectOutcome::Imported;
+            report.slug = Some(result.slug.clone());
+            report.result = Some(result);
+        }
+        Err(e) => {
+            error!(position, "Import failed: {}", e);
+            report.outcome = ProjectOutcome::Failed;
+            report.error = Some(Value::String(e.to_string()));
+        }
+    }
+    report
+}
+
+async fn import_object(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    store: &PgStore,
+    source: &ForgeRegistry,
+) -> Result<Vec<ProjectReport>, Error> {
+    let object = client.get_object().bucket(bucket).key(key).send().await?;
+
+    let mut projects = Vec::new();
+    if key.ends_with(".ndjson") || key.ends_with(".jsonl") {
+        let mut lines = object.body.into_async_read().lines();
+        let mut position = 0;
+        while let Some(line) = lines.next_line().await? {
+            position += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            projects.push(import_one(&line, position, store, source).await);
+        }
+    } else {
+        let bytes = object.body.collect().await?.into_bytes();
+        let entries = match serde_json::from_slice::<Value>(&bytes)? {
+            Value::Array(entries) => entries,
+            project => vec![project],
+        };
+        for (i, entry) in entries.iter().enumerate() {
+            let json = serde_json::to_string(entry)?;
+            projects.push(import_one(&json, i + 1, store, source).await);
+        }
+    }
+    Ok(projects)
+}
+
+async fn process_record(
+    record: &S3EventRecord,
+    client: &Client,
+    store: &PgStore,
+    source: &ForgeRegistry,
+) -> Result<ImportReport, Error> {
+    let bucket =
+        record.s3.bucket.name.clone().ok_or_else(|| {
+            ImportError::BadRequest("S3 record without a bucket name".to_string())
+        })?;
+    let key = record
+        .s3
+        .object
+        .key
+        .as_deref()
+        .map(decode_key)
+        .ok_or_else(|| ImportError::BadRequest("S3 record without an object key".to_string()))?;
+
+    let projects = import_object(client, &bucket, &key, store, source).await?;
+    let count = |outcome: fn(&ProjectOutcome) -> bool| {
+        projects.iter().filter(|p| outcome(&p.outcome)).count()
+    };
+    let mut report = ImportReport {
+        imported: count(|o| matches!(o, ProjectOutcome::Imported)),
+        invalid: count(|o| matches!(o, ProjectOutcome::Invalid)),
+        failed: count(|o| matches!(o, ProjectOutcome::Failed)),
+        report_bucket: report::bucket().unwrap_or_else(|| bucket.clone()),
+        report_key: None,
+        bucket,
+        key,
+        projects,
+    };
+
+    let report_key = report::write(
+        client,
+        &report.report_bucket,
+        &format!("{}.report.json", report.key),
+        &report,
+    )
+    .await?;
+    info!(
+        imported = report.imported,
+        invalid = report.invalid,
+        failed = report.failed,
+        report_key = %report_key,
+        "Bulk import finished"
+    );
+    report.report_key = Some(report_key);
+    Ok(report)
+}
+
+/// Imports every object of the event, returning a summary per object.
+/// Failing to read an object or write its report fails the invocation so S3
+/// retries it; individual project failures are only recorded in the report.
+pub async fn handle_s3_event(event: S3Event) -> Result<Vec<ImportReport>, Error> {
+    let client = Client::new(sdk_config().await);
+    let store = PgStore::connect().await?;
+    let source = ForgeRegistry::from_config().await?;
+
+    let mut reports = Vec::new();
+    for record in &event.records {
+        let key = record.s3.object.key.as_deref().unwrap_or_default();
+        let span = info_span!("s3_object", key = %key);
+        let mut report = process_record(record, &client, &store, &source)
+            .instrument(span)
+            .await?;
+        // The full detail lives in the report object; keep the invocation result small.
+        report.projects.clear();
+        reports.push(report);
+    }
+    Ok(reports)
+}
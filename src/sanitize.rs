@@ -0,0 +1,240 @@
+//! Sanitization of the text imported from the forges, before it's stored:
+//! issue titles and labels come from anyone able to open an issue, and
+//! exotic ones mustn't break the site's rendering nor overflow its layout.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Titles are cut to this many characters.
+pub const MAX_TITLE_CHARS: usize = 300;
+/// Labels are cut to this many characters.
+pub const MAX_LABEL_CHARS: usize = 100;
+
+/// Characters dropped from text: the replacement character left by invalid
+/// UTF-8, and the invisible ones changing how the text around them renders,
+/// such as bidirectional overrides and zero-width spaces.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{fffd}'
+            | '\u{200b}'..='\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{feff}'
+    )
+}
+
+/// `text` as a single line: NFC-normalized, without control and invisible
+/// characters, its whitespace trimmed and runs of it collapsed to single
+/// spaces, and cut to `max_chars` characters, the last of them an ellipsis.
+pub fn line(text: &str, max_chars: usize) -> String {
+    let cleaned: String = text
+        .nfc()
+        .filter(|c| !is_invisible(*c))
+        // Tabs and newlines separate words, other control characters nothing.
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .filter(|c| !c.is_control())
+        .collect();
+    let line = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= max_chars {
+        return line;
+    }
+    let mut cut: String = line.chars().take(max_chars.saturating_sub(1)).collect();
+    cut.truncate(cut.trim_end().len());
+    cut.push('…');
+    cut
+}
+
+/// Excerpts are cut to this many characters.
+pub const MAX_EXCERPT_CHARS: usize = 280;
+
+/// Removes everything from each `start` to the following `end`, both
+/// matched case-insensitively, up to the end of `text` if `end` is missing.
+fn remove_between(text: &str, start: &str, end: &str) -> String {
+    let lowercase = text.to_ascii_lowercase();
+    let mut kept = String::with_capacity(text.len());
+    let mut copied = 0;
+    while let Some(found) = lowercase[copied..].find(start) {
+        let from = copied + found;
+        kept.push_str(&text[copied..from]);
+        copied = lowercase[from + start.len()..]
+            .find(end)
+            .map_or(text.len(), |to| from + start.len() + to + end.len());
+        kept.push(' ');
+    }
+    kept.push_str(&text[copied..]);
+    kept
+}
+
+/// Removes the HTML tags, i.e. `<` followed by a letter, `/` or `!` up to the
+/// next `>`, leaving other `<`s, e.g. of `a < b`, alone.
+fn remove_tags(text: &str) -> String {
+    let mut kept = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        kept.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let is_tag = after
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        match after.find('>').filter(|_| is_tag) {
+            Some(end) => {
+                kept.push(' ');
+                rest = &after[end + 1..];
+            }
+            None => {
+                kept.push('<');
+                rest = after;
+            }
+        }
+    }
+    kept.push_str(rest);
+    kept
+}
+
+/// Replaces the markdown images and links with their text, or drops images
+/// unless `image_alt` is set.
+fn replace_links(text: &str, image_alt: bool) -> String {
+    let mut kept = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        let is_image = rest[..start].ends_with('!');
+        kept.push_str(&rest[..if is_image { start - 1 } else { start }]);
+        let after = &rest[start + 1..];
+        let Some(close) = after.find(']') else {
+            kept.push('[');
+            rest = after;
+            continue;
+        };
+        let label = &after[..close];
+        let target = &after[close + 1..];
+        // `[text](url)` or `[text][ref]`, anything else is kept as is.
+        let target_len = match target.chars().next() {
+            Some('(') => target.find(')').map(|end| end + 1),
+            Some('[') => target.find(']').map(|end| end + 1),
+            _ => Some(0),
+        };
+        match target_len {
+            Some(target_len) => {
+                if !is_image || image_alt {
+                    kept.push_str(label);
+                }
+                rest = &target[target_len..];
+            }
+            None => {
+                kept.push('[');
+                rest = after;
+            }
+        }
+    }
+    kept.push_str(rest);
+    kept
+}
+
+/// Strips a line's block markup: headings, quotes, list and task markers,
+/// and drops rules, table separators and link reference definitions.
+fn strip_block_markup(line: &str) -> &str {
+    let mut line = line.trim();
+    if line.starts_with('[') && line.contains("]:") {
+        return "";
+    }
+    if line.len() >= 3
+        && line
+            .chars()
+            .all(|c| matches!(c, '-' | '*' | '_' | '=' | '|' | ':' | ' '))
+    {
+        return "";
+    }
+    loop {
+        let stripped = line
+            .trim_start_matches('#')
+            .trim_start_matches('>')
+            .trim_start();
+        let stripped = ["- ", "* ", "+ ", "[ ] ", "[x] ", "[X] "]
+            .iter()
+            .find_map(|marker| stripped.strip_prefix(marker))
+            .unwrap_or(stripped);
+        let digits = stripped.len()
+            - stripped
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        let stripped = match stripped[digits..].strip_prefix(". ") {
+            Some(item) if digits > 0 => item,
+            _ => stripped,
+        };
+        if stripped.len() == line.len() {
+            return line;
+        }
+        line = stripped;
+    }
+}
+
+/// A plain-text excerpt of an issue's markdown description, safe to show as
+/// is: without HTML comments, tags nor the content of scripts and styles,
+/// without code blocks, with links reduced to their text and images dropped
+/// (or reduced to their alt text with `image_alt`), without markdown markup,
+/// and then as a [`line`] of at most [`MAX_EXCERPT_CHARS`].
+pub fn excerpt(markdown: &str, image_alt: bool) -> String {
+    let text = remove_between(markdown, "<!--", "-->");
+    let text = remove_between(&text, "<script", "</script>");
+    let text = remove_between(&text, "<style", "</style>");
+    let text = remove_tags(&text);
+
+    let mut in_code_block = false;
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if !in_code_block {
+            lines.push(strip_block_markup(line));
+        }
+    }
+    let text = replace_links(&lines.join("\n"), image_alt);
+    let text = text.replace("**", "").replace("__", "").replace("~~", "");
+    let text: String = text.chars().filter(|c| !matches!(c, '`' | '*')).collect();
+    line(&text, MAX_EXCERPT_CHARS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = r#"<!-- Thanks for opening an issue! -->
+## Description
+
+The **parser** fails on `a < b`, see [the spec](https://example.org/spec).
+<script>alert("hi")</script>
+
+![screenshot](https://example.org/shot.png)
+
+```rust
+let x = 1;
+```
+
+- [ ] Fix it
+- [x] Report it
+1. First
+---
+[spec]: https://example.org/spec
+"#;
+
+    #[test]
+    fn excerpts_plain_text() {
+        assert_eq!(
+            excerpt(BODY, false),
+            "Description The parser fails on a < b, see the spec. Fix it Report it First"
+        );
+        assert!(excerpt(BODY, true).contains("the spec. screenshot Fix it"));
+    }
+
+    #[test]
+    fn cuts_long_excerpts() {
+        let excerpt = excerpt(&"word ".repeat(100), false);
+        assert_eq!(excerpt.chars().count(), MAX_EXCERPT_CHARS);
+        assert!(excerpt.ends_with("word…"));
+    }
+}
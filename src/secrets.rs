@@ -0,0 +1,173 @@
+//! Secret settings, either given in plain text or, preferably, fetched from
+//! AWS Secrets Manager or SSM Parameter Store given the secret's ARN.
+//!
+//! Fetched values are cached in memory for `SECRETS_CACHE_SECONDS` so warm
+//! invocations don't call AWS each time, and a rotated secret is picked up
+//! within that delay. Callers noticing a stale value, e.g. a database
+//! rejecting its password, can [`invalidate`] it to fetch the current one.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use aws_sdk_secretsmanager::error::DisplayErrorContext;
+use lambda_http::tracing::info;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::aws::sdk_config;
+use crate::config;
+use crate::error::ImportError;
+use crate::redact;
+
+/// Fetched secret values by ARN, with when they were fetched.
+static CACHE: Mutex<Option<HashMap<String, (String, Instant)>>> = Mutex::const_new(None);
+
+/// Format of the database secrets managed by RDS and its rotation functions.
+#[derive(Debug, Deserialize)]
+struct DatabaseSecret {
+    username: String,
+    password: String,
+    host: String,
+    port: Option<u16>,
+    dbname: Option<String>,
+}
+
+async fn fetch(arn: &str) -> Result<String, ImportError> {
+    let sdk_config = sdk_config().await;
+    if arn.starts_with("arn:aws:ssm:") {
+        let client = aws_sdk_ssm::Client::new(sdk_config);
+        let output = client
+            .get_parameter()
+            .name(arn)
+            .with_decryption(true)
+            .send()
+            .await
+            .map_err(|e| ImportError::Secret(format!("{}: {}", arn, DisplayErrorContext(e))))?;
+        return output
+            .parameter
+            .and_then(|parameter| parameter.value)
+            .ok_or_else(|| ImportError::Secret(format!("SSM parameter `{}` has no value", arn)));
+    }
+
+    let client = aws_sdk_secretsmanager::Client::new(sdk_config);
+    let output = client
+        .get_secret_value()
+        .secret_id(arn)
+        .send()
+        .await
+        .map_err(|e| ImportError::Secret(format!("{}: {}", arn, DisplayErrorContext(e))))?;
+    output
+        .secret_string
+        .ok_or_else(|| ImportError::Secret(format!("Secret `{}` has no string value", arn)))
+}
+
+/// Returns the value of the secret at `arn`, from the cache while fresh.
+async fn secret(arn: &str) -> Result<String, ImportError> {
+    let ttl = Duration::from_secs(config::get().secrets_cache_seconds);
+    let mut cache = CACHE.lock().await;
+    let cache = cache.get_or_insert_with(HashMap::new);
+
+    if let Some((value, fetched_at)) = cache.get(arn) {
+        if fetched_at.elapsed() < ttl {
+            return Ok(value.clone());
+        }
+    }
+
+    let value = fetch(arn).await?;
+    redact::register(&value);
+    info!(arn, "Fetched secret");
+    cache.insert(arn.to_string(), (value.clone(), Instant::now()));
+    Ok(value)
+}
+
+/// Drops the cached value of the secret at `arn`, so the next read fetches
+/// its current version.
+pub async fn invalidate(arn: &str) {
+    if let Some(cache) = CACHE.lock().await.as_mut() {
+        cache.remove(arn);
+    }
+}
+
+/// The ARN of the secret holding the database URL, unless it's given in
+/// plain text.
+pub fn database_url_secret_arn() -> Option<&'static str> {
+    let config = config::get();
+    match &config.database_url {
+        Some(_) => None,
+        None => config.database_url_secret_arn.as_deref(),
+    }
+}
+
+/// The database URL, from `DATABASE_URL_SECRET_ARN` unless `DATABASE_URL` is
+/// set. The secret holds either the URL itself or RDS's JSON credentials.
+pub async fn database_url() -> Result<String, ImportError> {
+    let Some(arn) = database_url_secret_arn() else {
+        return Ok(config::get().database_url.clone().unwrap_or_default());
+    };
+
+    let value = secret(arn).await?;
+    let Ok(secret) = serde_json::from_str::<DatabaseSecret>(&value) else {
+        return Ok(value.trim().to_string());
+    };
+
+    redact::register(&secret.password);
+    let invalid = || ImportError::Secret("Invalid database secret".to_string());
+    let mut url = url::Url::parse("postgres://localhost").map_err(|_| invalid())?;
+    url.set_host(Some(&secret.host)).map_err(|_| invalid())?;
+    url.set_port(secret.port)
+        .and_then(|_| url.set_username(&secret.username))
+        .and_then(|_| url.set_password(Some(&secret.password)))
+        .map_err(|_| invalid())?;
+    url.set_path(secret.dbname.as_deref().unwrap_or_default());
+    Ok(url.to_string())
+}
+
+/// A token given in plain text, or else fetched from the secret at `arn`.
+async fn token(
+    plain: &Option<String>,
+    arn: &Option<String>,
+) -> Result<Option<String>, ImportError> {
+    match (plain, arn) {
+        (Some(token), _) => Ok(Some(token.clone())),
+        (None, Some(arn)) => Ok(Some(secret(arn).await?.trim().to_string())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// The GitHub token, from `GITHUB_TOKEN_SECRET_ARN` unless `GITHUB_TOKEN` is
+/// set.
+pub async fn github_token() -> Result<String, ImportError> {
+    let config = config::get();
+    token(&config.github_token, &config.github_token_secret_arn)
+        .await?
+        .ok_or_else(|| ImportError::Config("no GitHub token".to_string()))
+}
+
+/// The GitLab token, from `GITLAB_TOKEN_SECRET_ARN` unless `GITLAB_TOKEN` is
+/// set. Public projects can be imported without one.
+pub async fn gitlab_token() -> Result<Option<String>, ImportError> {
+    let config = config::get();
+    token(&config.gitlab_token, &config.gitlab_token_secret_arn).await
+}
+
+/// The Bitbucket access token, from `BITBUCKET_TOKEN_SECRET_ARN` unless
+/// `BITBUCKET_TOKEN` is set. Public repositories can be imported without one.
+pub async fn bitbucket_token() -> Result<Option<String>, ImportError> {
+    let config = config::get();
+    token(&config.bitbucket_token, &config.bitbucket_token_secret_arn).await
+}
+
+/// The token of the Gitea instances, from `GITEA_TOKEN_SECRET_ARN` unless
+/// `GITEA_TOKEN` is set. Public repositories can be imported without one.
+pub async fn gitea_token() -> Result<Option<String>, ImportError> {
+    let config = config::get();
+    token(&config.gitea_token, &config.gitea_token_secret_arn).await
+}
+
+/// The SourceHut personal access token, from `SOURCEHUT_TOKEN_SECRET_ARN`
+/// unless `SOURCEHUT_TOKEN` is set. SourceHut trackers can't be imported
+/// without one.
+pub async fn sourcehut_token() -> Result<Option<String>, ImportError> {
+    let config = config::get();
+    token(&config.sourcehut_token, &config.sourcehut_token_secret_arn).await
+}
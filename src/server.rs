@@ -0,0 +1,74 @@
+//! Plain HTTP server exposing the same routes as the Lambda, so the importer
+//! can be developed and tested end-to-end without AWS.
+
+use std::collections::HashMap;
+
+use axum::{
+    body::to_bytes, extract::Request as AxumRequest, http::header, response::IntoResponse,
+    response::Response as AxumResponse, routing::get,
+};
+use lambda_http::{
+    aws_lambda_events::query_map::QueryMap, tracing::info, Body, Error, Request, RequestExt,
+};
+use tokio::net::TcpListener;
+
+use crate::handler::handle_request;
+use crate::metrics;
+
+/// Request bodies larger than this are rejected, mirroring API Gateway's limit.
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+fn query_map(query: Option<&str>) -> QueryMap {
+    let mut params: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, value) in url::form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        params
+            .entry(key.into_owned())
+            .or_default()
+            .push(value.into_owned());
+    }
+    params.into()
+}
+
+/// Converts an axum request into the shape the Lambda runtime hands to
+/// [`handle_request`].
+async fn to_lambda_request(request: AxumRequest) -> Result<Request, Error> {
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_BYTES).await?;
+    let body = match String::from_utf8(bytes.to_vec()) {
+        Ok(text) if text.is_empty() => Body::Empty,
+        Ok(text) => Body::Text(text),
+        Err(e) => Body::Binary(e.into_bytes()),
+    };
+    let query = query_map(parts.uri.query());
+    Ok(Request::from_parts(parts, body).with_query_string_parameters(query))
+}
+
+async fn proxy(request: AxumRequest) -> Result<AxumResponse, String> {
+    let request = to_lambda_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    let response = handle_request(request).await.map_err(|e| e.to_string())?;
+    let (parts, body) = response.into_parts();
+    Ok(AxumResponse::from_parts(parts, body.to_vec().into()))
+}
+
+/// `GET /metrics`, the import metrics of this process for Prometheus, which
+/// the Lambda publishes to CloudWatch instead.
+async fn prometheus_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::prometheus(),
+    )
+}
+
+/// Serves every route on `addr`, and `/metrics`, until the process is
+/// stopped.
+pub async fn serve(addr: &str) -> Result<(), Error> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("Listening on http://{}", listener.local_addr()?);
+    let router = axum::Router::new()
+        .route("/metrics", get(prometheus_metrics))
+        .fallback(proxy);
+    axum::serve(listener, router).await?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Project slugs, used in the site's urls: checked when a payload gives one,
+//! derived from the project's name when it doesn't.
+
+use crate::error::ImportError;
+use crate::model::Project;
+use crate::store::Store;
+
+pub const MIN_LEN: usize = 2;
+pub const MAX_LEN: usize = 64;
+
+/// Suffixes tried on a derived slug taken by another project, `-2` first.
+const MAX_SUFFIX: u32 = 100;
+
+/// Why `slug` can't be used, if it can't: slugs are lowercase ASCII letters
+/// and digits, words separated by single hyphens.
+pub fn problem(slug: &str) -> Option<String> {
+    if !(MIN_LEN..=MAX_LEN).contains(&slug.len()) {
+        return Some(format!(
+            "slugs are {} to {} characters long",
+            MIN_LEN, MAX_LEN
+        ));
+    }
+    let is_word = |word: &str| {
+        !word.is_empty()
+            && word
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    };
+    (!slug.split('-').all(is_word)).then(|| {
+        "slugs are lowercase letters and digits, words separated by single hyphens".to_string()
+    })
+}
+
+/// The slug of a project named `name`: its ASCII letters and digits,
+/// lowercase, words separated by hyphens.
+pub fn from_name(name: &str) -> String {
+    let lowercase = name.to_ascii_lowercase();
+    let words: Vec<&str> = lowercase
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let mut slug = words.join("-");
+    if slug.len() > MAX_LEN {
+        slug.truncate(MAX_LEN);
+        slug.truncate(slug.trim_end_matches('-').len());
+    }
+    if slug.len() < MIN_LEN {
+        slug = if slug.is_empty() {
+            "project".to_string()
+        } else {
+            format!("project-{}", slug)
+        };
+    }
+    slug
+}
+
+/// `base` suffixed with `-{n}`, cut so that it stays within [`MAX_LEN`].
+fn suffixed(base: &str, n: u32) -> String {
+    let suffix = format!("-{}", n);
+    let base = &base[..base.len().min(MAX_LEN - suffix.len())];
+    format!("{}{}", base.trim_end_matches('-'), suffix)
+}
+
+/// Gives `project` a slug derived from its name if the payload had none,
+/// suffixed with `-2`, `-3`… when other projects have it already.
+pub async fn assign(store: &dyn Store, project: &mut Project) -> Result<(), ImportError> {
+    if !project.slug.is_empty() {
+        return Ok(());
+    }
+    let base = from_name(&project.name);
+    for n in 1..=MAX_SUFFIX {
+        let slug = if n == 1 {
+            base.clone()
+        } else {
+            suffixed(&base, n)
+        };
+        if store.find_project_id(&slug).await?.is_none() {
+            project.slug = slug;
+            return Ok(());
+        }
+    }
+    Err(ImportError::BadRequest(format!(
+        "No free slug left for `{}`, give one",
+        project.name
+    )))
+}
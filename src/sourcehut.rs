@@ -0,0 +1,321 @@
+//! Access to the todo.sr.ht GraphQL API, for SourceHut todo trackers such as
+//! `https://todo.sr.ht/~owner/tracker`.
+//!
+//! The API only serves authenticated clients, so SourceHut trackers can only
+//! be imported when a token is configured. Its listings are paged with
+//! opaque cursors and can't be filtered by status: trackers are walked from
+//! the first page, and resolved tickets are dropped here.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::breaker::{CircuitBreaker, Upstream};
+use crate::error::ImportError;
+use crate::forge::{self, Forge};
+use crate::github::{IssuePage, IssueSource, RepositoryMetadata, RepositoryStatus};
+use crate::model::{ForgeKind, KudosIssue, RepoInfo};
+use crate::review::ReviewState;
+use crate::secrets;
+
+const FORGE: &str = "SourceHut";
+
+const API_URL: &str = "https://todo.sr.ht/query";
+
+const TRACKER_QUERY: &str = r#"
+query Tracker($owner: String!, $tracker: String!) {
+  user(username: $owner) {
+    tracker(name: $tracker) { id }
+  }
+}"#;
+
+const TICKETS_QUERY: &str = r#"
+query Tickets($owner: String!, $tracker: String!, $cursor: Cursor) {
+  user(username: $owner) {
+    tracker(name: $tracker) {
+      tickets(cursor: $cursor) {
+        cursor
+        results {
+          id
+          subject
+          body
+          created
+          updated
+          status
+          submitter { canonicalName }
+          assignees { canonicalName }
+          labels { name }
+        }
+      }
+    }
+  }
+}"#;
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserData<T> {
+    user: Option<User<T>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct User<T> {
+    tracker: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrackerTickets {
+    tickets: TicketCursor,
+}
+
+#[derive(Debug, Deserialize)]
+struct TicketCursor {
+    /// Where the next page starts, `None` on the last one.
+    cursor: Option<String>,
+    results: Vec<Ticket>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entity {
+    /// `~username` for users.
+    canonical_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ticket {
+    id: i64,
+    subject: String,
+    body: Option<String>,
+    created: DateTime<Utc>,
+    updated: DateTime<Utc>,
+    /// `REPORTED`, `CONFIRMED`, `IN_PROGRESS`, `PENDING` or `RESOLVED`.
+    status: String,
+    submitter: Entity,
+    assignees: Vec<Entity>,
+    labels: Vec<Label>,
+}
+
+fn login(entity: Entity) -> String {
+    entity.canonical_name.trim_start_matches('~').to_string()
+}
+
+impl Ticket {
+    fn into_issue(self, repo_info: &RepoInfo) -> KudosIssue {
+        KudosIssue {
+            number: self.id,
+            title: self.subject,
+            html_url: repo_info.issue_url(self.id),
+            issue_created_at: self.created,
+            issue_updated_at: self.updated,
+            user: login(self.submitter),
+            labels: self.labels.into_iter().map(|label| label.name).collect(),
+            assignees: self.assignees.into_iter().map(login).collect(),
+            body: self.body,
+            excerpt: None,
+            is_beginner_friendly: false,
+            categories: Vec::new(),
+            is_paid: false,
+            reward: None,
+            difficulty: None,
+            issue_type: None,
+            program_tags: Vec::new(),
+            staleness: None,
+            seeking_contributors: false,
+            review_state: ReviewState::Pending,
+            referenced_issues: Vec::new(),
+            reactions: 0,
+            ranking_score: None,
+        }
+    }
+}
+
+/// [`IssueSource`] backed by the todo.sr.ht GraphQL API.
+pub struct SourceHutSource {
+    client: reqwest::Client,
+    token: String,
+    /// Cursor starting each page after the first, by tracker url and page.
+    cursors: Mutex<HashMap<(String, u32), String>>,
+    breaker: CircuitBreaker,
+}
+
+impl SourceHutSource {
+    pub fn new(client: reqwest::Client, token: String) -> Self {
+        SourceHutSource {
+            client,
+            token,
+            cursors: Mutex::new(HashMap::new()),
+            breaker: CircuitBreaker::new(Upstream::Forge(FORGE)),
+        }
+    }
+
+    /// Builds a client authenticated with the SourceHut token, or `None`
+    /// when there's no token to use the API with.
+    pub async fn from_config() -> Result<Option<Self>, ImportError> {
+        let Some(token) = secrets::sourcehut_token().await? else {
+            return Ok(None);
+        };
+        Ok(Some(SourceHutSource::new(forge::client()?, token)))
+    }
+
+    /// Runs a query about the repository's tracker, returning `None` when
+    /// there's no such tracker.
+    async fn query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        repo_info: &RepoInfo,
+        cursor: Option<&str>,
+    ) -> Result<Option<T>, ImportError> {
+        let variables = json!({
+            "owner": repo_info.owner.trim_start_matches('~'),
+            "tracker": repo_info.name,
+            "cursor": cursor,
+        });
+        let request = self
+            .client
+            .post(API_URL)
+            .bearer_auth(&self.token)
+            .json(&json!({ "query": query, "variables": variables }));
+        let response = forge::send(FORGE, request).await?;
+        let response: GraphQlResponse<UserData<T>> = forge::json(FORGE, response).await?;
+
+        if !response.errors.is_empty() {
+            let messages: Vec<String> = response.errors.into_iter().map(|e| e.message).collect();
+            return Err(ImportError::Forge {
+                forge: FORGE,
+                status: None,
+                message: messages.join("; "),
+            });
+        }
+        Ok(response
+            .data
+            .and_then(|data| data.user)
+            .and_then(|user| user.tracker))
+    }
+
+    /// Fetches one page of the tracker's tickets, remembering where the next
+    /// one starts.
+    async fn tickets(
+        &self,
+        repo_info: &RepoInfo,
+        page: u32,
+        cursor: Option<&str>,
+    ) -> Result<TicketCursor, ImportError> {
+        let tickets: Option<TrackerTickets> = self.query(TICKETS_QUERY, repo_info, cursor).await?;
+        let tickets = tickets
+            .ok_or_else(|| ImportError::NotFound(format!("Tracker {}", repo_info.url())))?
+            .tickets;
+        if let Some(next) = &tickets.cursor {
+            self.cursors
+                .lock()
+                .unwrap()
+                .insert((repo_info.url(), page + 1), next.clone());
+        }
+        Ok(tickets)
+    }
+
+    /// The cursor starting `page`, walking the earlier pages when it isn't
+    /// known yet, e.g. when resuming a sync in a new invocation.
+    async fn cursor(&self, repo_info: &RepoInfo, page: u32) -> Result<Option<String>, ImportError> {
+        let mut known = 1;
+        let mut cursor = None;
+        {
+            let cursors = self.cursors.lock().unwrap();
+            for p in (2..=page).rev() {
+                if let Some(c) = cursors.get(&(repo_info.url(), p)) {
+                    known = p;
+                    cursor = Some(c.clone());
+                    break;
+                }
+            }
+        }
+        while known < page {
+            let tickets = self.tickets(repo_info, known, cursor.as_deref()).await?;
+            let Some(next) = tickets.cursor else {
+                return Ok(None);
+            };
+            cursor = Some(next);
+            known += 1;
+        }
+        Ok(cursor)
+    }
+}
+
+impl Forge for SourceHutSource {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::SourceHut
+    }
+}
+
+#[async_trait]
+impl IssueSource for SourceHutSource {
+    /// SourceHut trackers can't be archived.
+    async fn repository_status(
+        &self,
+        repo_info: &RepoInfo,
+    ) -> Result<RepositoryStatus, ImportError> {
+        self.breaker
+            .call(async {
+                let tracker: Option<IgnoredAny> =
+                    self.query(TRACKER_QUERY, repo_info, None).await?;
+                Ok(match tracker {
+                    Some(_) => RepositoryStatus::Active(RepositoryMetadata::default()),
+                    None => RepositoryStatus::NotFound,
+                })
+            })
+            .await
+    }
+
+    async fn open_issues(&self, repo_info: &RepoInfo, page: u32) -> Result<IssuePage, ImportError> {
+        self.breaker
+            .call(async {
+                let cursor = match page {
+                    0 | 1 => None,
+                    _ => match self.cursor(repo_info, page).await? {
+                        Some(cursor) => Some(cursor),
+                        // The tracker has fewer pages than it used to.
+                        None => {
+                            return Ok(IssuePage {
+                                issues: Vec::new(),
+                                has_next: false,
+                                pull_requests: 0,
+                            })
+                        }
+                    },
+                };
+                let tickets = self.tickets(repo_info, page, cursor.as_deref()).await?;
+
+                Ok(IssuePage {
+                    has_next: tickets.cursor.is_some(),
+                    pull_requests: 0,
+                    issues: tickets
+                        .results
+                        .into_iter()
+                        .filter(|ticket| ticket.status != "RESOLVED")
+                        .map(|ticket| ticket.into_issue(repo_info))
+                        .collect(),
+                })
+            })
+            .await
+    }
+}
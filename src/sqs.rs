@@ -0,0 +1,225 @@
+//! Imports driven by SQS messages whose bodies are project payloads, so
+//! callers can queue imports and get retries instead of waiting on HTTP.
+//! Messages can also carry a single [`Task`], as queued by [`crate::fanout`].
+//! Each message runs as a [job](crate::jobs).
+
+use aws_lambda_events::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent, SqsMessage};
+use lambda_http::{
+    tracing::{error, info, info_span, Instrument},
+    Error,
+};
+use serde_json::Value;
+
+use crate::fanout::{fan_out, fanout_queue};
+use crate::forge::ForgeRegistry;
+use crate::import::{self, Retry};
+use crate::jobs::run_job;
+use crate::model::ImportResponse;
+use crate::model::Project;
+use crate::step_functions::{is_task, run_task, Task};
+use crate::store::{PgStore, Store};
+use crate::validation::{parse_project, PayloadError};
+
+/// What a message asks for.
+#[derive(Debug)]
+pub enum MessagePayload {
+    Project(Project),
+    Task(Task),
+}
+
+/// Parses a message body, describing the problem when it can't be processed.
+pub fn parse_message(body: &str) -> Result<MessagePayload, String> {
+    if serde_json::from_str::<Value>(body).is_ok_and(|payload| is_task(&payload)) {
+        return serde_json::from_str(body)
+            .map(MessagePayload::Task)
+            .map_err(|e| format!("invalid task: {}", e));
+    }
+
+    match parse_project(body, false) {
+        Ok(project) => Ok(MessagePayload::Project(project)),
+        Err(PayloadError::Syntax(e)) => Err(format!("body is not valid JSON: {}", e)),
+        Err(PayloadError::Invalid(errors)) => Err(format!(
+            "invalid project payload: {}",
+            serde_json::to_string(&errors).unwrap_or_default()
+        )),
+    }
+}
+
+/// Imports the project, or runs the task, carried by a message. Returns the
+/// result of the import, `None` for tasks and fanned out projects. Retried
+/// imports continue from the checkpoint of the earlier attempts, keeping the
+/// project they inserted.
+pub async fn process_payload(
+    payload: MessagePayload,
+    store: &PgStore,
+    source: &ForgeRegistry,
+    retry: Option<Retry<'_>>,
+) -> Result<Option<ImportResponse>, Error> {
+    let project = match payload {
+        MessagePayload::Task(task) => {
+            run_task(store, source, task).await?;
+            return Ok(None);
+        }
+        MessagePayload::Project(project) => project,
+    };
+
+    if let Some(queue_url) = fanout_queue(&project) {
+        fan_out(store, &queue_url, project, retry).await?;
+        return Ok(None);
+    }
+
+    let result = match retry {
+        Some(retry) => {
+            let checkpoint = retry.checkpoint(store).await?;
+            import::resume_import(store, source, project, retry.token, checkpoint, None).await?
+        }
+        None => import::import_project(store, source, project).await?,
+    };
+    info!(
+        project_id = result.project_id,
+        total_issues_imported = result.total_issues_imported,
+        "Imported project from queue"
+    );
+    Ok(Some(result))
+}
+
+/// Processes one message through the `jobs` table, keyed by message id so a
+/// redelivered message already done or running isn't run again. Only failing
+/// to queue or record the job is an error, the job's own retries are left to
+/// the scheduled run.
+async fn process_message(
+    message: &SqsMessage,
+    store: &PgStore,
+    source: &ForgeRegistry,
+) -> Result<(), Error> {
+    let body = message.body.as_deref().unwrap_or_default();
+    let job_id = store
+        .enqueue_job(message.message_id.as_deref(), body)
+        .await?;
+
+    match store.claim_job(job_id).await? {
+        Some(job) => {
+            run_job(store, source, &job)
+                .instrument(info_span!("job", job_id))
+                .await?;
+        }
+        None => info!(job_id, "Job already claimed, skipping message"),
+    }
+    Ok(())
+}
+
+/// Processes every message of the batch, reporting the failed ones as partial
+/// batch failures. The event source mapping needs `ReportBatchItemFailures`
+/// enabled for only those to be retried.
+pub async fn handle_sqs_event(event: SqsEvent) -> Result<SqsBatchResponse, Error> {
+    let store = PgStore::connect().await?;
+    let source = ForgeRegistry::from_config().await?;
+
+    let mut batch_item_failures = Vec::new();
+    for message in &event.records {
+        let message_id = message.message_id.clone().unwrap_or_default();
+        let span = info_span!("sqs_message", message_id = %message_id);
+
+        if let Err(e) = process_message(message, &store, &source)
+            .instrument(span.clone())
+            .await
+        {
+            span.in_scope(|| error!("Couldn't process message, it will be retried: {}", e));
+            batch_item_failures.push(BatchItemFailure {
+                item_identifier: message_id,
+            });
+        }
+    }
+
+    Ok(SqsBatchResponse {
+        batch_item_failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use async_trait::async_trait;
+    use sqlx::Row;
+
+    use super::*;
+    use crate::dlq;
+    use crate::error::ImportError;
+    use crate::forge::Forge;
+    use crate::github::{IssuePage, IssueSource, RepositoryMetadata, RepositoryStatus};
+    use crate::model::{ForgeKind, RepoInfo};
+    use crate::test_db;
+
+    /// A repository without issues, whose first status check fails.
+    #[derive(Default)]
+    struct FlakyForge {
+        failed: AtomicBool,
+    }
+
+    #[async_trait]
+    impl IssueSource for FlakyForge {
+        async fn repository_status(
+            &self,
+            _repo_info: &RepoInfo,
+        ) -> Result<RepositoryStatus, ImportError> {
+            if !self.failed.swap(true, Ordering::Relaxed) {
+                return Err(ImportError::Secret("token expired".to_string()));
+            }
+            Ok(RepositoryStatus::Active(RepositoryMetadata::default()))
+        }
+
+        async fn open_issues(
+            &self,
+            _repo_info: &RepoInfo,
+            _page: u32,
+        ) -> Result<IssuePage, ImportError> {
+            Ok(IssuePage {
+                issues: Vec::new(),
+                has_next: false,
+                pull_requests: 0,
+            })
+        }
+    }
+
+    impl Forge for FlakyForge {
+        fn kind(&self) -> ForgeKind {
+            ForgeKind::GitHub
+        }
+    }
+
+    const PAYLOAD: &str = r#"{
+        "name": "Retried",
+        "attributes": {"purposes": [], "stackLevels": [], "technologies": [], "types": []},
+        "links": {"repository": [{"label": "Retried", "url": "https://github.com/kudos-ink/retried"}]}
+    }"#;
+
+    #[tokio::test]
+    async fn retried_imports_keep_their_project() {
+        let Some((store, pool)) = test_db::store("retried_imports").await else {
+            return;
+        };
+        let source = ForgeRegistry::new(vec![Box::new(FlakyForge::default())]);
+        let retry = Retry {
+            token: "job-1",
+            payload: PAYLOAD,
+        };
+
+        let payload = parse_message(PAYLOAD).unwrap();
+        let failed = process_payload(payload, &store, &source, Some(retry)).await;
+        assert!(failed.is_err_and(|e| dlq::classify(&e).is_transient()));
+        let payload = parse_message(PAYLOAD).unwrap();
+        let imported = process_payload(payload, &store, &source, Some(retry))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let projects = sqlx::query("SELECT COUNT(*) AS count, MIN(id) AS id FROM projects")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(projects.get::<i64, _>("count"), 1);
+        assert_eq!(projects.get::<i32, _>("id"), imported.project_id);
+        assert_eq!(imported.repositories.len(), 1);
+    }
+}
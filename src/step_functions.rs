@@ -0,0 +1,208 @@
+//! Step Functions integration, so imports too large for one invocation can be
+//! orchestrated as a state machine: a `prepare` step creates the project and
+//! lists its repositories, then a Map state runs one `import_repository` step
+//! per repository, each retried on its own by the state machine.
+//!
+//! Steps are usually invoked synchronously and return their output. When
+//! invoked with `.waitForTaskToken`, pass the token as `task_token`: the step
+//! then reports its outcome with `SendTaskSuccess`/`SendTaskFailure` and sends
+//! heartbeats while it runs.
+
+use std::{future::Future, time::Duration};
+
+use aws_sdk_sfn::Client;
+use lambda_http::{
+    tracing::{error, info},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::aws::sdk_config;
+use crate::config;
+use crate::error::ImportError;
+use crate::filters::IssueFilters;
+use crate::forge::ForgeRegistry;
+use crate::github::IssueSource;
+use crate::import::{defer_repository, import_repository, prepare_project};
+use crate::model::{ImportFilters, ImportWarning, ImportedRepository, Repository};
+use crate::store::{PgStore, Store};
+use crate::validation::parse_project;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "task", rename_all = "snake_case")]
+pub enum Task {
+    /// Creates the project and returns the repositories to import.
+    Prepare { project: Value },
+    /// Imports one repository of a prepared project.
+    ImportRepository {
+        project_id: i32,
+        repository: Repository,
+        /// The project's, see [`PreparedProject::filters`].
+        #[serde(default)]
+        filters: ImportFilters,
+    },
+}
+
+/// Payload of a state machine step.
+#[derive(Debug, Deserialize)]
+pub struct TaskInput {
+    #[serde(flatten)]
+    pub task: Task,
+    pub task_token: Option<String>,
+}
+
+/// Output of the `prepare` step, to be fed to a Map state over `repositories`.
+#[derive(Debug, Serialize)]
+pub struct PreparedProject {
+    pub project_id: i32,
+    pub slug: String,
+    /// Project-level warnings, e.g. attribute values outside the taxonomy.
+    pub warnings: Vec<ImportWarning>,
+    pub repositories: Vec<Repository>,
+    /// To pass along each repository to its `import_repository` step.
+    pub filters: ImportFilters,
+}
+
+/// Output of an `import_repository` step; `repository` is `None` when the
+/// repository was skipped, with the reason in `warnings`.
+#[derive(Debug, Serialize)]
+pub struct RepositoryStep {
+    pub repository: Option<ImportedRepository>,
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Only step payloads have a top level `task`.
+pub fn is_task(payload: &Value) -> bool {
+    payload.get("task").is_some_and(Value::is_string)
+}
+
+async fn prepare(store: &dyn Store, project: Value) -> Result<PreparedProject, Error> {
+    let mut project = parse_project(&project.to_string(), false).map_err(ImportError::from)?;
+
+    let warnings = prepare_project(store, &mut project).await?;
+    let project_id = store.insert_project(&project).await?;
+    info!(project_id, slug = %project.slug, "Project prepared");
+
+    Ok(PreparedProject {
+        project_id,
+        slug: project.slug,
+        warnings,
+        repositories: project.links.repository,
+        filters: project.filters,
+    })
+}
+
+async fn import_repository_step(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    project_id: i32,
+    repository: Repository,
+    filters: &ImportFilters,
+) -> Result<RepositoryStep, Error> {
+    let config = config::get();
+    let filters =
+        IssueFilters::new(&config.label_blocklist, filters).map_err(ImportError::BadRequest)?;
+    let mut warnings = Vec::new();
+    let repository = if config.github_degraded {
+        let reason = "GitHub unavailable";
+        defer_repository(store, project_id, &repository, &mut warnings, reason).await?
+    } else {
+        import_repository(
+            store,
+            source,
+            project_id,
+            repository,
+            &filters,
+            &mut warnings,
+            None,
+        )
+        .await?
+    };
+    if let Some(repository) = &repository {
+        info!(
+            project_id,
+            repository = %repository.url,
+            issues_imported = repository.issues_imported,
+            "Repository step finished"
+        );
+    }
+
+    Ok(RepositoryStep {
+        repository,
+        warnings,
+    })
+}
+
+/// Runs a step, returning its output.
+pub async fn run_task(
+    store: &dyn Store,
+    source: &dyn IssueSource,
+    task: Task,
+) -> Result<Value, Error> {
+    match task {
+        Task::Prepare { project } => Ok(serde_json::to_value(prepare(store, project).await?)?),
+        Task::ImportRepository {
+            project_id,
+            repository,
+            filters,
+        } => Ok(serde_json::to_value(
+            import_repository_step(store, source, project_id, repository, &filters).await?,
+        )?),
+    }
+}
+
+/// Runs `work` while sending a heartbeat for the task every interval.
+async fn with_heartbeats<F: Future>(client: &Client, task_token: &str, work: F) -> F::Output {
+    tokio::pin!(work);
+    let mut heartbeats =
+        tokio::time::interval(Duration::from_secs(config::get().sfn_heartbeat_seconds));
+    // The first tick completes immediately, there's nothing to report yet.
+    heartbeats.tick().await;
+    loop {
+        tokio::select! {
+            output = &mut work => return output,
+            _ = heartbeats.tick() => {
+                let sent = client.send_task_heartbeat().task_token(task_token).send().await;
+                if let Err(e) = sent {
+                    error!("Failed to send task heartbeat: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Runs a state machine step. With a task token the outcome is sent to Step
+/// Functions and the invocation itself returns `null`.
+pub async fn handle_task(input: TaskInput) -> Result<Value, Error> {
+    let store = PgStore::connect().await?;
+    let source = ForgeRegistry::from_config().await?;
+    let task = run_task(&store, &source, input.task);
+
+    let Some(task_token) = input.task_token else {
+        return task.await;
+    };
+
+    let client = Client::new(sdk_config().await);
+    match with_heartbeats(&client, &task_token, task).await {
+        Ok(output) => {
+            client
+                .send_task_success()
+                .task_token(&task_token)
+                .output(output.to_string())
+                .send()
+                .await?;
+        }
+        Err(e) => {
+            error!("Task failed: {}", e);
+            client
+                .send_task_failure()
+                .task_token(&task_token)
+                .error("ImportFailed")
+                .cause(e.to_string())
+                .send()
+                .await?;
+        }
+    }
+    Ok(Value::Null)
+}
@@ -0,0 +1,1747 @@
+//! Storage of imported projects, repositories and issues, behind the
+//! [`Store`] trait so the pipeline doesn't depend on Postgres directly.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::LevelFilter;
+use sqlx::postgres::{PgConnectOptions, PgPool, PgRow};
+use sqlx::types::Json;
+use sqlx::{ConnectOptions, Row};
+
+use crate::backfill::ClosedIssue;
+use crate::breaker::{CircuitBreaker, Upstream};
+use crate::classify::{Difficulty, IssueType, Reward};
+use crate::config;
+use crate::contributors::ContributorStats;
+use crate::duplicates;
+use crate::error::ImportError;
+use crate::friendliness::Onboarding;
+use crate::health::RepositoryHealth;
+use crate::languages::Languages;
+use crate::model::{
+    issue_url, CuratedIssue, ForgeKind, ImportCheckpoint, ImportFailure, ImportFilters,
+    IssueCuration, Job, JobState, KudosIssue, LabelStats, ListedIssue, Pagination, Project,
+    ProjectStats, RepoInfo, RepositoryStats, ReviewedIssue, StoredCheckpoint, StoredRepository,
+    SyncCursor,
+};
+use crate::responsiveness::Responsiveness;
+use crate::review::ReviewState;
+use crate::secrets;
+
+/// Optional filters of the issue listing.
+#[derive(Debug, Default)]
+pub struct IssueFilter<'a> {
+    pub label: Option<&'a str>,
+    pub repository: Option<&'a str>,
+    pub since: Option<DateTime<Utc>>,
+    pub beginner_friendly: Option<bool>,
+    /// A `category:value` tag, see [`crate::classify`].
+    pub category: Option<&'a str>,
+    pub paid: Option<bool>,
+    pub difficulty: Option<Difficulty>,
+    pub issue_type: Option<IssueType>,
+    /// A program, e.g. `hacktoberfest`, see [`crate::classify::programs`].
+    pub program: Option<&'a str>,
+    /// Leaves out issues staler than this, and those not scored yet.
+    pub max_staleness: Option<f64>,
+    pub seeking_contributors: Option<bool>,
+    /// See [`crate::review`].
+    pub review_state: ReviewState,
+    pub sort: IssueSort,
+}
+
+/// Order of the issue listing, featured issues always first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IssueSort {
+    /// Most recently created first.
+    #[default]
+    Newest,
+    /// Highest reward value first, then newest, issues without one last.
+    Reward,
+    /// Highest ranking score first, see [`crate::ranking`].
+    Rank,
+}
+
+impl IssueSort {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "newest" => Some(IssueSort::Newest),
+            "reward" => Some(IssueSort::Reward),
+            "rank" => Some(IssueSort::Rank),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+pub trait Store: Send + Sync {
+    /// Inserts a project row, and its import rules for re-syncs to apply.
+    async fn insert_project(&self, project: &Project) -> Result<i32, ImportError>;
+
+    /// Inserts a repository row for the repository `repo_info` describes,
+    /// keeping its forge, scope and programs for re-syncs. The project's row
+    /// for the url, left by an earlier attempt of the import, is updated and
+    /// reused instead.
+    async fn insert_repository(
+        &self,
+        slug: &str,
+        project_id: i32,
+        repo_info: &RepoInfo,
+    ) -> Result<i32, ImportError>;
+
+    /// Inserts or refreshes a repository's issues, returning how many rows
+    /// were written. Upserted issues are marked open again, those marked
+    /// closed before counting as reopened, and get their project's
+    /// technologies and purposes.
+    async fn upsert_issues(&self, repo_id: i32, issues: &[KudosIssue]) -> Result<u64, ImportError>;
+
+    /// Marks the repository's open issues whose number isn't in `open_numbers`
+    /// as closed, returning how many were.
+    async fn mark_stale(&self, repo_id: i32, open_numbers: &[i64]) -> Result<u64, ImportError>;
+
+    /// Records that the repository's issues still have to be synced, e.g.
+    /// because GitHub was unavailable when it was imported, from `cursor`
+    /// when given, or else from where the last stopped sync left off.
+    async fn mark_sync_pending(
+        &self,
+        repo_id: i32,
+        reason: &str,
+        cursor: Option<&SyncCursor>,
+    ) -> Result<(), ImportError>;
+
+    /// Records a successful sync, clearing any pending one and past failures.
+    async fn mark_synced(&self, repo_id: i32) -> Result<(), ImportError>;
+
+    /// Records a failed sync attempt, leaving any pending sync pending.
+    async fn record_sync_failure(&self, repo_id: i32, error: &str) -> Result<(), ImportError>;
+
+    /// Records the repository's contributor statistics, replacing the last.
+    async fn record_contributors(
+        &self,
+        repo_id: i32,
+        stats: &ContributorStats,
+    ) -> Result<(), ImportError>;
+
+    /// Records what the repository offers first-time contributors, replacing
+    /// the last check.
+    async fn record_onboarding(
+        &self,
+        repo_id: i32,
+        onboarding: &Onboarding,
+    ) -> Result<(), ImportError>;
+
+    /// Records the repository's languages, replacing the last.
+    async fn record_languages(
+        &self,
+        repo_id: i32,
+        languages: &Languages,
+    ) -> Result<(), ImportError>;
+
+    /// Adds `technology` to the technologies of the repository's project if
+    /// it was imported without any, or they already come from languages,
+    /// and to its issues'.
+    async fn backfill_technology(&self, repo_id: i32, technology: &str) -> Result<(), ImportError>;
+
+    /// Returns the repository's last health score, `None` when it was never
+    /// scored.
+    async fn health_score(&self, repo_id: i32) -> Result<Option<f64>, ImportError>;
+
+    /// Records the repository's health, replacing the last.
+    async fn record_health(
+        &self,
+        repo_id: i32,
+        health: &RepositoryHealth,
+    ) -> Result<(), ImportError>;
+
+    /// Records the repository's measured responsiveness, replacing the last.
+    async fn record_responsiveness(
+        &self,
+        repo_id: i32,
+        responsiveness: &Responsiveness,
+    ) -> Result<(), ImportError>;
+
+    /// Returns the repositories of the project, or of every project.
+    async fn repositories(
+        &self,
+        project_id: Option<i32>,
+    ) -> Result<Vec<StoredRepository>, ImportError>;
+
+    /// Inserts or refreshes issues of the repository fetched by the closed
+    /// issues backfill, returning how many rows were written.
+    async fn upsert_closed_issues(
+        &self,
+        repo_id: i32,
+        issues: &[ClosedIssue],
+    ) -> Result<u64, ImportError>;
+
+    /// Returns up to `limit` repositories with a pending sync not attempted
+    /// since it was requested, or neither synced nor attempted since
+    /// `synced_before`: pending ones first, then the least recently synced or
+    /// attempted. A pending sync that failed waits like any other.
+    async fn stale_repositories(
+        &self,
+        synced_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<StoredRepository>, ImportError>;
+
+    async fn find_project_id(&self, slug: &str) -> Result<Option<i32>, ImportError>;
+
+    /// Returns one page of a project's open issues of the filter's review
+    /// state, featured then newest first and hidden ones and duplicates left
+    /// out, with the total number of issues matching the filter.
+    async fn list_issues(
+        &self,
+        project_id: i32,
+        filter: &IssueFilter<'_>,
+        pagination: Pagination,
+    ) -> Result<(Vec<ListedIssue>, i64), ImportError>;
+
+    /// Marks each open issue of the repository's project duplicating an
+    /// earlier one of another repository as its duplicate, see
+    /// [`crate::duplicates`], returning how many issues changed.
+    async fn mark_duplicates(&self, repo_id: i32) -> Result<u64, ImportError>;
+
+    /// Sets an issue's curation flags, returning them all, or `None` if
+    /// there's no such issue.
+    async fn curate_issue(
+        &self,
+        id: i32,
+        curation: &IssueCuration,
+    ) -> Result<Option<CuratedIssue>, ImportError>;
+
+    /// Sets an issue's review state, returning it, or `None` if there's no
+    /// such issue.
+    async fn review_issue(
+        &self,
+        id: i32,
+        state: ReviewState,
+    ) -> Result<Option<ReviewedIssue>, ImportError>;
+
+    /// Returns the statistics of a project's publicly listed issues.
+    async fn project_stats(&self, project_id: i32) -> Result<ProjectStats, ImportError>;
+
+    /// Returns the rows of the `feature_flags` table, by name.
+    async fn feature_flags(&self) -> Result<Vec<(String, bool)>, ImportError>;
+
+    /// Returns the rows of the `taxonomy` table, as facet and value.
+    async fn taxonomy(&self) -> Result<Vec<(String, String)>, ImportError>;
+
+    /// Returns the rows of the `label_categories` table, as label pattern,
+    /// category and value.
+    async fn label_categories(&self) -> Result<Vec<(String, String, String)>, ImportError>;
+
+    async fn create_checkpoint(&self, token: &str, payload: &str) -> Result<(), ImportError>;
+
+    async fn load_checkpoint(&self, token: &str) -> Result<Option<StoredCheckpoint>, ImportError>;
+
+    /// Records the import's progress, clearing the cursor of the repository
+    /// that was being synced.
+    async fn save_checkpoint(
+        &self,
+        token: &str,
+        state: &ImportCheckpoint,
+    ) -> Result<(), ImportError>;
+
+    async fn save_sync_cursor(&self, token: &str, cursor: &SyncCursor) -> Result<(), ImportError>;
+
+    async fn delete_checkpoint(&self, token: &str) -> Result<(), ImportError>;
+
+    async fn record_failure(&self, failure: &ImportFailure) -> Result<(), ImportError>;
+
+    /// Queues a job, returning its id. A job already queued with the same
+    /// `dedupe_key` is kept and its id returned instead.
+    async fn enqueue_job(
+        &self,
+        dedupe_key: Option<&str>,
+        payload: &str,
+    ) -> Result<i64, ImportError>;
+
+    /// Claims the given job if it's queued and not claimed by someone else.
+    async fn claim_job(&self, id: i64) -> Result<Option<Job>, ImportError>;
+
+    /// Claims up to `limit` queued jobs due to run, along with running jobs
+    /// whose invocation must have died since.
+    async fn claim_jobs(&self, limit: i64) -> Result<Vec<Job>, ImportError>;
+
+    /// Marks a job done, with the key of its report if one was written.
+    async fn complete_job(&self, id: i64, report_key: Option<&str>) -> Result<(), ImportError>;
+
+    /// Records a job's failure, queuing it again to run at `retry_at` if given
+    /// or marking it failed otherwise.
+    async fn fail_job(
+        &self,
+        id: i64,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ImportError>;
+
+    async fn find_job(&self, id: i64) -> Result<Option<Job>, ImportError>;
+}
+
+/// Values bound per issue by [`Store::upsert_issues`].
+const ISSUE_COLUMNS: usize = 22;
+
+/// Columns of the `jobs` table read into a [`Job`].
+const JOB_COLUMNS: &str =
+    "id, state, payload, attempts, last_error, report_key, created_at, updated_at";
+
+/// Columns of `repositories r`, and its project's `project_import_rules pr`,
+/// read into a [`StoredRepository`].
+const STORED_REPOSITORY_COLUMNS: &str = "r.id, r.url, r.forge, r.paths, r.label_prefix, \
+    r.program_tags, r.excluded_issues, pr.filters, s.cursor AS sync_cursor";
+
+/// Which issues `i` the project stats count: the publicly listed ones, open,
+/// approved, and neither hidden nor duplicates.
+const STATS_ISSUES: &str = "i.open AND i.review_state = 'approved' AND NOT i.hidden \
+    AND i.duplicate_of IS NULL";
+
+fn stored_repository_from_row(row: &PgRow) -> StoredRepository {
+    StoredRepository {
+        id: row.get("id"),
+        url: row.get("url"),
+        forge: row
+            .get::<Option<String>, _>("forge")
+            .as_deref()
+            .and_then(ForgeKind::from_db),
+        paths: row.get("paths"),
+        label_prefix: row.get("label_prefix"),
+        program_tags: row.get("program_tags"),
+        excluded_issues: row.get("excluded_issues"),
+        // Projects imported before rules were kept have none.
+        filters: row
+            .get::<Option<Json<ImportFilters>>, _>("filters")
+            .map(|filters| filters.0)
+            .unwrap_or_default(),
+        sync_cursor: row
+            .get::<Option<Json<SyncCursor>>, _>("sync_cursor")
+            .map(|cursor| cursor.0),
+    }
+}
+
+fn job_from_row(row: &PgRow) -> Result<Job, ImportError> {
+    let state: String = row.get("state");
+    Ok(Job {
+        id: row.get("id"),
+        state: JobState::from_db(&state)
+            .ok_or_else(|| sqlx::Error::Decode(format!("Unknown job state `{}`", state).into()))?,
+        payload: row.get("payload"),
+        attempts: row.get("attempts"),
+        last_error: row.get("last_error"),
+        report_key: row.get("report_key"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+/// [`Store`] backed by the Kudos Postgres database.
+pub struct PgStore {
+    pool: PgPool,
+    breaker: CircuitBreaker,
+}
+
+impl PgStore {
+    pub fn new(pool: PgPool) -> Self {
+        PgStore {
+            pool,
+            breaker: CircuitBreaker::new(Upstream::Postgres),
+        }
+    }
+
+    /// Connects to the configured database. When its credentials come from a
+    /// secret and are rejected, the secret may have been rotated since it was
+    /// cached, so it's fetched again for a second attempt.
+    pub async fn connect() -> Result<Self, ImportError> {
+        let pool = match connect_pool(&secrets::database_url().await?).await {
+            Err(e) if is_auth_error(&e) => {
+                let Some(arn) = secrets::database_url_secret_arn() else {
+                    return Err(e.into());
+                };
+                secrets::invalidate(arn).await;
+                connect_pool(&secrets::database_url().await?).await?
+            }
+            pool => pool?,
+        };
+        Ok(PgStore::new(pool))
+    }
+}
+
+/// Connects to the database at `url`. Statements slower than
+/// `SLOW_QUERY_THRESHOLD_MS` are logged as warnings by sqlx, with their
+/// duration and the rows they returned and affected.
+async fn connect_pool(url: &str) -> Result<PgPool, sqlx::Error> {
+    let threshold = Duration::from_millis(config::get().slow_query_threshold_ms);
+    let options =
+        PgConnectOptions::from_str(url)?.log_slow_statements(LevelFilter::Warn, threshold);
+    PgPool::connect_with(options).await
+}
+
+/// Whether Postgres rejected the credentials (`invalid_password`).
+fn is_auth_error(error: &sqlx::Error) -> bool {
+    matches!(error, sqlx::Error::Database(e) if e.code().as_deref() == Some("28P01"))
+}
+
+#[async_trait]
+impl Store for PgStore {
+    async fn insert_project(&self, project: &Project) -> Result<i32, ImportError> {
+        self.breaker
+            .call(async {
+                let project_row = sqlx::query(
+                    r#"
+                    WITH project AS (
+                        INSERT INTO projects (name, slug, types, purposes, stack_levels, technologies)
+                        VALUES ($1, $2, $3, $4, $5, $6)
+                        RETURNING id
+                    ), rules AS (
+                        INSERT INTO project_import_rules (project_id, filters)
+                        SELECT id, $7 FROM project
+                    )
+                    SELECT id FROM project;
+                    "#,
+                )
+                .bind(&project.name)
+                .bind(&project.slug)
+                .bind(&project.attributes.types)
+                .bind(&project.attributes.purposes)
+                .bind(&project.attributes.stack_levels)
+                .bind(&project.attributes.technologies)
+                .bind(Json(&project.filters))
+                .fetch_one(&self.pool)
+                .await?;
+
+                Ok(project_row.get("id"))
+            })
+            .await
+    }
+
+    async fn insert_repository(
+        &self,
+        slug: &str,
+        project_id: i32,
+        repo_info: &RepoInfo,
+    ) -> Result<i32, ImportError> {
+        self.breaker
+            .call(async {
+                let repo_row = sqlx::query(
+                    r#"
+                    INSERT INTO repositories (slug, project_id, url, forge, paths, label_prefix,
+                                              program_tags, excluded_issues, license)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                    ON CONFLICT (project_id, url) DO UPDATE
+                    SET slug = EXCLUDED.slug,
+                        forge = EXCLUDED.forge,
+                        paths = EXCLUDED.paths,
+                        label_prefix = EXCLUDED.label_prefix,
+                        program_tags = EXCLUDED.program_tags,
+                        excluded_issues = EXCLUDED.excluded_issues,
+                        license = EXCLUDED.license
+                    RETURNING id;
+                    "#,
+                )
+                .bind(slug)
+                .bind(project_id)
+                .bind(repo_info.url())
+                .bind(repo_info.kind().map(ForgeKind::as_str))
+                .bind(&repo_info.paths)
+                .bind(&repo_info.label_prefix)
+                .bind(&repo_info.program_tags)
+                .bind(&repo_info.excluded_issues)
+                .bind(&repo_info.license)
+                .fetch_one(&self.pool)
+                .await?;
+
+                Ok(repo_row.get("id"))
+            })
+            .await
+    }
+
+    async fn upsert_issues(&self, repo_id: i32, issues: &[KudosIssue]) -> Result<u64, ImportError> {
+        self.breaker
+            .call(async {
+                if issues.is_empty() {
+                    return Ok(0);
+                }
+
+                let placeholders = (0..issues.len())
+                    .map(|i| {
+                        let mut params: Vec<String> = (1..=ISSUE_COLUMNS)
+                            .map(|column| format!("${}", i * ISSUE_COLUMNS + column))
+                            .collect();
+                        params.push("(SELECT technologies FROM project)".to_string());
+                        params.push("(SELECT purposes FROM project)".to_string());
+                        format!("({})", params.join(", "))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                // The reopened issues are read before the upsert opens them:
+                // the statement's parts all see the rows as they were.
+                let repository_param = issues.len() * ISSUE_COLUMNS + 1;
+                let query_string = format!(
+                    r#"
+                    WITH project AS (
+                        SELECT COALESCE(p.technologies, '{{}}') AS technologies,
+                               COALESCE(p.purposes, '{{}}') AS purposes
+                        FROM repositories r
+                        JOIN projects p ON p.id = r.project_id
+                        WHERE r.id = ${}
+                    ), reopened AS (
+                        INSERT INTO issue_reopens (issue_id, closed_at)
+                        SELECT id, issue_closed_at
+                        FROM issues
+                        WHERE repository_id = ${}
+                          AND NOT open
+                          AND number::INT8 = ANY(${})
+                    )
+                    INSERT INTO issues (number, title, labels, repository_id, issue_created_at, excerpt,
+                                        is_beginner_friendly, categories, is_paid, reward_amount,
+                                        reward_currency, difficulty, difficulty_signals, issue_type,
+                                        program_tags, staleness, reward_value,
+                                        seeking_contributors, review_state, referenced_issues,
+                                        reactions, ranking_score, technologies, purposes)
+                    VALUES {}
+                    ON CONFLICT (repository_id, number) DO UPDATE
+                    SET title = EXCLUDED.title,
+                        labels = EXCLUDED.labels,
+                        excerpt = EXCLUDED.excerpt,
+                        is_beginner_friendly = EXCLUDED.is_beginner_friendly,
+                        categories = EXCLUDED.categories,
+                        is_paid = EXCLUDED.is_paid,
+                        reward_amount = EXCLUDED.reward_amount,
+                        reward_currency = EXCLUDED.reward_currency,
+                        reward_value = EXCLUDED.reward_value,
+                        seeking_contributors = EXCLUDED.seeking_contributors,
+                        difficulty = EXCLUDED.difficulty,
+                        difficulty_signals = EXCLUDED.difficulty_signals,
+                        issue_type = EXCLUDED.issue_type,
+                        program_tags = EXCLUDED.program_tags,
+                        staleness = EXCLUDED.staleness,
+                        referenced_issues = EXCLUDED.referenced_issues,
+                        reactions = EXCLUDED.reactions,
+                        ranking_score = EXCLUDED.ranking_score,
+                        technologies = EXCLUDED.technologies,
+                        purposes = EXCLUDED.purposes,
+                        review_state = CASE
+                            WHEN issues.review_state = 'pending' AND issues.reviewed_at IS NULL
+                                THEN EXCLUDED.review_state
+                            ELSE issues.review_state
+                        END,
+                        reopen_count = issues.reopen_count + CASE WHEN issues.open THEN 0 ELSE 1 END,
+                        last_reopened_at = CASE
+                            WHEN issues.open THEN issues.last_reopened_at
+                            ELSE NOW()
+                        END,
+                        open = TRUE,
+                        issue_closed_at = NULL
+                    "#,
+                    repository_param,
+                    repository_param,
+                    repository_param + 1,
+                    placeholders
+                );
+
+                let mut upsert_issues_query = sqlx::query(&query_string);
+
+                for issue in issues {
+                    upsert_issues_query = upsert_issues_query
+                        .bind(issue.number)
+                        .bind(&issue.title)
+                        .bind(&issue.labels)
+                        .bind(repo_id)
+                        .bind(issue.issue_created_at)
+                        .bind(&issue.excerpt)
+                        .bind(issue.is_beginner_friendly)
+                        .bind(&issue.categories)
+                        .bind(issue.is_paid)
+                        .bind(issue.reward.as_ref().map(|reward| reward.amount))
+                        .bind(issue.reward.as_ref().map(|reward| &reward.currency))
+                        .bind(
+                            issue
+                                .difficulty
+                                .as_ref()
+                                .map(|estimate| estimate.difficulty.as_str()),
+                        )
+                        .bind(
+                            issue
+                                .difficulty
+                                .as_ref()
+                                .map_or(&[][..], |estimate| &estimate.signals),
+                        )
+                        .bind(issue.issue_type.map(IssueType::as_str))
+                        .bind(&issue.program_tags)
+                        .bind(issue.staleness)
+                        .bind(issue.reward.as_ref().and_then(|reward| reward.value))
+                        .bind(issue.seeking_contributors)
+                        .bind(issue.review_state.as_str())
+                        .bind(&issue.referenced_issues)
+                        .bind(issue.reactions as i32)
+                        .bind(issue.ranking_score)
+                }
+                let numbers: Vec<i64> = issues.iter().map(|issue| issue.number).collect();
+                upsert_issues_query = upsert_issues_query.bind(repo_id).bind(numbers);
+
+                Ok(upsert_issues_query
+                    .execute(&self.pool)
+                    .await?
+                    .rows_affected())
+            })
+            .await
+    }
+
+    async fn mark_stale(&self, repo_id: i32, open_numbers: &[i64]) -> Result<u64, ImportError> {
+        self.breaker
+            .call(async {
+                let result = sqlx::query(
+                    r#"
+                    UPDATE issues
+                    SET open = FALSE, issue_closed_at = NOW()
+                    WHERE repository_id = $1
+                      AND open
+                      AND NOT (number::INT8 = ANY($2))
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(open_numbers)
+                .execute(&self.pool)
+                .await?;
+                Ok(result.rows_affected())
+            })
+            .await
+    }
+
+    async fn mark_sync_pending(
+        &self,
+        repo_id: i32,
+        reason: &str,
+        cursor: Option<&SyncCursor>,
+    ) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    INSERT INTO repo_sync_state (repository_id, pending, reason, cursor)
+                    VALUES ($1, TRUE, $2, $3)
+                    ON CONFLICT (repository_id) DO UPDATE
+                    SET pending = TRUE, reason = EXCLUDED.reason, requested_at = NOW(),
+                        cursor = COALESCE(EXCLUDED.cursor, repo_sync_state.cursor)
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(reason)
+                .bind(cursor.map(Json))
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn mark_synced(&self, repo_id: i32) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query("UPDATE repositories SET last_synced_at = NOW() WHERE id = $1")
+                    .bind(repo_id)
+                    .execute(&self.pool)
+                    .await?;
+                sqlx::query(
+                    r#"
+                    INSERT INTO repo_sync_state (repository_id, pending, last_attempted_at)
+                    VALUES ($1, FALSE, NOW())
+                    ON CONFLICT (repository_id) DO UPDATE
+                    SET pending = FALSE, last_attempted_at = NOW(), last_error = NULL,
+                        consecutive_failures = 0, cursor = NULL
+                    "#,
+                )
+                .bind(repo_id)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn record_sync_failure(&self, repo_id: i32, error: &str) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    INSERT INTO repo_sync_state
+                        (repository_id, pending, last_attempted_at, last_error, consecutive_failures)
+                    VALUES ($1, FALSE, NOW(), $2, 1)
+                    ON CONFLICT (repository_id) DO UPDATE
+                    SET last_attempted_at = NOW(), last_error = EXCLUDED.last_error,
+                        consecutive_failures = repo_sync_state.consecutive_failures + 1
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(error)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn record_contributors(
+        &self,
+        repo_id: i32,
+        stats: &ContributorStats,
+    ) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    UPDATE repositories
+                    SET top_contributors = $2, recent_contributors = $3,
+                        active_maintainers = $4, contributors_fetched_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(Json(&stats.top))
+                .bind(stats.recent_contributors as i32)
+                .bind(stats.active_maintainers as i32)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn record_onboarding(
+        &self,
+        repo_id: i32,
+        onboarding: &Onboarding,
+    ) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    UPDATE repositories
+                    SET has_contributing_guide = $2, has_issue_templates = $3,
+                        has_beginner_labels = $4, friendliness = $5, first_timer_friendly = $6,
+                        friendliness_checked_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(onboarding.has_contributing_guide)
+                .bind(onboarding.has_issue_templates)
+                .bind(onboarding.has_beginner_labels)
+                .bind(onboarding.score())
+                .bind(onboarding.is_friendly())
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn record_languages(
+        &self,
+        repo_id: i32,
+        languages: &Languages,
+    ) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    UPDATE repositories
+                    SET primary_language = $2, languages = $3, languages_fetched_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(&languages.primary)
+                .bind(Json(&languages.breakdown))
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn backfill_technology(&self, repo_id: i32, technology: &str) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    WITH project AS (
+                        UPDATE projects
+                        SET technologies = array_append(COALESCE(technologies, '{}'), $2),
+                            technologies_from_languages = TRUE
+                        WHERE id = (SELECT project_id FROM repositories WHERE id = $1)
+                          AND (COALESCE(cardinality(technologies), 0) = 0
+                               OR technologies_from_languages)
+                          AND NOT $2 = ANY(COALESCE(technologies, '{}'))
+                        RETURNING id, technologies
+                    )
+                    UPDATE issues
+                    SET technologies = project.technologies
+                    FROM project, repositories r
+                    WHERE r.project_id = project.id
+                      AND issues.repository_id = r.id
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(technology)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn health_score(&self, repo_id: i32) -> Result<Option<f64>, ImportError> {
+        self.breaker
+            .call(async {
+                let row = sqlx::query("SELECT health_score FROM repositories WHERE id = $1")
+                    .bind(repo_id)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                Ok(row.and_then(|row| row.get("health_score")))
+            })
+            .await
+    }
+
+    async fn record_health(
+        &self,
+        repo_id: i32,
+        health: &RepositoryHealth,
+    ) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    UPDATE repositories
+                    SET health_score = $2, recent_commits = $3, open_issues_count = $4,
+                        closed_issues_count = $5, last_release_at = $6, health_scored_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(health.score)
+                .bind(health.activity.recent_commits as i32)
+                .bind(health.activity.open_issues as i64)
+                .bind(health.activity.closed_issues as i64)
+                .bind(health.activity.last_release_at)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn record_responsiveness(
+        &self,
+        repo_id: i32,
+        responsiveness: &Responsiveness,
+    ) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    UPDATE repositories
+                    SET responsiveness = $2, median_first_response_hours = $3,
+                        responsiveness_sampled = $4, responsiveness_measured_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(responsiveness.score)
+                .bind(responsiveness.median_first_response_hours)
+                .bind(responsiveness.sampled as i32)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn stale_repositories(
+        &self,
+        synced_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<StoredRepository>, ImportError> {
+        self.breaker
+            .call(async {
+                let rows = sqlx::query(&format!(
+                    r#"
+                    SELECT {}
+                    FROM repositories r
+                    LEFT JOIN repo_sync_state s ON s.repository_id = r.id
+                    LEFT JOIN project_import_rules pr ON pr.project_id = r.project_id
+                    WHERE (s.pending AND COALESCE(s.last_attempted_at, '-infinity') < s.requested_at)
+                        OR COALESCE(GREATEST(r.last_synced_at, s.last_attempted_at), '-infinity') < $1
+                    ORDER BY COALESCE(s.pending, FALSE) DESC,
+                        GREATEST(r.last_synced_at, s.last_attempted_at) ASC NULLS FIRST
+                    LIMIT $2
+                    "#,
+                    STORED_REPOSITORY_COLUMNS
+                ))
+                .bind(synced_before)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(rows.iter().map(stored_repository_from_row).collect())
+            })
+            .await
+    }
+
+    async fn repositories(
+        &self,
+        project_id: Option<i32>,
+    ) -> Result<Vec<StoredRepository>, ImportError> {
+        self.breaker
+            .call(async {
+                let rows = sqlx::query(&format!(
+                    r#"
+                    SELECT {}
+                    FROM repositories r
+                    LEFT JOIN repo_sync_state s ON s.repository_id = r.id
+                    LEFT JOIN project_import_rules pr ON pr.project_id = r.project_id
+                    WHERE $1::INT4 IS NULL OR r.project_id = $1
+                    ORDER BY r.id
+                    "#,
+                    STORED_REPOSITORY_COLUMNS
+                ))
+                .bind(project_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(rows.iter().map(stored_repository_from_row).collect())
+            })
+            .await
+    }
+
+    async fn upsert_closed_issues(
+        &self,
+        repo_id: i32,
+        issues: &[ClosedIssue],
+    ) -> Result<u64, ImportError> {
+        self.breaker
+            .call(async {
+                if issues.is_empty() {
+                    return Ok(0);
+                }
+                let numbers: Vec<i64> = issues.iter().map(|issue| issue.number).collect();
+                let created: Vec<DateTime<Utc>> =
+                    issues.iter().map(|issue| issue.created_at).collect();
+                let closed: Vec<DateTime<Utc>> = issues.iter().map(|issue| issue.closed_at).collect();
+                let result = sqlx::query(
+                    r#"
+                    INSERT INTO closed_issues (repository_id, number, issue_created_at, issue_closed_at)
+                    SELECT $1, * FROM UNNEST($2::INT8[], $3::TIMESTAMPTZ[], $4::TIMESTAMPTZ[])
+                    ON CONFLICT (repository_id, number) DO UPDATE
+                    SET issue_created_at = EXCLUDED.issue_created_at,
+                        issue_closed_at = EXCLUDED.issue_closed_at,
+                        fetched_at = NOW()
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(numbers)
+                .bind(created)
+                .bind(closed)
+                .execute(&self.pool)
+                .await?;
+                Ok(result.rows_affected())
+            })
+            .await
+    }
+
+    async fn find_project_id(&self, slug: &str) -> Result<Option<i32>, ImportError> {
+        self.breaker
+            .call(async {
+                let row = sqlx::query("SELECT id FROM projects WHERE slug = $1")
+                    .bind(slug)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                Ok(row.map(|row| row.get("id")))
+            })
+            .await
+    }
+
+    async fn list_issues(
+        &self,
+        project_id: i32,
+        filter: &IssueFilter<'_>,
+        pagination: Pagination,
+    ) -> Result<(Vec<ListedIssue>, i64), ImportError> {
+        self.breaker
+            .call(async {
+                let rows = sqlx::query(
+                    r#"
+                    SELECT i.id, i.number::INT8 AS number, i.title, i.labels, i.issue_created_at,
+                           i.excerpt, i.is_beginner_friendly, i.categories, i.is_paid,
+                           i.reward_amount, i.reward_currency, i.reward_value, i.difficulty,
+                           i.difficulty_signals, i.issue_type, i.program_tags,
+                           i.staleness, i.seeking_contributors, i.review_state,
+                           i.reactions, i.ranking_score, i.featured,
+                           r.slug AS repository, r.url AS repository_url,
+                           r.forge AS repository_forge,
+                           COUNT(*) OVER () AS total
+                    FROM issues i
+                    JOIN repositories r ON r.id = i.repository_id
+                    WHERE r.project_id = $1
+                      AND i.open
+                      AND NOT i.hidden
+                      AND i.duplicate_of IS NULL
+                      AND ($2::TEXT IS NULL OR $2 = ANY(i.labels))
+                      AND ($3::TEXT IS NULL OR r.slug = $3)
+                      AND ($4::TIMESTAMPTZ IS NULL OR i.issue_created_at >= $4)
+                      AND ($5::BOOL IS NULL OR i.is_beginner_friendly = $5)
+                      AND ($6::TEXT IS NULL OR $6 = ANY(i.categories))
+                      AND ($7::BOOL IS NULL OR i.is_paid = $7)
+                      AND ($8::TEXT IS NULL OR i.difficulty = $8)
+                      AND ($9::TEXT IS NULL OR i.issue_type = $9)
+                      AND ($10::TEXT IS NULL OR $10 = ANY(i.program_tags))
+                      AND ($11::FLOAT8 IS NULL OR i.staleness <= $11)
+                      AND ($15::BOOL IS NULL OR i.seeking_contributors = $15)
+                      AND i.review_state = $16
+                    ORDER BY i.featured DESC,
+                        CASE WHEN $14 THEN i.reward_value END DESC NULLS LAST,
+                        CASE WHEN $17 THEN i.ranking_score END DESC NULLS LAST,
+                        i.issue_created_at DESC, i.id DESC
+                    LIMIT $12 OFFSET $13
+                    "#,
+                )
+                .bind(project_id)
+                .bind(filter.label)
+                .bind(filter.repository)
+                .bind(filter.since)
+                .bind(filter.beginner_friendly)
+                .bind(filter.category)
+                .bind(filter.paid)
+                .bind(filter.difficulty.map(Difficulty::as_str))
+                .bind(filter.issue_type.map(IssueType::as_str))
+                .bind(filter.program)
+                .bind(filter.max_staleness)
+                .bind(pagination.per_page)
+                .bind(pagination.offset())
+                .bind(filter.sort == IssueSort::Reward)
+                .bind(filter.seeking_contributors)
+                .bind(filter.review_state.as_str())
+                .bind(filter.sort == IssueSort::Rank)
+                .fetch_all(&self.pool)
+                .await?;
+
+                let total = rows.first().map_or(0, |row| row.get("total"));
+                let issues = rows
+                    .into_iter()
+                    .map(|row| {
+                        let number: i64 = row.get("number");
+                        let repository_url: String = row.get("repository_url");
+                        let forge = row
+                            .get::<Option<String>, _>("repository_forge")
+                            .as_deref()
+                            .and_then(ForgeKind::from_db);
+                        ListedIssue {
+                            id: row.get("id"),
+                            number,
+                            title: row.get("title"),
+                            html_url: issue_url(forge, &repository_url, number),
+                            labels: row.get("labels"),
+                            repository: row.get("repository"),
+                            issue_created_at: row.get("issue_created_at"),
+                            excerpt: row.get("excerpt"),
+                            is_beginner_friendly: row.get("is_beginner_friendly"),
+                            categories: row.get("categories"),
+                            is_paid: row.get("is_paid"),
+                            reward: row
+                                .get::<Option<f64>, _>("reward_amount")
+                                .zip(row.get::<Option<String>, _>("reward_currency"))
+                                .map(|(amount, currency)| Reward {
+                                    amount,
+                                    currency,
+                                    value: row.get("reward_value"),
+                                }),
+                            difficulty: row
+                                .get::<Option<String>, _>("difficulty")
+                                .as_deref()
+                                .and_then(Difficulty::from_db),
+                            difficulty_signals: row.get("difficulty_signals"),
+                            issue_type: row
+                                .get::<Option<String>, _>("issue_type")
+                                .as_deref()
+                                .and_then(IssueType::from_db),
+                            program_tags: row.get("program_tags"),
+                            staleness: row.get("staleness"),
+                            seeking_contributors: row.get("seeking_contributors"),
+                            reactions: row.get("reactions"),
+                            ranking_score: row.get("ranking_score"),
+                            review_state: ReviewState::from_db(row.get("review_state"))
+                                .unwrap_or_default(),
+                            featured: row.get("featured"),
+                        }
+                    })
+                    .collect();
+
+                Ok((issues, total))
+            })
+            .await
+    }
+
+    async fn mark_duplicates(&self, repo_id: i32) -> Result<u64, ImportError> {
+        self.breaker
+            .call(async {
+                // Each duplicate points at the earliest issue it duplicates,
+                // those duplicating none being reset.
+                let result = sqlx::query(
+                    r#"
+                    WITH project_issues AS (
+                        SELECT i.id, i.repository_id, i.issue_created_at,
+                               LOWER(REGEXP_REPLACE(BTRIM(i.title), '\s+', ' ', 'g')) AS title_key,
+                               LOWER(r.url) || CASE r.forge
+                                   WHEN 'sourcehut' THEN '/'
+                                   ELSE '/issues/'
+                               END || i.number AS url,
+                               i.referenced_issues
+                        FROM issues i
+                        JOIN repositories r ON r.id = i.repository_id
+                        WHERE i.open
+                          AND r.project_id = (SELECT project_id FROM repositories WHERE id = $1)
+                    ), duplicates AS (
+                        SELECT DISTINCT ON (d.id) d.id, c.id AS duplicate_of
+                        FROM project_issues d
+                        JOIN project_issues c
+                          ON c.repository_id <> d.repository_id
+                         AND (c.issue_created_at, c.id) < (d.issue_created_at, d.id)
+                         AND ((CHAR_LENGTH(d.title_key) >= $2 AND c.title_key = d.title_key)
+                              OR (c.url = ANY(d.referenced_issues)
+                                  AND d.url = ANY(c.referenced_issues)))
+                        ORDER BY d.id, c.issue_created_at, c.id
+                    )
+                    UPDATE issues
+                    SET duplicate_of = duplicates.duplicate_of
+                    FROM project_issues p
+                    LEFT JOIN duplicates ON duplicates.id = p.id
+                    WHERE issues.id = p.id
+                      AND issues.duplicate_of IS DISTINCT FROM duplicates.duplicate_of
+                    "#,
+                )
+                .bind(repo_id)
+                .bind(duplicates::MIN_TITLE_CHARS)
+                .execute(&self.pool)
+                .await?;
+                Ok(result.rows_affected())
+            })
+            .await
+    }
+
+    async fn curate_issue(
+        &self,
+        id: i32,
+        curation: &IssueCuration,
+    ) -> Result<Option<CuratedIssue>, ImportError> {
+        self.breaker
+            .call(async {
+                let row = sqlx::query(
+                    r#"
+                    UPDATE issues
+                    SET featured = COALESCE($2, featured),
+                        hidden = COALESCE($3, hidden),
+                        curator_note = CASE WHEN $4::TEXT IS NULL THEN curator_note
+                                            ELSE NULLIF(TRIM($4), '') END,
+                        curated_at = NOW()
+                    WHERE id = $1
+                    RETURNING id, featured, hidden, curator_note, curated_at
+                    "#,
+                )
+                .bind(id)
+                .bind(curation.featured)
+                .bind(curation.hidden)
+                .bind(&curation.curator_note)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(row.map(|row| CuratedIssue {
+                    id: row.get("id"),
+                    featured: row.get("featured"),
+                    hidden: row.get("hidden"),
+                    curator_note: row.get("curator_note"),
+                    curated_at: row.get("curated_at"),
+                }))
+            })
+            .await
+    }
+
+    async fn review_issue(
+        &self,
+        id: i32,
+        state: ReviewState,
+    ) -> Result<Option<ReviewedIssue>, ImportError> {
+        self.breaker
+            .call(async {
+                let row = sqlx::query(
+                    r#"
+                    UPDATE issues
+                    SET review_state = $2, reviewed_at = NOW()
+                    WHERE id = $1
+                    RETURNING id, review_state, reviewed_at
+                    "#,
+                )
+                .bind(id)
+                .bind(state.as_str())
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(row.map(|row| ReviewedIssue {
+                    id: row.get("id"),
+                    review_state: state,
+                    reviewed_at: row.get("reviewed_at"),
+                }))
+            })
+            .await
+    }
+
+    async fn project_stats(&self, project_id: i32) -> Result<ProjectStats, ImportError> {
+        self.breaker
+            .call(async {
+                let totals = sqlx::query(&format!(
+                    r#"
+                    SELECT COUNT(i.id) AS total_issues,
+                           (EXTRACT(EPOCH FROM AVG(NOW() - i.issue_created_at)) / 86400)::FLOAT8
+                               AS average_issue_age_days
+                    FROM issues i
+                    JOIN repositories r ON r.id = i.repository_id
+                    WHERE r.project_id = $1 AND {}
+                    "#,
+                    STATS_ISSUES
+                ))
+                .bind(project_id)
+                .fetch_one(&self.pool)
+                .await?;
+
+                let by_label = sqlx::query(&format!(
+                    r#"
+                    SELECT label, COUNT(*) AS issues
+                    FROM issues i
+                    JOIN repositories r ON r.id = i.repository_id
+                    CROSS JOIN UNNEST(i.labels) AS label
+                    WHERE r.project_id = $1 AND {}
+                    GROUP BY label
+                    ORDER BY issues DESC, label
+                    "#,
+                    STATS_ISSUES
+                ))
+                .bind(project_id)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| LabelStats {
+                    label: row.get("label"),
+                    issues: row.get("issues"),
+                })
+                .collect();
+
+                let by_repository: Vec<RepositoryStats> = sqlx::query(&format!(
+                    r#"
+                    SELECT r.slug, r.last_synced_at, COUNT(i.id) AS issues
+                    FROM repositories r
+                    LEFT JOIN issues i ON i.repository_id = r.id AND {}
+                    WHERE r.project_id = $1
+                    GROUP BY r.id, r.slug, r.last_synced_at
+                    ORDER BY issues DESC, r.slug
+                    "#,
+                    STATS_ISSUES
+                ))
+                .bind(project_id)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| RepositoryStats {
+                    slug: row.get("slug"),
+                    issues: row.get("issues"),
+                    last_synced_at: row.get("last_synced_at"),
+                })
+                .collect();
+
+                Ok(ProjectStats {
+                    total_issues: totals.get("total_issues"),
+                    average_issue_age_days: totals.get("average_issue_age_days"),
+                    last_synced_at: by_repository.iter().filter_map(|r| r.last_synced_at).max(),
+                    by_label,
+                    by_repository,
+                })
+            })
+            .await
+    }
+
+    async fn feature_flags(&self) -> Result<Vec<(String, bool)>, ImportError> {
+        self.breaker
+            .call(async {
+                let rows = sqlx::query("SELECT name, enabled FROM feature_flags")
+                    .fetch_all(&self.pool)
+                    .await?;
+                Ok(rows
+                    .into_iter()
+                    .map(|row| (row.get("name"), row.get("enabled")))
+                    .collect())
+            })
+            .await
+    }
+
+    async fn taxonomy(&self) -> Result<Vec<(String, String)>, ImportError> {
+        self.breaker
+            .call(async {
+                let rows = sqlx::query("SELECT facet, value FROM taxonomy")
+                    .fetch_all(&self.pool)
+                    .await?;
+                Ok(rows
+                    .into_iter()
+                    .map(|row| (row.get("facet"), row.get("value")))
+                    .collect())
+            })
+            .await
+    }
+
+    async fn label_categories(&self) -> Result<Vec<(String, String, String)>, ImportError> {
+        self.breaker
+            .call(async {
+                let rows = sqlx::query("SELECT pattern, category, value FROM label_categories")
+                    .fetch_all(&self.pool)
+                    .await?;
+                Ok(rows
+                    .into_iter()
+                    .map(|row| (row.get("pattern"), row.get("category"), row.get("value")))
+                    .collect())
+            })
+            .await
+    }
+
+    async fn create_checkpoint(&self, token: &str, payload: &str) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query("INSERT INTO import_checkpoints (token, payload) VALUES ($1, $2)")
+                    .bind(token)
+                    .bind(payload)
+                    .execute(&self.pool)
+                    .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn load_checkpoint(&self, token: &str) -> Result<Option<StoredCheckpoint>, ImportError> {
+        self.breaker
+            .call(async {
+                let row = sqlx::query(
+                    "SELECT payload, state, cursor FROM import_checkpoints WHERE token = $1",
+                )
+                .bind(token)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(row.map(|row| StoredCheckpoint {
+                    payload: row.get("payload"),
+                    state: row
+                        .get::<Option<Json<ImportCheckpoint>>, _>("state")
+                        .map(|state| state.0),
+                    cursor: row
+                        .get::<Option<Json<SyncCursor>>, _>("cursor")
+                        .map(|cursor| cursor.0),
+                }))
+            })
+            .await
+    }
+
+    async fn save_checkpoint(
+        &self,
+        token: &str,
+        state: &ImportCheckpoint,
+    ) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    UPDATE import_checkpoints
+                    SET state = $2, cursor = NULL, updated_at = NOW()
+                    WHERE token = $1
+                    "#,
+                )
+                .bind(token)
+                .bind(Json(state))
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn save_sync_cursor(&self, token: &str, cursor: &SyncCursor) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    "UPDATE import_checkpoints SET cursor = $2, updated_at = NOW() WHERE token = $1",
+                )
+                .bind(token)
+                .bind(Json(cursor))
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn delete_checkpoint(&self, token: &str) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query("DELETE FROM import_checkpoints WHERE token = $1")
+                    .bind(token)
+                    .execute(&self.pool)
+                    .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn record_failure(&self, failure: &ImportFailure) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    INSERT INTO import_failures (message_id, body, kind, status, error, receive_count)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    "#,
+                )
+                .bind(&failure.message_id)
+                .bind(&failure.body)
+                .bind(failure.kind.as_str())
+                .bind(failure.status.as_str())
+                .bind(&failure.error)
+                .bind(failure.receive_count)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn enqueue_job(
+        &self,
+        dedupe_key: Option<&str>,
+        payload: &str,
+    ) -> Result<i64, ImportError> {
+        self.breaker
+            .call(async {
+                // The no-op update makes RETURNING yield the existing row on conflict.
+                let row = sqlx::query(
+                    r#"
+                    INSERT INTO jobs (dedupe_key, payload)
+                    VALUES ($1, $2)
+                    ON CONFLICT (dedupe_key) DO UPDATE SET dedupe_key = EXCLUDED.dedupe_key
+                    RETURNING id
+                    "#,
+                )
+                .bind(dedupe_key)
+                .bind(payload)
+                .fetch_one(&self.pool)
+                .await?;
+                Ok(row.get("id"))
+            })
+            .await
+    }
+
+    async fn claim_job(&self, id: i64) -> Result<Option<Job>, ImportError> {
+        self.breaker
+            .call(async {
+                let query = format!(
+                    r#"
+                    UPDATE jobs
+                    SET state = 'running', attempts = attempts + 1, updated_at = NOW()
+                    WHERE id = (
+                        SELECT id FROM jobs
+                        WHERE id = $1 AND state = 'queued'
+                        FOR UPDATE SKIP LOCKED
+                    )
+                    RETURNING {}
+                    "#,
+                    JOB_COLUMNS
+                );
+                let row = sqlx::query(&query)
+                    .bind(id)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                row.as_ref().map(job_from_row).transpose()
+            })
+            .await
+    }
+
+    async fn claim_jobs(&self, limit: i64) -> Result<Vec<Job>, ImportError> {
+        self.breaker
+            .call(async {
+                // Lambda invocations last 15 minutes at most, a job running for
+                // longer than that was abandoned.
+                let query = format!(
+                    r#"
+                    UPDATE jobs
+                    SET state = 'running', attempts = attempts + 1, updated_at = NOW()
+                    WHERE id IN (
+                        SELECT id FROM jobs
+                        WHERE (state = 'queued' AND run_after <= NOW())
+                           OR (state = 'running' AND updated_at < NOW() - INTERVAL '15 minutes')
+                        ORDER BY run_after, id
+                        LIMIT $1
+                        FOR UPDATE SKIP LOCKED
+                    )
+                    RETURNING {}
+                    "#,
+                    JOB_COLUMNS
+                );
+                let rows = sqlx::query(&query)
+                    .bind(limit)
+                    .fetch_all(&self.pool)
+                    .await?;
+                rows.iter().map(job_from_row).collect()
+            })
+            .await
+    }
+
+    async fn complete_job(&self, id: i64, report_key: Option<&str>) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    "UPDATE jobs SET state = 'done', report_key = $2, updated_at = NOW() WHERE id = $1",
+                )
+                .bind(id)
+                .bind(report_key)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn fail_job(
+        &self,
+        id: i64,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ImportError> {
+        self.breaker
+            .call(async {
+                sqlx::query(
+                    r#"
+                    UPDATE jobs
+                    SET state = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN 'failed' ELSE 'queued' END,
+                        last_error = $2,
+                        run_after = COALESCE($3, run_after),
+                        updated_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(id)
+                .bind(error)
+                .bind(retry_at)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn find_job(&self, id: i64) -> Result<Option<Job>, ImportError> {
+        self.breaker
+            .call(async {
+                let query = format!("SELECT {} FROM jobs WHERE id = $1", JOB_COLUMNS);
+                let row = sqlx::query(&query)
+                    .bind(id)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                row.as_ref().map(job_from_row).transpose()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::test_db;
+
+    /// Inserts a project with a repository for each of `urls`, returning
+    /// their ids.
+    async fn insert_repositories(pool: &PgPool, urls: &[&str]) -> Vec<i32> {
+        let project_id: i32 = sqlx::query_scalar(
+            "INSERT INTO projects (name, slug) VALUES ('Kudos', 'kudos') RETURNING id",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        let mut ids = Vec::new();
+        for url in urls {
+            let id = sqlx::query_scalar(
+                "INSERT INTO repositories (slug, project_id, url) VALUES ($1, $2, $1) RETURNING id",
+            )
+            .bind(url)
+            .bind(project_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            ids.push(id);
+        }
+        ids
+    }
+
+    #[tokio::test]
+    async fn failed_pending_syncs_wait_like_the_others() {
+        let Some((store, pool)) = test_db::store("failed_pending_syncs").await else {
+            return;
+        };
+        let ids = insert_repositories(
+            &pool,
+            &[
+                "https://github.com/kudos-ink/pending",
+                "https://github.com/kudos-ink/stale",
+            ],
+        )
+        .await;
+        let (pending, stale) = (ids[0], ids[1]);
+        store.mark_synced(stale).await.unwrap();
+        sqlx::query("UPDATE repositories SET last_synced_at = NOW() - INTERVAL '2 days'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE repo_sync_state SET last_attempted_at = NOW() - INTERVAL '2 days'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        store
+            .mark_sync_pending(pending, "GitHub unavailable", None)
+            .await
+            .unwrap();
+        let synced_before = Utc::now() - Duration::hours(24);
+
+        let ids = |repositories: Vec<StoredRepository>| -> Vec<i32> {
+            repositories.iter().map(|repo| repo.id).collect()
+        };
+        let batch = store.stale_repositories(synced_before, 1).await.unwrap();
+        assert_eq!(ids(batch), [pending]);
+
+        store
+            .record_sync_failure(pending, "GitHub unavailable")
+            .await
+            .unwrap();
+        let batch = store.stale_repositories(synced_before, 1).await.unwrap();
+        assert_eq!(ids(batch), [stale]);
+
+        // Marked pending again, e.g. by another import, it's retried first.
+        store
+            .mark_sync_pending(pending, "GitHub unavailable", None)
+            .await
+            .unwrap();
+        let batch = store.stale_repositories(synced_before, 1).await.unwrap();
+        assert_eq!(ids(batch), [pending]);
+    }
+
+    #[tokio::test]
+    async fn project_stats_count_listed_issues_only() {
+        let Some((store, pool)) = test_db::store("project_stats").await else {
+            return;
+        };
+        let repo_id = insert_repositories(&pool, &["https://github.com/kudos-ink/stats"]).await[0];
+        sqlx::query(
+            r#"
+            INSERT INTO issues (number, title, labels, repository_id, issue_created_at,
+                                review_state, hidden)
+            VALUES (1, 'Listed', '{bug}', $1, NOW(), 'approved', FALSE),
+                   (2, 'Pending', '{bug}', $1, NOW(), 'pending', FALSE),
+                   (3, 'Hidden', '{bug}', $1, NOW(), 'approved', TRUE),
+                   (4, 'Duplicate', '{bug}', $1, NOW(), 'approved', FALSE)
+            "#,
+        )
+        .bind(repo_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            UPDATE issues SET duplicate_of = (SELECT id FROM issues WHERE number = 1)
+            WHERE number = 4
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let project_id = store.find_project_id("kudos").await.unwrap().unwrap();
+        let stats = store.project_stats(project_id).await.unwrap();
+        assert_eq!(stats.total_issues, 1);
+        assert_eq!(stats.by_label.len(), 1);
+        assert_eq!(stats.by_label[0].issues, 1);
+        assert_eq!(stats.by_repository[0].issues, 1);
+    }
+
+    #[tokio::test]
+    async fn sourcehut_tickets_link_to_duplicates() {
+        let Some((store, pool)) = test_db::store("sourcehut_duplicates").await else {
+            return;
+        };
+        let ids = insert_repositories(
+            &pool,
+            &[
+                "https://github.com/kudos-ink/app",
+                "https://todo.sr.ht/~kudos/app",
+            ],
+        )
+        .await;
+        sqlx::query("UPDATE repositories SET forge = 'sourcehut' WHERE id = $1")
+            .bind(ids[1])
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO issues (number, title, labels, repository_id, issue_created_at,
+                                referenced_issues)
+            VALUES (1, 'Crash', '{}', $1, NOW() - INTERVAL '1 day',
+                    '{https://todo.sr.ht/~kudos/app/2}'),
+                   (2, 'Crash on start', '{}', $2, NOW(),
+                    '{https://github.com/kudos-ink/app/issues/1}')
+            "#,
+        )
+        .bind(ids[0])
+        .bind(ids[1])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let project_id = store.find_project_id("kudos").await.unwrap().unwrap();
+        let filter = IssueFilter {
+            review_state: ReviewState::Approved,
+            ..IssueFilter::default()
+        };
+        let pagination = Pagination {
+            page: 1,
+            per_page: 10,
+        };
+        let html_urls = || async {
+            let (issues, _) = store
+                .list_issues(project_id, &filter, pagination)
+                .await
+                .unwrap();
+            issues
+                .into_iter()
+                .map(|issue| issue.html_url)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            html_urls().await,
+            [
+                "https://todo.sr.ht/~kudos/app/2",
+                "https://github.com/kudos-ink/app/issues/1",
+            ]
+        );
+
+        assert_eq!(store.mark_duplicates(ids[1]).await.unwrap(), 1);
+        assert_eq!(
+            html_urls().await,
+            ["https://github.com/kudos-ink/app/issues/1"]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! The catalog's taxonomy: the values allowed in each attribute facet of a
+//! project, so the site's facets stay consistent. A facet's values are the
+//! ones listed in its `TAXONOMY_*` setting and the rows of the `taxonomy`
+//! table; facets with none allow any value.
+//!
+//! Database rows are cached for `TAXONOMY_CACHE_SECONDS`, so adding a value
+//! takes effect within that delay.
+//!
+//! Technologies are first normalized through `TECHNOLOGY_ALIASES`, so that
+//! e.g. `rust-lang` and `Rust` don't become two facets on the site.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use lambda_http::tracing::warn;
+use tokio::sync::Mutex;
+
+use crate::config;
+use crate::model::ProjectAttributes;
+use crate::store::Store;
+use crate::validation::{ValidationError, ValidationReason};
+
+/// Database values, lowercase, by facet.
+type Rows = HashMap<String, Vec<String>>;
+
+/// Database values, with when they were read.
+static ROWS: Mutex<Option<(Rows, Instant)>> = Mutex::const_new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Facet {
+    Purposes,
+    StackLevels,
+    Technologies,
+    Types,
+}
+
+impl Facet {
+    pub const ALL: [Facet; 4] = [
+        Facet::Purposes,
+        Facet::StackLevels,
+        Facet::Technologies,
+        Facet::Types,
+    ];
+
+    /// Name of the facet in the `taxonomy` table, as the `projects` column.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Facet::Purposes => "purposes",
+            Facet::StackLevels => "stack_levels",
+            Facet::Technologies => "technologies",
+            Facet::Types => "types",
+        }
+    }
+
+    /// Key of the facet in the payload's `attributes`.
+    fn field(self) -> &'static str {
+        match self {
+            Facet::StackLevels => "stackLevels",
+            _ => self.as_str(),
+        }
+    }
+
+    fn configured(self) -> &'static [String] {
+        let config = config::get();
+        match self {
+            Facet::Purposes => &config.taxonomy_purposes,
+            Facet::StackLevels => &config.taxonomy_stack_levels,
+            Facet::Technologies => &config.taxonomy_technologies,
+            Facet::Types => &config.taxonomy_types,
+        }
+    }
+
+    fn values(self, attributes: &ProjectAttributes) -> &[String] {
+        match self {
+            Facet::Purposes => &attributes.purposes,
+            Facet::StackLevels => &attributes.stack_levels,
+            Facet::Technologies => &attributes.technologies,
+            Facet::Types => &attributes.types,
+        }
+    }
+}
+
+async fn rows(store: &dyn Store) -> Rows {
+    let ttl = Duration::from_secs(config::get().taxonomy_cache_seconds);
+    let mut cache = ROWS.lock().await;
+    if let Some((rows, read_at)) = cache.as_ref() {
+        if read_at.elapsed() < ttl {
+            return rows.clone();
+        }
+    }
+
+    // Like feature flags, not worth failing an import over: keep the last
+    // known values, or the configured ones alone, until the TTL is over.
+    let rows = match store.taxonomy().await {
+        Ok(rows) => {
+            let mut by_facet = Rows::new();
+            for (facet, value) in rows {
+                by_facet
+                    .entry(facet)
+                    .or_default()
+                    .push(value.trim().to_lowercase());
+            }
+            by_facet
+        }
+        Err(e) => {
+            warn!("Couldn't read the taxonomy, using the previous one: {}", e);
+            cache
+                .as_ref()
+                .map(|(rows, _)| rows.clone())
+                .unwrap_or_default()
+        }
+    };
+    *cache = Some((rows.clone(), Instant::now()));
+    rows
+}
+
+/// Parses a `TECHNOLOGY_ALIASES` entry into the alias, lowercase, and the
+/// technology it stands for.
+pub fn parse_alias(entry: &str) -> Result<(String, String), String> {
+    match entry.split_once('=') {
+        Some((alias, technology)) if !alias.trim().is_empty() && !technology.trim().is_empty() => {
+            Ok((alias.trim().to_lowercase(), technology.trim().to_string()))
+        }
+        _ => Err(format!(
+            "TECHNOLOGY_ALIASES has an invalid entry `{}`, expected `alias=Technology`",
+            entry
+        )),
+    }
+}
+
+/// Replaces the aliases among `technologies` with the technology they stand
+/// for, dropping the duplicates this makes.
+pub fn normalize_technologies(technologies: &mut Vec<String>) {
+    let aliases: HashMap<String, String> = config::get()
+        .technology_aliases
+        .iter()
+        .filter_map(|entry| parse_alias(entry).ok())
+        .collect();
+    let mut normalized: Vec<String> = Vec::with_capacity(technologies.len());
+    for technology in technologies.drain(..) {
+        let technology = aliases
+            .get(&technology.trim().to_lowercase())
+            .cloned()
+            .unwrap_or(technology);
+        if !normalized
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(&technology))
+        {
+            normalized.push(technology);
+        }
+    }
+    *technologies = normalized;
+}
+
+/// The attribute values of a project outside the taxonomy, as validation
+/// errors of the payload. Values are compared case-insensitively.
+pub async fn unknown_values(
+    store: &dyn Store,
+    attributes: &ProjectAttributes,
+) -> Vec<ValidationError> {
+    let rows = rows(store).await;
+    let mut errors = Vec::new();
+    for facet in Facet::ALL {
+        let stored = rows.get(facet.as_str()).map_or(&[][..], Vec::as_slice);
+        let configured = facet.configured();
+        if stored.is_empty() && configured.is_empty() {
+            continue;
+        }
+        for (i, value) in facet.values(attributes).iter().enumerate() {
+            let value_lowercase = value.trim().to_lowercase();
+            if stored.contains(&value_lowercase) || configured.contains(&value_lowercase) {
+                continue;
+            }
+            errors.push(ValidationError {
+                field: format!("attributes.{}[{}]", facet.field(), i),
+                pointer: format!("/attributes/{}/{}", facet.field(), i),
+                reason: ValidationReason::UnknownValue,
+                message: format!("`{}` isn't an allowed value of `{}`", value, facet.field()),
+            });
+        }
+    }
+    errors
+}
@@ -0,0 +1,201 @@
+//! Set-up of the tracing subscriber. Logs follow Lambda's logging controls
+//! like `lambda_http::tracing::init_default_subscriber` does. When built with
+//! the `otlp` feature and `OTEL_EXPORTER_OTLP_ENDPOINT` set, spans and
+//! metrics are also exported over OTLP (HTTP/protobuf) to the collector of
+//! the team's observability backend.
+//!
+//! The SDK exports in the background, which a frozen Lambda doesn't run, so
+//! every invocation ends with a [`flush`].
+//!
+//! Log lines are scrubbed of secrets, see [`crate::redact`]. Debug events
+//! about single issues, of the [`ISSUE_EVENTS`] target, are
+//! sampled per `ISSUE_LOG_SAMPLE_RATE`, so an import of thousands of issues
+//! can be debugged without logging each of them.
+
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use lambda_http::tracing::subscriber::filter::{self, EnvFilter, FilterExt, LevelFilter};
+use lambda_http::tracing::subscriber::layer::{Filter, SubscriberExt};
+use lambda_http::tracing::subscriber::util::SubscriberInitExt;
+use lambda_http::tracing::subscriber::{fmt, Layer, Registry};
+use lambda_http::tracing::Level;
+#[cfg(feature = "otlp")]
+use std::sync::OnceLock;
+
+use crate::config;
+#[cfg(feature = "otlp")]
+use crate::error::ImportError;
+use crate::redact::Redacting;
+
+#[cfg(feature = "otlp")]
+static PROVIDERS: OnceLock<(
+    opentelemetry_sdk::trace::TracerProvider,
+    opentelemetry_sdk::metrics::SdkMeterProvider,
+)> = OnceLock::new();
+
+/// Parses `OTEL_EXPORTER_OTLP_HEADERS`: comma-separated `name=value` pairs.
+pub fn parse_headers(value: &str) -> Result<HashMap<String, String>, String> {
+    value
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(format!(
+                "OTEL_EXPORTER_OTLP_HEADERS has an invalid header `{}`",
+                pair.trim()
+            )),
+        })
+        .collect()
+}
+
+/// `AWS_LAMBDA_LOG_LEVEL`, else `RUST_LOG`, directives, `INFO` by default.
+fn env_filter() -> EnvFilter {
+    let level = env::var("AWS_LAMBDA_LOG_LEVEL").or_else(|_| env::var("RUST_LOG"));
+    let level = Level::from_str(level.as_deref().unwrap_or("INFO")).unwrap_or(Level::INFO);
+    EnvFilter::builder()
+        .with_default_directive(LevelFilter::from_level(level).into())
+        .from_env_lossy()
+}
+
+/// Target of the debug events about single issues.
+pub const ISSUE_EVENTS: &str = "gh_import_issues::issues";
+
+/// [`env_filter`], keeping `ISSUE_LOG_SAMPLE_RATE` of the [`ISSUE_EVENTS`]
+/// it lets through, evenly spread, e.g. one in ten at `0.1`.
+fn log_filter<S>() -> impl Filter<S> {
+    let rate = config::get().issue_log_sample_rate;
+    let seen = AtomicU64::new(0);
+    // Dynamic, so that the decision isn't cached for the whole callsite.
+    let sampling = filter::dynamic_filter_fn(move |metadata, _| {
+        if !metadata.is_event() || metadata.target() != ISSUE_EVENTS {
+            return true;
+        }
+        let n = seen.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * rate).floor() > (n * rate).floor()
+    });
+    env_filter().and(sampling)
+}
+
+/// Installs the subscriber. Must be called after the configuration is
+/// loaded, from within the Tokio runtime.
+pub fn init() {
+    let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = Vec::new();
+
+    let json =
+        env::var("AWS_LAMBDA_LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"));
+    let logs = fmt::layer()
+        .with_target(false)
+        .without_time()
+        .with_writer(Redacting(io::stdout));
+    layers.push(if json {
+        logs.json().with_filter(log_filter()).boxed()
+    } else {
+        logs.with_filter(log_filter()).boxed()
+    });
+
+    #[cfg(feature = "otlp")]
+    let otlp_error = match otlp_layer() {
+        Ok(Some(layer)) => {
+            layers.push(layer.with_filter(log_filter()).boxed());
+            None
+        }
+        Ok(None) => None,
+        Err(e) => Some(e),
+    };
+
+    lambda_http::tracing::subscriber::registry()
+        .with(layers)
+        .init();
+
+    // Telemetry is never worth failing the function over.
+    #[cfg(feature = "otlp")]
+    if let Some(e) = otlp_error {
+        lambda_http::tracing::error!("Couldn't set up the OTLP export: {}", e);
+    }
+}
+
+#[cfg(feature = "otlp")]
+fn otlp_layer() -> Result<Option<Box<dyn Layer<Registry> + Send + Sync>>, ImportError> {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig, WithHttpConfig};
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::{runtime, Resource};
+
+    let config = config::get();
+    let Some(endpoint) = &config.otel_exporter_otlp_endpoint else {
+        return Ok(None);
+    };
+    let endpoint = endpoint.trim_end_matches('/');
+    let headers = match &config.otel_exporter_otlp_headers {
+        Some(headers) => parse_headers(headers).map_err(ImportError::Config)?,
+        None => HashMap::new(),
+    };
+    let resource = Resource::new([KeyValue::new(
+        "service.name",
+        config.otel_service_name.clone(),
+    )]);
+    let error = |e: &dyn std::error::Error| ImportError::Config(format!("OTLP exporter: {}", e));
+
+    let spans = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint))
+        .with_headers(headers.clone())
+        .build()
+        .map_err(|e| error(&e))?;
+    let tracer_provider = TracerProvider::builder()
+        .with_batch_exporter(spans, runtime::Tokio)
+        .with_resource(resource.clone())
+        .build();
+
+    let metrics = MetricExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/metrics", endpoint))
+        .with_headers(headers)
+        .build()
+        .map_err(|e| error(&e))?;
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(metrics, runtime::Tokio).build())
+        .with_resource(resource)
+        .build();
+    opentelemetry::global::set_meter_provider(meter_provider.clone());
+
+    let tracer = tracer_provider.tracer(env!("CARGO_PKG_NAME"));
+    let _ = PROVIDERS.set((tracer_provider, meter_provider));
+    Ok(Some(
+        tracing_opentelemetry::layer().with_tracer(tracer).boxed(),
+    ))
+}
+
+/// Exports the spans and metrics recorded so far, if exporting.
+pub async fn flush() {
+    #[cfg(feature = "otlp")]
+    if let Some((tracer_provider, meter_provider)) = PROVIDERS.get() {
+        // Flushing blocks until the background exporters are done.
+        let flushed = tokio::task::spawn_blocking(|| {
+            let traces = tracer_provider
+                .force_flush()
+                .into_iter()
+                .find_map(Result::err);
+            (traces, meter_provider.force_flush().err())
+        })
+        .await;
+        match flushed {
+            Ok((None, None)) => {}
+            Ok((traces, metrics)) => {
+                let e = traces
+                    .map(|e| e.to_string())
+                    .or(metrics.map(|e| e.to_string()));
+                lambda_http::tracing::warn!("Couldn't export telemetry: {}", e.unwrap_or_default());
+            }
+            Err(e) => lambda_http::tracing::warn!("Couldn't export telemetry: {}", e),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Postgres databases for tests of the [`Store`](crate::store::Store), on the
+//! server at `TEST_DATABASE_URL`. Each test gets a schema of its own, holding
+//! the tables of the Kudos API ([`BASE_SCHEMA`]) with `migrations/` applied on
+//! top. Tests needing one pass when the variable isn't set.
+
+use std::{env, fs, path::Path};
+
+use sqlx::{postgres::PgPoolOptions, Executor, PgPool};
+
+use crate::{
+    config::{self, Config},
+    store::PgStore,
+};
+
+/// The tables the importer shares with the Kudos API, which creates them.
+const BASE_SCHEMA: &str = r#"
+CREATE TABLE projects (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    types TEXT[],
+    purposes TEXT[],
+    stack_levels TEXT[],
+    technologies TEXT[]
+);
+CREATE TABLE repositories (
+    id SERIAL PRIMARY KEY,
+    slug TEXT NOT NULL,
+    project_id INT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    url TEXT NOT NULL
+);
+CREATE TABLE issues (
+    id SERIAL PRIMARY KEY,
+    number INT NOT NULL,
+    title TEXT NOT NULL,
+    labels TEXT[],
+    repository_id INT NOT NULL REFERENCES repositories (id) ON DELETE CASCADE,
+    issue_created_at TIMESTAMPTZ NOT NULL
+);
+"#;
+
+/// A store on a fresh `schema`, with a pool to check its tables, `None` when
+/// `TEST_DATABASE_URL` isn't set. The schema is named after the test and
+/// recreated on every run.
+pub async fn store(schema: &str) -> Option<(PgStore, PgPool)> {
+    let url = env::var("TEST_DATABASE_URL").ok()?;
+    config::init(
+        Config::from_vars([
+            ("DATABASE_URL".to_string(), url.clone()),
+            ("GITHUB_TOKEN".to_string(), "test".to_string()),
+        ])
+        .expect("test configuration"),
+    );
+
+    let schema = format!("test_{}", schema);
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .expect("test database");
+    pool.execute(
+        format!(
+            "DROP SCHEMA IF EXISTS {0} CASCADE; CREATE SCHEMA {0};",
+            schema
+        )
+        .as_str(),
+    )
+    .await
+    .expect("test schema");
+    pool.close().await;
+
+    let search_path = format!("SET search_path TO {}", schema);
+    let pool = PgPoolOptions::new()
+        .after_connect(move |conn, _| {
+            let search_path = search_path.clone();
+            Box::pin(async move {
+                conn.execute(search_path.as_str()).await?;
+                Ok(())
+            })
+        })
+        .connect(&url)
+        .await
+        .expect("test database");
+    sqlx::raw_sql(BASE_SCHEMA)
+        .execute(&pool)
+        .await
+        .expect("base schema");
+
+    let migrations = Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+    let mut migrations: Vec<_> = fs::read_dir(migrations)
+        .expect("migrations")
+        .map(|entry| entry.expect("migration").path())
+        .collect();
+    migrations.sort();
+    for migration in migrations {
+        let sql = fs::read_to_string(&migration).expect("migration");
+        sqlx::raw_sql(&sql)
+            .execute(&pool)
+            .await
+            .unwrap_or_else(|e| panic!("{}: {}", migration.display(), e));
+    }
+    Some((PgStore::new(pool.clone()), pool))
+}
@@ -0,0 +1,616 @@
+//! Validation of incoming project payloads.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::config;
+use crate::filters;
+use crate::forge;
+use crate::model::{ForgeKind, ImportWarning, Project, RepoInfo, StrictProject, WarningReason};
+use crate::slug;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationReason {
+    Missing,
+    WrongType,
+    Empty,
+    UnknownField,
+    /// The repository's code host can't be imported from.
+    Unsupported,
+    /// The value doesn't have the expected format, e.g. a slug with spaces.
+    Invalid,
+    /// The attribute value is outside the catalog's taxonomy.
+    UnknownValue,
+    /// The array has more entries than allowed.
+    TooMany,
+    /// The payload's `schemaVersion` isn't one of [`SCHEMA_VERSIONS`].
+    UnsupportedVersion,
+}
+
+/// Versions of the payload schema that can be imported. Payloads without a
+/// `schemaVersion` are taken as version 1, the schema before it was added.
+pub const SCHEMA_VERSIONS: &[u64] = &[1];
+
+/// A single problem found in the project payload.
+#[derive(Debug, Serialize)]
+pub struct ValidationError {
+    /// Dotted path of the offending field, e.g. `links.repository[0].url`.
+    pub field: String,
+    /// RFC 6901 pointer to the same location, e.g. `/links/repository/0/url`.
+    pub pointer: String,
+    pub reason: ValidationReason,
+    pub message: String,
+}
+
+/// Why a payload couldn't be turned into a [`Project`].
+#[derive(Debug)]
+pub enum PayloadError {
+    /// The body isn't JSON at all.
+    Syntax(serde_json::Error),
+    /// The body is JSON but doesn't match the project schema.
+    Invalid(Vec<ValidationError>),
+}
+
+/// A location in the payload, tracked both as a dotted path and a JSON pointer.
+#[derive(Clone, Default)]
+struct FieldPath {
+    field: String,
+    pointer: String,
+}
+
+impl FieldPath {
+    fn key(&self, key: &str) -> Self {
+        FieldPath {
+            field: if self.field.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", self.field, key)
+            },
+            pointer: format!(
+                "{}/{}",
+                self.pointer,
+                key.replace('~', "~0").replace('/', "~1")
+            ),
+        }
+    }
+
+    fn index(&self, index: usize) -> Self {
+        FieldPath {
+            field: format!("{}[{}]", self.field, index),
+            pointer: format!("{}/{}", self.pointer, index),
+        }
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Walks the payload against the project schema, collecting every problem
+/// instead of stopping at the first one like serde would.
+struct PayloadValidator {
+    strict: bool,
+    errors: Vec<ValidationError>,
+}
+
+impl PayloadValidator {
+    fn push(&mut self, path: &FieldPath, reason: ValidationReason, message: String) {
+        self.errors.push(ValidationError {
+            field: path.field.clone(),
+            pointer: path.pointer.clone(),
+            reason,
+            message,
+        });
+    }
+
+    fn wrong_type(&mut self, path: &FieldPath, expected: &str, value: &Value) {
+        let message = format!("expected {}, found {}", expected, json_type(value));
+        self.push(path, ValidationReason::WrongType, message);
+    }
+
+    /// Checks `value` is an object and, in strict mode, that it only has `known` keys.
+    fn object<'a>(
+        &mut self,
+        value: &'a Value,
+        path: &FieldPath,
+        known: &[&str],
+    ) -> Option<&'a Map<String, Value>> {
+        let Some(map) = value.as_object() else {
+            self.wrong_type(path, "an object", value);
+            return None;
+        };
+        if self.strict {
+            for key in map.keys().filter(|key| !known.contains(&key.as_str())) {
+                let message = format!("unknown field `{}`", key);
+                self.push(&path.key(key), ValidationReason::UnknownField, message);
+            }
+        }
+        Some(map)
+    }
+
+    fn required<'a>(
+        &mut self,
+        map: &'a Map<String, Value>,
+        path: &FieldPath,
+        key: &str,
+    ) -> Option<(&'a Value, FieldPath)> {
+        let path = path.key(key);
+        match map.get(key) {
+            Some(value) => Some((value, path)),
+            None => {
+                let message = format!("missing field `{}`", key);
+                self.push(&path, ValidationReason::Missing, message);
+                None
+            }
+        }
+    }
+
+    /// Optional fields are only checked in strict mode; lenient parsing ignores them.
+    fn optional<'a>(
+        &mut self,
+        map: &'a Map<String, Value>,
+        path: &FieldPath,
+        key: &str,
+    ) -> Option<(&'a Value, FieldPath)> {
+        let value = map.get(key).filter(|_| self.strict)?;
+        Some((value, path.key(key)))
+    }
+
+    fn string(&mut self, value: &Value, path: &FieldPath) {
+        if !value.is_string() {
+            self.wrong_type(path, "a string", value);
+        }
+    }
+
+    /// Checks `value` is a string with something else than whitespace.
+    fn non_blank(&mut self, value: &Value, path: &FieldPath) {
+        match value.as_str() {
+            Some(string) if string.trim().is_empty() => {
+                let message = "expected a non-blank string".to_string();
+                self.push(path, ValidationReason::Empty, message);
+            }
+            Some(_) => {}
+            None => self.wrong_type(path, "a string", value),
+        }
+    }
+
+    /// Checks `value` is an array of non-blank strings.
+    fn string_array(&mut self, value: &Value, path: &FieldPath) {
+        let Some(items) = value.as_array() else {
+            self.wrong_type(path, "an array of strings", value);
+            return;
+        };
+        for (i, item) in items.iter().enumerate() {
+            self.non_blank(item, &path.index(i));
+        }
+    }
+
+    /// Checks an array of links, returning the well-formed ones: objects
+    /// whose `label` and `url` are non-blank strings.
+    fn links<'a>(
+        &mut self,
+        value: &'a Value,
+        path: &FieldPath,
+        non_empty: bool,
+        known: &[&str],
+    ) -> Vec<(&'a Map<String, Value>, FieldPath)> {
+        let Some(items) = value.as_array() else {
+            self.wrong_type(path, "an array of links", value);
+            return Vec::new();
+        };
+        if non_empty && items.is_empty() {
+            let message = "expected at least one entry".to_string();
+            self.push(path, ValidationReason::Empty, message);
+        }
+        let mut links = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let item_path = path.index(i);
+            let Some(link) = self.object(item, &item_path, known) else {
+                continue;
+            };
+            let errors = self.errors.len();
+            for key in ["label", "url"] {
+                if let Some((value, path)) = self.required(link, &item_path, key) {
+                    self.non_blank(value, &path);
+                }
+            }
+            if self.errors.len() == errors {
+                links.push((link, item_path));
+            }
+        }
+        links
+    }
+
+    /// Checks an array of repositories has at most `max` entries.
+    fn repository_count(&mut self, value: &Value, path: &FieldPath, max: usize) {
+        let len = value.as_array().map_or(0, Vec::len);
+        if len > max {
+            let message = format!(
+                "expected at most {} repositories, found {}: list only the ones curated for \
+                 contributors, or split them between several projects",
+                max, len
+            );
+            self.push(path, ValidationReason::TooMany, message);
+        }
+    }
+
+    /// Checks a repository's excluded issues: positive numbers, and urls of
+    /// the repository's issues.
+    fn excluded_issues(&mut self, link: &Map<String, Value>, path: &FieldPath) {
+        if let Some(value) = link.get("excludedIssueNumbers") {
+            let path = path.key("excludedIssueNumbers");
+            match value.as_array() {
+                Some(numbers) => {
+                    for (i, number) in numbers.iter().enumerate() {
+                        if number.as_i64().is_none_or(|number| number <= 0) {
+                            self.wrong_type(&path.index(i), "an issue number", number);
+                        }
+                    }
+                }
+                None => self.wrong_type(&path, "an array of issue numbers", value),
+            }
+        }
+
+        let Some(value) = link.get("excludedIssueUrls") else {
+            return;
+        };
+        let path = path.key("excludedIssueUrls");
+        let Some(urls) = value.as_array() else {
+            self.wrong_type(&path, "an array of strings", value);
+            return;
+        };
+        let url = link.get("url").and_then(Value::as_str).unwrap_or_default();
+        // Urls without a repository path are reported by the import itself.
+        let forge = link
+            .get("forge")
+            .and_then(Value::as_str)
+            .and_then(ForgeKind::from_db);
+        let repo_info = RepoInfo::from_forge_url(url, forge).ok();
+        for (i, issue_url) in urls.iter().enumerate() {
+            let path = path.index(i);
+            let Some(issue_url) = issue_url.as_str() else {
+                self.wrong_type(&path, "a string", issue_url);
+                continue;
+            };
+            if let Some(repo_info) = &repo_info {
+                if repo_info.issue_number(issue_url).is_none() {
+                    let message = format!("`{}` isn't the url of an issue of {}", issue_url, url);
+                    self.push(&path, ValidationReason::Invalid, message);
+                }
+            }
+        }
+    }
+
+    /// Checks a repository link can be imported: its `forge`, which is
+    /// checked even in lenient mode since it picks the API to call, or else
+    /// the forge detected from its url's host.
+    fn repository(&mut self, link: &Map<String, Value>, path: &FieldPath) {
+        // Like `forge`, the scope changes what's imported.
+        if let Some(value) = link.get("labelPrefix") {
+            self.non_blank(value, &path.key("labelPrefix"));
+        }
+        if let Some(value) = link.get("paths") {
+            self.string_array(value, &path.key("paths"));
+        }
+        self.excluded_issues(link, path);
+
+        let forge = match link.get("forge") {
+            None => None,
+            Some(value) => {
+                let path = path.key("forge");
+                let forge = value.as_str().and_then(ForgeKind::from_db);
+                if forge.is_none() {
+                    let names: Vec<&str> =
+                        ForgeKind::ALL.iter().map(|kind| kind.as_str()).collect();
+                    let expected = format!("one of {}", names.join(", "));
+                    self.wrong_type(&path, &expected, value);
+                    return;
+                }
+                forge
+            }
+        };
+
+        let url = link.get("url").and_then(Value::as_str).unwrap_or_default();
+        // Urls without a repository path are reported by the import itself.
+        let Ok(mut repo_info) = RepoInfo::from_url(url) else {
+            return;
+        };
+        repo_info.forge = forge;
+        // Hosts no forge serves get the `forge` hint below instead.
+        if !forge::is_allowed_host(&repo_info.host) && repo_info.kind().is_some() {
+            let message = format!("`{}` isn't an allowed repository host", repo_info.host);
+            self.push(&path.key("url"), ValidationReason::Unsupported, message);
+            return;
+        }
+        let path = path.key(if forge.is_some() { "forge" } else { "url" });
+        match repo_info.kind() {
+            None => {
+                let message = format!(
+                    "`{}` isn't a supported code host, set `forge` to import it \
+                     from a self-hosted instance",
+                    repo_info.host
+                );
+                self.push(&path, ValidationReason::Unsupported, message);
+            }
+            Some(kind) => {
+                if let Some(reason) = forge::unavailable_reason(kind) {
+                    self.push(&path, ValidationReason::Unsupported, reason.to_string());
+                }
+            }
+        }
+    }
+
+    /// Checks the issue filters, even in lenient mode since they change what's
+    /// imported: title patterns must be valid regular expressions within
+    /// the limits of [`filters`], ages numbers of days in order and
+    /// `skipAssigned` a boolean.
+    fn filters(&mut self, value: &Value, path: &FieldPath) {
+        let known = [
+            "includeTitles",
+            "excludeTitles",
+            "minAgeDays",
+            "maxAgeDays",
+            "skipAssigned",
+        ];
+        let Some(map) = self.object(value, path, &known) else {
+            return;
+        };
+        if let Some(value) = map.get("skipAssigned").filter(|value| !value.is_boolean()) {
+            self.wrong_type(&path.key("skipAssigned"), "a boolean", value);
+        }
+        let mut ages = [None, None];
+        for (key, age) in ["minAgeDays", "maxAgeDays"].into_iter().zip(&mut ages) {
+            let Some(value) = map.get(key) else {
+                continue;
+            };
+            match value.as_u64().filter(|&days| days <= u64::from(u32::MAX)) {
+                Some(days) => *age = Some(days),
+                None => {
+                    self.wrong_type(&path.key(key), "a non-negative whole number of days", value)
+                }
+            }
+        }
+        if let [Some(min), Some(max)] = ages {
+            if min > max {
+                let message = format!(
+                    "`minAgeDays` ({}) is greater than `maxAgeDays` ({})",
+                    min, max
+                );
+                self.push(&path.key("minAgeDays"), ValidationReason::Invalid, message);
+            }
+        }
+        for key in ["includeTitles", "excludeTitles"] {
+            let Some(value) = map.get(key) else {
+                continue;
+            };
+            let path = path.key(key);
+            let Some(patterns) = value.as_array() else {
+                self.wrong_type(&path, "an array of strings", value);
+                continue;
+            };
+            if patterns.len() > filters::MAX_TITLE_PATTERNS {
+                let message = format!(
+                    "expected at most {} patterns, found {}",
+                    filters::MAX_TITLE_PATTERNS,
+                    patterns.len()
+                );
+                self.push(&path, ValidationReason::TooMany, message);
+            }
+            for (i, pattern) in patterns.iter().enumerate() {
+                let path = path.index(i);
+                match pattern.as_str() {
+                    Some(pattern) => {
+                        if let Err(message) = filters::title_regex(pattern) {
+                            self.push(&path, ValidationReason::Invalid, message);
+                        }
+                    }
+                    None => self.wrong_type(&path, "a string", pattern),
+                }
+            }
+        }
+    }
+
+    fn project(&mut self, value: &Value) {
+        let root = FieldPath::default();
+        let known = [
+            "schemaVersion",
+            "name",
+            "slug",
+            "description",
+            "attributes",
+            "links",
+            "filters",
+        ];
+        let Some(project) = self.object(value, &root, &known) else {
+            return;
+        };
+
+        // The rest of the payload can't be checked against a schema that's
+        // unknown.
+        if let Some(value) = project.get("schemaVersion") {
+            let path = root.key("schemaVersion");
+            match value.as_u64() {
+                Some(version) if !SCHEMA_VERSIONS.contains(&version) => {
+                    let supported: Vec<String> =
+                        SCHEMA_VERSIONS.iter().map(u64::to_string).collect();
+                    let message = format!(
+                        "unsupported schema version {}, expected one of {}",
+                        version,
+                        supported.join(", ")
+                    );
+                    self.push(&path, ValidationReason::UnsupportedVersion, message);
+                    return;
+                }
+                Some(_) => {}
+                None => {
+                    self.wrong_type(&path, "a positive integer", value);
+                    return;
+                }
+            }
+        }
+
+        if let Some((value, path)) = self.required(project, &root, "name") {
+            self.non_blank(value, &path);
+        }
+        // Derived from the name when absent.
+        if let Some(value) = project.get("slug") {
+            let path = root.key("slug");
+            match value.as_str() {
+                Some(slug) => {
+                    if let Some(message) = slug::problem(slug) {
+                        self.push(&path, ValidationReason::Invalid, message);
+                    }
+                }
+                None => self.wrong_type(&path, "a string", value),
+            }
+        }
+        if let Some((value, path)) = self.optional(project, &root, "description") {
+            self.string(value, &path);
+        }
+
+        if let Some((value, path)) = self.required(project, &root, "attributes") {
+            let known = [
+                "networks",
+                "purposes",
+                "stackLevels",
+                "technologies",
+                "types",
+            ];
+            if let Some(attributes) = self.object(value, &path, &known) {
+                for key in ["purposes", "stackLevels", "technologies", "types"] {
+                    if let Some((value, path)) = self.required(attributes, &path, key) {
+                        self.string_array(value, &path);
+                    }
+                }
+                if let Some((value, path)) = self.optional(attributes, &path, "networks") {
+                    self.string_array(value, &path);
+                }
+            }
+        }
+
+        if let Some(value) = project.get("filters") {
+            self.filters(value, &root.key("filters"));
+        }
+
+        if let Some((value, path)) = self.required(project, &root, "links") {
+            let known = ["repository", "website", "docs", "explorer", "social"];
+            if let Some(links) = self.object(value, &path, &known) {
+                if let Some((value, path)) = self.required(links, &path, "repository") {
+                    self.repository_count(value, &path, config::get().max_repositories_per_project);
+                    let known = [
+                        "label",
+                        "url",
+                        "forge",
+                        "labelPrefix",
+                        "paths",
+                        "excludedIssueNumbers",
+                        "excludedIssueUrls",
+                    ];
+                    for (link, path) in self.links(value, &path, true, &known) {
+                        self.repository(link, &path);
+                    }
+                }
+                for key in ["website", "docs", "explorer", "social"] {
+                    if let Some((value, path)) = self.optional(links, &path, key) {
+                        self.links(value, &path, false, &["label", "url"]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses and validates the project payload, rejecting unknown fields when
+/// `strict` is set. Lenient parsing stays the default so older and newer
+/// payloads keep working.
+pub fn parse_project(json: &str, strict: bool) -> Result<Project, PayloadError> {
+    let value: Value = serde_json::from_str(json).map_err(PayloadError::Syntax)?;
+
+    let mut validator = PayloadValidator {
+        strict,
+        errors: Vec::new(),
+    };
+    validator.project(&value);
+    if !validator.errors.is_empty() {
+        return Err(PayloadError::Invalid(validator.errors));
+    }
+
+    let version = value
+        .get("schemaVersion")
+        .and_then(Value::as_u64)
+        .unwrap_or(1);
+    let project = deserialize(value, version, strict);
+    // The validator mirrors the schema, so this only trips if the two drift apart.
+    let mut project = project.map_err(|e| {
+        PayloadError::Invalid(vec![ValidationError {
+            field: String::new(),
+            pointer: String::new(),
+            reason: ValidationReason::WrongType,
+            message: e.to_string(),
+        }])
+    })?;
+    for repo in &mut project.links.repository {
+        repo.expand_shorthand();
+    }
+    remove_duplicates(&mut project);
+    Ok(project)
+}
+
+/// Deserializes a validated payload of the given schema version. Each
+/// version maps its schema onto [`Project`], so that payloads of older
+/// import scripts keep working once the schema changes.
+fn deserialize(value: Value, version: u64, strict: bool) -> Result<Project, serde_json::Error> {
+    match version {
+        1 if strict => serde_json::from_value::<StrictProject>(value).map(Project::from),
+        1 => serde_json::from_value::<Project>(value),
+        _ => Err(serde::de::Error::custom(format!(
+            "unsupported schema version {}",
+            version
+        ))),
+    }
+}
+
+/// Keeps the first of the repositories listed more than once, told apart by
+/// their canonical url, and warns about the others. Resumed imports parse the
+/// payload again, so the list must come out the same every time.
+fn remove_duplicates(project: &mut Project) {
+    // Canonical urls of the repositories kept, with their index.
+    let mut urls: Vec<(String, usize)> = Vec::new();
+    let mut index = 0;
+    let mut warnings = Vec::new();
+    project.links.repository.retain(|repo| {
+        let position = index;
+        index += 1;
+        let url = RepoInfo::from_repository(repo)
+            .map(|repo_info| repo_info.url())
+            .unwrap_or_else(|_| repo.url.trim().to_string());
+        match urls.iter().find(|(known, _)| *known == url) {
+            Some((_, first)) => {
+                let message = format!(
+                    "`links.repository[{}]` is listed already as `links.repository[{}]`, \
+                     it's imported once",
+                    position, first
+                );
+                warnings.push(ImportWarning::repository(
+                    &repo.url,
+                    WarningReason::DuplicateRepository,
+                    message,
+                ));
+                false
+            }
+            None => {
+                urls.push((url, position));
+                true
+            }
+        }
+    });
+    project.warnings.extend(warnings);
+}
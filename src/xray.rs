@@ -0,0 +1,94 @@
+//! AWS X-Ray subsegments of the calls to the importer's dependencies, so the
+//! Lambda's service map and traces show where an invocation's time goes,
+//! e.g. GitHub latency against Postgres writes.
+//!
+//! Subsegments are only recorded when the function has active tracing and
+//! the invocation is sampled. They're sent as UDP datagrams to the X-Ray
+//! daemon Lambda runs next to the function; a lost datagram only loses its
+//! subsegment.
+
+use std::env;
+use std::future::Future;
+use std::net::UdpSocket;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::breaker::Upstream;
+use crate::error::ImportError;
+
+/// Header prefixing every document sent to the daemon.
+const DAEMON_HEADER: &str = "{\"format\": \"json\", \"version\": 1}";
+
+/// A socket to the daemon, `None` when not running with active tracing.
+fn daemon() -> Option<&'static (UdpSocket, String)> {
+    static DAEMON: OnceLock<Option<(UdpSocket, String)>> = OnceLock::new();
+    DAEMON
+        .get_or_init(|| {
+            let address = env::var("AWS_XRAY_DAEMON_ADDRESS").ok()?;
+            let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+            socket.set_nonblocking(true).ok()?;
+            Some((socket, address))
+        })
+        .as_ref()
+}
+
+/// The invocation's trace and the segment subsegments belong to, if it's
+/// sampled. Lambda's runtime sets `_X_AMZN_TRACE_ID` for every invocation.
+fn trace() -> Option<(String, String)> {
+    let header = env::var("_X_AMZN_TRACE_ID").ok()?;
+    let (mut root, mut parent, mut sampled) = (None, None, false);
+    for part in header.split(';') {
+        match part.split_once('=') {
+            Some(("Root", value)) => root = Some(value.to_string()),
+            Some(("Parent", value)) => parent = Some(value.to_string()),
+            Some(("Sampled", value)) => sampled = value == "1",
+            _ => {}
+        }
+    }
+    sampled.then_some((root?, parent?))
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+/// Runs `call` to `upstream`, recorded as a subsegment named after it.
+/// Upstream failures are recorded as faults, other errors as errors.
+pub async fn subsegment<T>(
+    upstream: Upstream,
+    call: impl Future<Output = Result<T, ImportError>>,
+) -> Result<T, ImportError> {
+    let (Some((socket, address)), Some((trace_id, parent_id))) = (daemon(), trace()) else {
+        return call.await;
+    };
+
+    let start_time = now();
+    let result = call.await;
+    let failure = result.as_ref().err();
+
+    let mut document = json!({
+        "name": upstream.to_string(),
+        "id": &Uuid::new_v4().simple().to_string()[..16],
+        "trace_id": trace_id,
+        "parent_id": parent_id,
+        "type": "subsegment",
+        "namespace": "remote",
+        "start_time": start_time,
+        "end_time": now(),
+        "fault": failure.is_some_and(ImportError::is_upstream_failure),
+        "error": failure.is_some_and(|e| !e.is_upstream_failure()),
+    });
+    if upstream == Upstream::Postgres {
+        document["sql"] = json!({ "database_type": "PostgreSQL" });
+    }
+    let datagram = format!("{}\n{}", DAEMON_HEADER, document);
+    // Tracing is never worth failing a call over.
+    let _ = socket.send_to(datagram.as_bytes(), address.as_str());
+
+    result
+}